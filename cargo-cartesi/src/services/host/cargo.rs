@@ -68,7 +68,7 @@ impl Cargo for HostCargo {
             path = path.join("..");
         }
         if !path.exists() {
-            return Err(Box::try_from("Target not found.").unwrap());
+            return Err(Box::from("Target not found."));
         }
 
         Ok(path
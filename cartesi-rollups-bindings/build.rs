@@ -0,0 +1,79 @@
+//! Generates the kernel ABI bindings from the `rollup.h` UAPI header when the `bindgen` feature is enabled.
+//!
+//! The header defaults to the vendored `include/rollup.h`, `CARTESI_ROLLUP_HEADER` points at another one, e.g. the
+//! `include/uapi/linux/cartesi/rollup.h` of a kernel tree.
+
+fn main() {
+    #[cfg(feature = "bindgen")]
+    generate::bindings();
+}
+
+#[cfg(feature = "bindgen")]
+mod generate {
+    use bindgen::callbacks::{IntKind, ParseCallbacks};
+    use std::env;
+    use std::fmt::Write;
+    use std::fs;
+    use std::path::PathBuf;
+
+    /// Constants the crate uses as array lengths.
+    const SIZE_CONSTANTS: [&str; 1] = ["CARTESI_ROLLUP_ADDRESS_SIZE"];
+
+    #[derive(Debug)]
+    struct SizeConstants;
+
+    impl ParseCallbacks for SizeConstants {
+        fn int_macro(&self, name: &str, _value: i64) -> Option<IntKind> {
+            SIZE_CONSTANTS.contains(&name).then_some(IntKind::Custom {
+                name: "usize",
+                is_signed: false,
+            })
+        }
+    }
+
+    pub fn bindings() {
+        println!("cargo:rerun-if-env-changed=CARTESI_ROLLUP_HEADER");
+
+        let header = env::var("CARTESI_ROLLUP_HEADER").unwrap_or_else(|_| "include/rollup.h".to_owned());
+        let out = PathBuf::from(env::var("OUT_DIR").unwrap()).join("rollup.rs");
+
+        let mut bindings = bindgen::Builder::default()
+            .header(&header)
+            .allowlist_type("rollup_.*")
+            .allowlist_var("CARTESI_ROLLUP_.*")
+            .layout_tests(false)
+            .parse_callbacks(Box::new(SizeConstants))
+            .parse_callbacks(Box::new(bindgen::CargoCallbacks::new()))
+            .generate()
+            .unwrap_or_else(|error| panic!("cannot generate bindings from {}: {}", header, error))
+            .to_string();
+
+        // bindgen skips function-like macros, so the IOCTL numbers are read from the `_IOWR` definitions.
+        let source = fs::read_to_string(&header).unwrap();
+        let ioctls: Vec<_> = source.lines().filter_map(ioctl).collect();
+        let kind = ioctls.first().map(|(_, kind, _)| *kind).expect("no IOCTL definitions");
+
+        assert!(
+            ioctls.iter().all(|(_, other, _)| *other == kind),
+            "IOCTL definitions of different types"
+        );
+        writeln!(bindings, "pub(crate) const IOCTL_ROLLUP: u8 = {};", kind).unwrap();
+
+        for (name, _, nr) in ioctls {
+            writeln!(bindings, "pub(crate) const {}_NR: u8 = {};", name, nr).unwrap();
+        }
+
+        fs::write(out, bindings).unwrap();
+    }
+
+    /// Parses `#define IOCTL_ROLLUP_<NAME> _IOWR(<type>, <nr>, struct <name>)` into its name, type and number.
+    fn ioctl(line: &str) -> Option<(&str, &str, &str)> {
+        let line = line.strip_prefix("#define")?.trim_start();
+        let (name, definition) = line.split_once(char::is_whitespace)?;
+        let arguments = definition.trim().strip_prefix("_IOWR(")?.strip_suffix(')')?;
+        let mut arguments = arguments.split(',').map(str::trim);
+
+        name.starts_with("IOCTL_ROLLUP_")
+            .then_some((name, arguments.next()?, arguments.next()?))
+    }
+}
@@ -0,0 +1,87 @@
+/* SPDX-License-Identifier: GPL-2.0 WITH Linux-syscall-note */
+/*
+ * Vendored include/uapi/linux/cartesi/rollup.h of the Cartesi Linux kernel.
+ *
+ * The <linux/ioctl.h> and <linux/types.h> includes are swapped for the C standard headers, so the header parses on
+ * hosts without the kernel headers installed. Keep everything else in sync with the kernel.
+ */
+#ifndef _UAPI_LINUX_CARTESI_ROLLUP_H
+#define _UAPI_LINUX_CARTESI_ROLLUP_H
+
+#include <stdbool.h>
+#include <stdint.h>
+
+#define CARTESI_ROLLUP_ADVANCE_STATE 0
+#define CARTESI_ROLLUP_INSPECT_STATE 1
+
+#define CARTESI_ROLLUP_ADDRESS_SIZE 20
+
+struct rollup_bytes {
+    unsigned char *data;
+    uint64_t length;
+};
+
+struct rollup_input_metadata {
+    uint8_t msg_sender[CARTESI_ROLLUP_ADDRESS_SIZE];
+    uint64_t block_number;
+    uint64_t timestamp;
+    uint64_t epoch_index;
+    uint64_t input_index;
+};
+
+struct rollup_advance_state {
+    struct rollup_input_metadata metadata;
+    struct rollup_bytes payload;
+};
+
+struct rollup_inspect_state {
+    struct rollup_bytes payload;
+};
+
+struct rollup_finish {
+    bool accept_previous_request;
+    int next_request_type;
+    int next_request_payload_length;
+};
+
+struct rollup_voucher {
+    uint8_t destination[CARTESI_ROLLUP_ADDRESS_SIZE];
+    struct rollup_bytes payload;
+    uint64_t index;
+};
+
+struct rollup_notice {
+    struct rollup_bytes payload;
+    uint64_t index;
+};
+
+struct rollup_report {
+    struct rollup_bytes payload;
+};
+
+struct rollup_exception {
+    struct rollup_bytes payload;
+};
+
+/* Finishes the current request and waits for the next one */
+#define IOCTL_ROLLUP_FINISH _IOWR(0xd3, 0, struct rollup_finish)
+
+/* Reads the metadata and payload of the current advance state request */
+#define IOCTL_ROLLUP_READ_ADVANCE_STATE _IOWR(0xd3, 0, struct rollup_advance_state)
+
+/* Reads the payload of the current inspect state request */
+#define IOCTL_ROLLUP_READ_INSPECT_STATE _IOWR(0xd3, 0, struct rollup_inspect_state)
+
+/* Outputs a new voucher, returning its index */
+#define IOCTL_ROLLUP_WRITE_VOUCHER _IOWR(0xd3, 1, struct rollup_voucher)
+
+/* Outputs a new notice, returning its index */
+#define IOCTL_ROLLUP_WRITE_NOTICE _IOWR(0xd3, 2, struct rollup_notice)
+
+/* Outputs a new report */
+#define IOCTL_ROLLUP_WRITE_REPORT _IOWR(0xd3, 3, struct rollup_report)
+
+/* Throws an exception, the machine halts */
+#define IOCTL_ROLLUP_THROW_EXCEPTION _IOWR(0xd3, 4, struct rollup_exception)
+
+#endif
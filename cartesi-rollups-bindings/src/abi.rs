@@ -0,0 +1,205 @@
+//! Items in this module mirror the kernel ABI of the rollup device, declared in the `rollup.h` UAPI header, and of the
+//! cmio device of Cartesi Rollups v2, declared in `cmio.h`.
+//!
+//! The structs and constants of the rollup device are hand-copied from `include/rollup.h`, or generated from it by
+//! bindgen when the `bindgen` feature is enabled. The ones of the cmio device are hand-copied. Either way their layout
+//! is asserted at compile time against the one of the kernel, so a change of the ABI fails the build instead of
+//! corrupting memory.
+#![allow(non_camel_case_types)]
+
+#[cfg(feature = "bindgen")]
+include!(concat!(env!("OUT_DIR"), "/rollup.rs"));
+
+#[cfg(not(feature = "bindgen"))]
+pub use self::copied::*;
+
+pub type rollup_address = [u8; CARTESI_ROLLUP_ADDRESS_SIZE];
+
+#[cfg(not(feature = "bindgen"))]
+mod copied {
+    pub const CARTESI_ROLLUP_ADVANCE_STATE: u32 = 0;
+    pub const CARTESI_ROLLUP_INSPECT_STATE: u32 = 1;
+    pub const CARTESI_ROLLUP_ADDRESS_SIZE: usize = 20;
+
+    /// Type of every IOCTL request of the rollup device.
+    pub(crate) const IOCTL_ROLLUP: u8 = 0xd3;
+    pub(crate) const IOCTL_ROLLUP_FINISH_NR: u8 = 0;
+    pub(crate) const IOCTL_ROLLUP_READ_ADVANCE_STATE_NR: u8 = 0;
+    pub(crate) const IOCTL_ROLLUP_READ_INSPECT_STATE_NR: u8 = 0;
+    pub(crate) const IOCTL_ROLLUP_WRITE_VOUCHER_NR: u8 = 1;
+    pub(crate) const IOCTL_ROLLUP_WRITE_NOTICE_NR: u8 = 2;
+    pub(crate) const IOCTL_ROLLUP_WRITE_REPORT_NR: u8 = 3;
+    pub(crate) const IOCTL_ROLLUP_THROW_EXCEPTION_NR: u8 = 4;
+
+    #[repr(C)]
+    #[derive(Debug, Copy, Clone)]
+    pub struct rollup_finish {
+        pub accept_previous_request: bool,
+        pub next_request_type: std::os::raw::c_int,
+        pub next_request_payload_length: std::os::raw::c_int,
+    }
+
+    #[repr(C)]
+    #[derive(Debug, Copy, Clone)]
+    pub struct rollup_bytes {
+        pub data: *mut std::os::raw::c_uchar,
+        pub length: u64,
+    }
+
+    #[repr(C)]
+    #[derive(Debug, Copy, Clone)]
+    pub struct rollup_input_metadata {
+        pub msg_sender: [u8; CARTESI_ROLLUP_ADDRESS_SIZE],
+        pub block_number: u64,
+        pub timestamp: u64,
+        pub epoch_index: u64,
+        pub input_index: u64,
+    }
+
+    #[repr(C)]
+    #[derive(Debug, Copy, Clone)]
+    pub struct rollup_advance_state {
+        pub metadata: rollup_input_metadata,
+        pub payload: rollup_bytes,
+    }
+
+    #[repr(C)]
+    #[derive(Debug, Copy, Clone)]
+    pub struct rollup_inspect_state {
+        pub payload: rollup_bytes,
+    }
+
+    #[repr(C)]
+    #[derive(Debug, Copy, Clone)]
+    pub struct rollup_voucher {
+        pub destination: [u8; CARTESI_ROLLUP_ADDRESS_SIZE],
+        pub payload: rollup_bytes,
+        pub index: u64,
+    }
+
+    #[repr(C)]
+    #[derive(Debug, Copy, Clone)]
+    pub struct rollup_notice {
+        pub payload: rollup_bytes,
+        pub index: u64,
+    }
+
+    #[repr(C)]
+    #[derive(Debug, Copy, Clone)]
+    pub struct rollup_report {
+        pub payload: rollup_bytes,
+    }
+
+    #[repr(C)]
+    #[derive(Debug, Copy, Clone)]
+    pub struct rollup_exception {
+        pub payload: rollup_bytes,
+    }
+}
+
+/// Memory range shared with the host by the cmio device.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct cmio_buffer {
+    /// Physical address of the range.
+    pub data: u64,
+    pub length: u64,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct cmio_setup {
+    /// Buffer the machine writes its outputs to.
+    pub tx: cmio_buffer,
+    /// Buffer the host writes the requests to.
+    pub rx: cmio_buffer,
+}
+
+/// Asserts at compile time the size and alignment of a struct and the offset of each of its fields.
+macro_rules! assert_layout {
+    ($ty:ident { size: $size:expr, align: $align:expr, $($field:ident: $offset:expr),+ $(,)? }) => {
+        const _: () = {
+            assert!(std::mem::size_of::<$ty>() == $size, concat!("size of ", stringify!($ty)));
+            assert!(std::mem::align_of::<$ty>() == $align, concat!("alignment of ", stringify!($ty)));
+            $(assert!(
+                std::mem::offset_of!($ty, $field) == $offset,
+                concat!("offset of ", stringify!($ty), "::", stringify!($field))
+            );)+
+        };
+    };
+}
+
+// The layout of the kernel of the Cartesi machine, a 64-bit RISC-V one.
+#[cfg(target_pointer_width = "64")]
+mod layout {
+    use super::*;
+
+    assert_layout!(rollup_finish {
+        size: 12,
+        align: 4,
+        accept_previous_request: 0,
+        next_request_type: 4,
+        next_request_payload_length: 8,
+    });
+    assert_layout!(rollup_bytes {
+        size: 16,
+        align: 8,
+        data: 0,
+        length: 8,
+    });
+    assert_layout!(rollup_input_metadata {
+        size: 56,
+        align: 8,
+        msg_sender: 0,
+        block_number: 24,
+        timestamp: 32,
+        epoch_index: 40,
+        input_index: 48,
+    });
+    assert_layout!(rollup_advance_state {
+        size: 72,
+        align: 8,
+        metadata: 0,
+        payload: 56,
+    });
+    assert_layout!(rollup_inspect_state {
+        size: 16,
+        align: 8,
+        payload: 0,
+    });
+    assert_layout!(rollup_voucher {
+        size: 48,
+        align: 8,
+        destination: 0,
+        payload: 24,
+        index: 40,
+    });
+    assert_layout!(rollup_notice {
+        size: 24,
+        align: 8,
+        payload: 0,
+        index: 16,
+    });
+    assert_layout!(rollup_report {
+        size: 16,
+        align: 8,
+        payload: 0,
+    });
+    assert_layout!(rollup_exception {
+        size: 16,
+        align: 8,
+        payload: 0,
+    });
+    assert_layout!(cmio_buffer {
+        size: 16,
+        align: 8,
+        data: 0,
+        length: 8,
+    });
+    assert_layout!(cmio_setup {
+        size: 32,
+        align: 8,
+        tx: 0,
+        rx: 16,
+    });
+}
@@ -1,92 +1,46 @@
 //! Items in this module communicate with the rollup device using IOCTL functions.
 //!
 //! All the IOCTL wiring is implemented in this crate and exposed as an abstraction over the native functions.
+//!
+//! Requests are read into a [`RollupBytes`] the caller keeps across requests, outputs are written straight from the
+//! borrowed payload.
+//!
+//! Every function reports failures as the [`Errno`] returned by the device so callers can tell them apart.
+use crate::abi::*;
+use crate::RollupBytes;
+use nix::errno::Errno;
 use nix::ioctl_readwrite;
-use nix::libc::{c_int, realloc, size_t};
-use std::error::Error;
-use std::os::raw::c_void;
-
-pub const CARTESI_ROLLUP_ADVANCE_STATE: u32 = 0;
-pub const CARTESI_ROLLUP_INSPECT_STATE: u32 = 1;
-pub const CARTESI_ROLLUP_ADDRESS_SIZE: usize = 20;
-
-#[allow(non_camel_case_types)]
-pub type rollup_address = [u8; CARTESI_ROLLUP_ADDRESS_SIZE];
-
-#[repr(C)]
-#[derive(Debug, Copy, Clone)]
-pub struct rollup_finish {
-    pub accept_previous_request: bool,
-    pub next_request_type: std::os::raw::c_int,
-    pub next_request_payload_length: std::os::raw::c_int,
-}
-
-#[repr(C)]
-#[derive(Debug, Copy, Clone)]
-pub struct rollup_bytes {
-    pub data: *mut std::os::raw::c_uchar,
-    pub length: u64,
-}
-
-#[repr(C)]
-#[derive(Debug, Copy, Clone)]
-pub struct rollup_input_metadata {
-    pub msg_sender: rollup_address,
-    pub block_number: u64,
-    pub timestamp: u64,
-    pub epoch_index: u64,
-    pub input_index: u64,
-}
-
-#[repr(C)]
-#[derive(Debug, Copy, Clone)]
-pub struct rollup_advance_state {
-    pub metadata: rollup_input_metadata,
-    pub payload: rollup_bytes,
-}
-
-#[repr(C)]
-#[derive(Debug, Copy, Clone)]
-pub struct rollup_inspect_state {
-    pub payload: rollup_bytes,
-}
-
-#[repr(C)]
-#[derive(Debug, Copy, Clone)]
-pub struct rollup_voucher {
-    pub destination: rollup_address,
-    pub payload: rollup_bytes,
-    pub index: u64,
-}
-
-#[repr(C)]
-#[derive(Debug, Copy, Clone)]
-pub struct rollup_notice {
-    pub payload: rollup_bytes,
-    pub index: u64,
-}
-
-#[repr(C)]
-#[derive(Debug, Copy, Clone)]
-pub struct rollup_report {
-    pub payload: rollup_bytes,
-}
-
-#[repr(C)]
-#[derive(Debug, Copy, Clone)]
-pub struct rollup_exception {
-    pub payload: rollup_bytes,
-}
-
-ioctl_readwrite!(finish_request, 0xd3, 0, rollup_finish);
-ioctl_readwrite!(read_advance_state_request, 0xd3, 0, rollup_advance_state);
-ioctl_readwrite!(read_inspect_state_request, 0xd3, 0, rollup_inspect_state);
-ioctl_readwrite!(write_voucher, 0xd3, 1, rollup_voucher);
-ioctl_readwrite!(write_notice, 0xd3, 2, rollup_notice);
-ioctl_readwrite!(write_report, 0xd3, 3, rollup_report);
-ioctl_readwrite!(throw_exception, 0xd3, 4, rollup_exception);
-
-pub fn rollup_finish_request(fd: c_int, accept: bool) -> Result<rollup_finish, Box<dyn Error>> {
+use nix::libc::c_int;
+
+ioctl_readwrite!(finish_request, IOCTL_ROLLUP, IOCTL_ROLLUP_FINISH_NR, rollup_finish);
+ioctl_readwrite!(
+    read_advance_state_request,
+    IOCTL_ROLLUP,
+    IOCTL_ROLLUP_READ_ADVANCE_STATE_NR,
+    rollup_advance_state
+);
+ioctl_readwrite!(
+    read_inspect_state_request,
+    IOCTL_ROLLUP,
+    IOCTL_ROLLUP_READ_INSPECT_STATE_NR,
+    rollup_inspect_state
+);
+ioctl_readwrite!(
+    write_voucher,
+    IOCTL_ROLLUP,
+    IOCTL_ROLLUP_WRITE_VOUCHER_NR,
+    rollup_voucher
+);
+ioctl_readwrite!(write_notice, IOCTL_ROLLUP, IOCTL_ROLLUP_WRITE_NOTICE_NR, rollup_notice);
+ioctl_readwrite!(write_report, IOCTL_ROLLUP, IOCTL_ROLLUP_WRITE_REPORT_NR, rollup_report);
+ioctl_readwrite!(
+    throw_exception,
+    IOCTL_ROLLUP,
+    IOCTL_ROLLUP_THROW_EXCEPTION_NR,
+    rollup_exception
+);
+
+pub fn rollup_finish_request(fd: c_int, accept: bool) -> nix::Result<rollup_finish> {
     let mut data = rollup_finish {
         accept_previous_request: accept,
         next_request_type: 0,
@@ -103,116 +57,111 @@ pub fn rollup_finish_request(fd: c_int, accept: bool) -> Result<rollup_finish, B
 pub fn rollup_read_advance_state_request(
     fd: c_int,
     finish: &rollup_finish,
-    payload: &mut Box<rollup_bytes>,
-) -> Result<rollup_input_metadata, Box<dyn Error>> {
-    let metadata = rollup_input_metadata {
-        msg_sender: [0; CARTESI_ROLLUP_ADDRESS_SIZE],
-        block_number: 0,
-        timestamp: 0,
-        epoch_index: 0,
-        input_index: 0,
+    payload: &mut RollupBytes,
+) -> nix::Result<rollup_input_metadata> {
+    let length = payload_length(finish)?;
+    payload.reserve(length)?;
+
+    let mut data = rollup_advance_state {
+        metadata: rollup_input_metadata {
+            msg_sender: [0; CARTESI_ROLLUP_ADDRESS_SIZE],
+            block_number: 0,
+            timestamp: 0,
+            epoch_index: 0,
+            input_index: 0,
+        },
+        payload: payload.as_raw(),
     };
 
     unsafe {
-        resize_bytes(payload.as_mut(), finish.next_request_payload_length)?;
-
-        let mut data = rollup_advance_state {
-            metadata,
-            payload: *payload.as_mut(),
-        };
-
         read_advance_state_request(fd, &mut data)?;
     }
+    payload.set_length(length);
 
-    Ok(metadata)
+    Ok(data.metadata)
 }
 
 pub fn rollup_read_inspect_state_request(
     fd: c_int,
     finish: &rollup_finish,
-    payload: &mut Box<rollup_bytes>,
-) -> Result<(), Box<dyn Error>> {
-    unsafe {
-        resize_bytes(payload.as_mut(), finish.next_request_payload_length)?;
+    payload: &mut RollupBytes,
+) -> nix::Result<()> {
+    let length = payload_length(finish)?;
+    payload.reserve(length)?;
 
-        let mut data = rollup_inspect_state {
-            payload: *payload.as_mut(),
-        };
+    let mut data = rollup_inspect_state {
+        payload: payload.as_raw(),
+    };
 
+    unsafe {
         read_inspect_state_request(fd, &mut data)?;
     }
+    payload.set_length(length);
 
     Ok(())
 }
 
-pub fn rollup_write_voucher(
-    fd: c_int,
-    destination: rollup_address,
-    payload: &mut Box<rollup_bytes>,
-) -> Result<u64, Box<dyn Error>> {
-    unsafe {
-        let mut data = rollup_voucher {
-            destination,
-            payload: *payload.as_mut(),
-            index: 0,
-        };
+pub fn rollup_write_voucher(fd: c_int, destination: rollup_address, payload: &[u8]) -> nix::Result<u64> {
+    let mut data = rollup_voucher {
+        destination,
+        payload: output_bytes(payload),
+        index: 0,
+    };
 
+    unsafe {
         write_voucher(fd, &mut data)?;
-
-        Ok(data.index)
     }
+
+    Ok(data.index)
 }
 
-pub fn rollup_write_notice(fd: c_int, payload: &mut Box<rollup_bytes>) -> Result<u64, Box<dyn Error>> {
-    unsafe {
-        let mut data = rollup_notice {
-            payload: *payload.as_mut(),
-            index: 0,
-        };
+pub fn rollup_write_notice(fd: c_int, payload: &[u8]) -> nix::Result<u64> {
+    let mut data = rollup_notice {
+        payload: output_bytes(payload),
+        index: 0,
+    };
 
+    unsafe {
         write_notice(fd, &mut data)?;
-
-        Ok(data.index)
     }
+
+    Ok(data.index)
 }
 
-pub fn rollup_write_report(fd: c_int, payload: &mut Box<rollup_bytes>) -> Result<(), Box<dyn Error>> {
-    unsafe {
-        let mut data = rollup_report {
-            payload: *payload.as_mut(),
-        };
+pub fn rollup_write_report(fd: c_int, payload: &[u8]) -> nix::Result<()> {
+    let mut data = rollup_report {
+        payload: output_bytes(payload),
+    };
 
+    unsafe {
         write_report(fd, &mut data)?;
     }
 
     Ok(())
 }
 
-pub fn rollup_throw_exception(fd: c_int, payload: &mut Box<rollup_bytes>) -> Result<(), Box<dyn Error>> {
-    unsafe {
-        let mut data = rollup_exception {
-            payload: *payload.as_mut(),
-        };
+pub fn rollup_throw_exception(fd: c_int, payload: &[u8]) -> nix::Result<()> {
+    let mut data = rollup_exception {
+        payload: output_bytes(payload),
+    };
 
+    unsafe {
         throw_exception(fd, &mut data)?;
     }
 
     Ok(())
 }
 
-unsafe fn resize_bytes(bytes: *mut rollup_bytes, size: c_int) -> Result<(), Box<dyn Error>> {
-    if (*bytes).length >= size.try_into()? {
-        return Ok(());
-    }
-
-    let new_data = realloc((*bytes).data as *mut c_void, size as size_t) as *mut u8;
+fn payload_length(finish: &rollup_finish) -> nix::Result<usize> {
+    finish.next_request_payload_length.try_into().map_err(|_| Errno::EINVAL)
+}
 
-    if new_data.is_null() {
-        return Err("Failed growing payload buffer".into());
+/// Points a [`rollup_bytes`] at `payload` so the device reads it in place.
+///
+/// The device never writes through the pointer of an output request, so handing out the shared slice is sound.
+fn output_bytes(payload: &[u8]) -> rollup_bytes {
+    rollup_bytes {
+        data: payload.as_ptr() as *mut std::os::raw::c_uchar,
+        length: payload.len() as u64,
     }
-
-    (*bytes).length = size.try_into()?;
-    (*bytes).data = new_data;
-
-    Ok(())
 }
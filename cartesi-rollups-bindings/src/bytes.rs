@@ -0,0 +1,131 @@
+use crate::rollup_bytes;
+use nix::errno::Errno;
+use nix::libc::{free, realloc, size_t};
+use std::ops::Deref;
+use std::os::raw::c_void;
+use std::ptr;
+
+/// Owned buffer the rollup device writes the payload of a request into.
+///
+/// The buffer only grows, so reusing one across requests allocates only when a payload is larger than every payload
+/// before it. The memory is freed on drop.
+#[derive(Debug)]
+pub struct RollupBytes {
+    data: *mut u8,
+    capacity: usize,
+    /// Number of bytes of the last payload read into the buffer.
+    length: usize,
+}
+
+// The buffer is exclusively owned, like the one of a `Vec<u8>`.
+unsafe impl Send for RollupBytes {}
+unsafe impl Sync for RollupBytes {}
+
+impl RollupBytes {
+    pub const fn new() -> Self {
+        Self {
+            data: ptr::null_mut(),
+            capacity: 0,
+            length: 0,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Grows the buffer to hold at least `capacity` bytes, keeping its contents.
+    pub fn reserve(&mut self, capacity: usize) -> nix::Result<()> {
+        if self.capacity >= capacity {
+            return Ok(());
+        }
+
+        let data = unsafe { realloc(self.data as *mut c_void, capacity as size_t) } as *mut u8;
+
+        if data.is_null() {
+            return Err(Errno::ENOMEM);
+        }
+
+        self.data = data;
+        self.capacity = capacity;
+
+        Ok(())
+    }
+
+    /// Replaces the payload with a copy of `payload`, the way the device writes a request into the buffer.
+    ///
+    /// Lets a simulated device stand in for the real one.
+    pub fn copy_from_slice(&mut self, payload: &[u8]) -> nix::Result<()> {
+        self.reserve(payload.len())?;
+
+        if !payload.is_empty() {
+            unsafe { ptr::copy_nonoverlapping(payload.as_ptr(), self.data, payload.len()) };
+        }
+        self.set_length(payload.len());
+
+        Ok(())
+    }
+
+    /// Returns the descriptor handing the whole buffer to the device.
+    pub(crate) fn as_raw(&mut self) -> rollup_bytes {
+        rollup_bytes {
+            data: self.data,
+            length: self.capacity as u64,
+        }
+    }
+
+    /// Marks the first `length` bytes, which the device just wrote, as the payload.
+    pub(crate) fn set_length(&mut self, length: usize) {
+        self.length = length.min(self.capacity);
+    }
+}
+
+impl Default for RollupBytes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for RollupBytes {
+    type Target = [u8];
+
+    /// Returns the payload of the last request read into the buffer.
+    fn deref(&self) -> &[u8] {
+        match self.length {
+            0 => &[],
+            length => unsafe { std::slice::from_raw_parts(self.data, length) },
+        }
+    }
+}
+
+impl Drop for RollupBytes {
+    fn drop(&mut self) {
+        unsafe { free(self.data as *mut c_void) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buffer_only_grows() {
+        let mut bytes = RollupBytes::new();
+
+        bytes.copy_from_slice(&[1, 2, 3, 4]).unwrap();
+        bytes.reserve(2).unwrap();
+
+        assert_eq!(4, bytes.capacity());
+        assert_eq!([1, 2, 3, 4], *bytes);
+
+        bytes.reserve(1024).unwrap();
+
+        assert_eq!(1024, bytes.capacity());
+        assert_eq!([1, 2, 3, 4], *bytes);
+
+        bytes.set_length(0);
+
+        assert!(bytes.is_empty());
+        assert!(RollupBytes::new().is_empty());
+    }
+}
@@ -0,0 +1,168 @@
+//! Items in this module communicate with the cmio device of Cartesi Rollups v2 machines, the device libcmt drives.
+//!
+//! The device shares a transmit and a receive buffer with the host. The machine maps both with [`CmioMapping`], writes
+//! its outputs to the transmit one and hands control over to the host with [`cmio_yield`], which replies with the
+//! next request in the receive one.
+use crate::{cmio_buffer, cmio_setup};
+use nix::errno::Errno;
+use nix::libc::c_int;
+use nix::sys::mman::{mmap, munmap, MapFlags, ProtFlags};
+use nix::{ioctl_read, ioctl_readwrite};
+use std::num::NonZeroUsize;
+use std::os::raw::c_void;
+
+pub const HTIF_DEVICE_YIELD: u8 = 2;
+
+pub const HTIF_YIELD_CMD_AUTOMATIC: u8 = 0;
+pub const HTIF_YIELD_CMD_MANUAL: u8 = 1;
+
+pub const HTIF_YIELD_AUTOMATIC_REASON_PROGRESS: u16 = 1;
+pub const HTIF_YIELD_AUTOMATIC_REASON_TX_OUTPUT: u16 = 2;
+pub const HTIF_YIELD_AUTOMATIC_REASON_TX_REPORT: u16 = 4;
+
+pub const HTIF_YIELD_MANUAL_REASON_RX_ACCEPTED: u16 = 1;
+pub const HTIF_YIELD_MANUAL_REASON_RX_REJECTED: u16 = 2;
+pub const HTIF_YIELD_MANUAL_REASON_TX_EXCEPTION: u16 = 4;
+
+/// Reason of the reply of the host to a manual yield carrying an advance state request.
+pub const HTIF_YIELD_REASON_ADVANCE: u16 = 0;
+/// Reason of the reply of the host to a manual yield carrying an inspect state request.
+pub const HTIF_YIELD_REASON_INSPECT: u16 = 1;
+
+ioctl_read!(setup_request, 0xd3, 0, cmio_setup);
+ioctl_readwrite!(yield_request, 0xd3, 1, u64);
+
+/// Returns the location of the buffers shared with the host.
+pub fn cmio_get_setup(fd: c_int) -> nix::Result<cmio_setup> {
+    let mut data = cmio_setup {
+        tx: cmio_buffer { data: 0, length: 0 },
+        rx: cmio_buffer { data: 0, length: 0 },
+    };
+
+    unsafe {
+        setup_request(fd, &mut data)?;
+    }
+
+    Ok(data)
+}
+
+/// Yields to the host with `request`, packed by [`cmio_yield_pack`], returning the packed reply.
+pub fn cmio_yield(fd: c_int, request: u64) -> nix::Result<u64> {
+    let mut data = request;
+
+    unsafe {
+        yield_request(fd, &mut data)?;
+    }
+
+    Ok(data)
+}
+
+/// Packs the fields of a yield the way the device expects them.
+pub fn cmio_yield_pack(device: u8, command: u8, reason: u16, data: u32) -> u64 {
+    (u64::from(device) << 56) | (u64::from(command) << 48) | (u64::from(reason) << 32) | u64::from(data)
+}
+
+/// Unpacks a yield into its device, command, reason and data.
+pub fn cmio_yield_unpack(packed: u64) -> (u8, u8, u16, u32) {
+    (
+        (packed >> 56) as u8,
+        (packed >> 48) as u8,
+        (packed >> 32) as u16,
+        packed as u32,
+    )
+}
+
+/// Buffers of the cmio device mapped into the process.
+///
+/// The mappings are removed on drop.
+#[derive(Debug)]
+pub struct CmioMapping {
+    tx: *mut u8,
+    tx_length: usize,
+    rx: *const u8,
+    rx_length: usize,
+}
+
+// The mappings are exclusively owned, like the buffer of a `Vec<u8>`.
+unsafe impl Send for CmioMapping {}
+unsafe impl Sync for CmioMapping {}
+
+impl CmioMapping {
+    /// Maps the buffers described by `setup` of the device open at `fd`.
+    pub fn new(fd: c_int, setup: &cmio_setup) -> nix::Result<Self> {
+        let tx = map(fd, &setup.tx, ProtFlags::PROT_READ | ProtFlags::PROT_WRITE)?;
+        let rx = map(fd, &setup.rx, ProtFlags::PROT_READ).inspect_err(|_| unsafe {
+            let _ = munmap(tx as *mut c_void, setup.tx.length as usize);
+        })?;
+
+        Ok(Self {
+            tx,
+            tx_length: setup.tx.length as usize,
+            rx,
+            rx_length: setup.rx.length as usize,
+        })
+    }
+
+    /// Copies `data` to the start of the transmit buffer, failing with [`Errno::ENOBUFS`] if it does not fit.
+    pub fn write_tx(&mut self, data: &[u8]) -> nix::Result<()> {
+        if data.len() > self.tx_length {
+            return Err(Errno::ENOBUFS);
+        }
+
+        unsafe { std::ptr::copy_nonoverlapping(data.as_ptr(), self.tx, data.len()) };
+
+        Ok(())
+    }
+
+    /// Returns the first `length` bytes of the receive buffer, failing with [`Errno::ENOBUFS`] if it is shorter.
+    pub fn rx(&self, length: usize) -> nix::Result<&[u8]> {
+        match length {
+            0 => Ok(&[]),
+            length if length <= self.rx_length => Ok(unsafe { std::slice::from_raw_parts(self.rx, length) }),
+            _ => Err(Errno::ENOBUFS),
+        }
+    }
+}
+
+impl Drop for CmioMapping {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = munmap(self.tx as *mut c_void, self.tx_length);
+            let _ = munmap(self.rx as *mut c_void, self.rx_length);
+        }
+    }
+}
+
+/// Maps `buffer` at the address the device placed it.
+fn map(fd: c_int, buffer: &cmio_buffer, protection: ProtFlags) -> nix::Result<*mut u8> {
+    let length = NonZeroUsize::new(buffer.length as usize).ok_or(Errno::EINVAL)?;
+    let address = NonZeroUsize::new(buffer.data as usize);
+
+    unsafe { mmap(address, length, protection, MapFlags::MAP_SHARED, fd, 0) }.map(|data| data as *mut u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_yield_fields_are_packed() {
+        let packed = cmio_yield_pack(
+            HTIF_DEVICE_YIELD,
+            HTIF_YIELD_CMD_AUTOMATIC,
+            HTIF_YIELD_AUTOMATIC_REASON_TX_OUTPUT,
+            0xdead,
+        );
+
+        assert_eq!(0x0200_0002_0000_dead, packed);
+        assert_eq!(
+            (
+                HTIF_DEVICE_YIELD,
+                HTIF_YIELD_CMD_AUTOMATIC,
+                HTIF_YIELD_AUTOMATIC_REASON_TX_OUTPUT,
+                0xdead
+            ),
+            cmio_yield_unpack(packed)
+        );
+    }
+}
@@ -1,3 +1,9 @@
+mod abi;
 mod bindings;
+mod bytes;
+mod cmio;
 
+pub use abi::*;
 pub use bindings::*;
+pub use bytes::*;
+pub use cmio::*;
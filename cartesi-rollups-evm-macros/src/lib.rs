@@ -0,0 +1,146 @@
+//! Procedural macros of `cartesi-rollups-evm-utils`, re-exported from its `abi` module by the `derive` feature.
+//!
+//! The derived implementations refer to the traits as `::cartesi_rollups_evm_utils::abi`, so the crate using them has
+//! to depend on `cartesi-rollups-evm-utils` directly.
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use sha3::{Digest, Keccak256};
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, LitStr};
+
+/// Computes the 4-byte selector of a function signature at compile time.
+///
+/// Expands to a `[u8; 4]` literal, so it can initialize constants. The signature is hashed as written, so it has to be
+/// canonical, e.g. `transfer(address,uint256)`.
+///
+/// ```
+/// # use cartesi_rollups_evm_macros::selector;
+/// const TRANSFER: [u8; 4] = selector!("transfer(address,uint256)");
+///
+/// assert_eq!([0xa9, 0x05, 0x9c, 0xbb], TRANSFER);
+/// ```
+#[proc_macro]
+pub fn selector(input: TokenStream) -> TokenStream {
+    let signature = parse_macro_input!(input as LitStr);
+    let value = signature.value();
+
+    if value.contains(char::is_whitespace) {
+        return Error::new(signature.span(), "function signature must not contain whitespace")
+            .to_compile_error()
+            .into();
+    }
+
+    let hash = Keccak256::digest(value.as_bytes());
+    let bytes = &hash[..4];
+
+    quote!([#(#bytes),*]).into()
+}
+
+/// Derives `AbiEncode` for a struct, encoding it as the tuple of its fields in declaration order.
+#[proc_macro_derive(AbiEncode)]
+pub fn derive_abi_encode(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand_abi_encode(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Derives `AbiDecode` for a struct, decoding it from the tuple of its fields in declaration order.
+#[proc_macro_derive(AbiDecode)]
+pub fn derive_abi_decode(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand_abi_decode(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn struct_fields(input: &DeriveInput) -> Result<&Fields, Error> {
+    match &input.data {
+        Data::Struct(data) => Ok(&data.fields),
+        _ => Err(Error::new(input.span(), "ABI derives support only structs")),
+    }
+}
+
+/// Returns the expressions accessing each field of `self`.
+fn field_accessors(fields: &Fields) -> Vec<TokenStream2> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| match &field.ident {
+            Some(ident) => quote!(#ident),
+            None => {
+                let index = syn::Index::from(index);
+                quote!(#index)
+            }
+        })
+        .collect()
+}
+
+fn expand_abi_encode(input: &DeriveInput) -> Result<TokenStream2, Error> {
+    let fields = struct_fields(input)?;
+    let accessors = field_accessors(fields);
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::cartesi_rollups_evm_utils::abi::AbiEncode for #name #type_generics #where_clause {
+            fn to_token(&self) -> ::cartesi_rollups_evm_utils::abi::Token {
+                ::cartesi_rollups_evm_utils::abi::Token::Tuple(::std::vec![
+                    #(::cartesi_rollups_evm_utils::abi::AbiEncode::to_token(&self.#accessors)),*
+                ])
+            }
+        }
+    })
+}
+
+fn expand_abi_decode(input: &DeriveInput) -> Result<TokenStream2, Error> {
+    let fields = struct_fields(input)?;
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    let types: Vec<_> = fields.iter().map(|field| &field.ty).collect();
+    let count = types.len();
+    let variables: Vec<_> = (0..count).map(|index| format_ident!("field{}", index)).collect();
+    let construct = match fields {
+        Fields::Named(_) => {
+            let idents = fields.iter().map(|field| &field.ident);
+            quote!(Self { #(#idents: #variables),* })
+        }
+        Fields::Unnamed(_) => quote!(Self(#(#variables),*)),
+        Fields::Unit => quote!(Self),
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::cartesi_rollups_evm_utils::abi::AbiDecode for #name #type_generics #where_clause {
+            fn param_type() -> ::cartesi_rollups_evm_utils::abi::ParamType {
+                ::cartesi_rollups_evm_utils::abi::ParamType::Tuple(::std::vec![
+                    #(<#types as ::cartesi_rollups_evm_utils::abi::AbiDecode>::param_type()),*
+                ])
+            }
+
+            fn from_token(
+                token: ::cartesi_rollups_evm_utils::abi::Token,
+            ) -> ::std::result::Result<Self, ::cartesi_rollups_evm_utils::abi::AbiError> {
+                let tokens = token
+                    .into_tuple()
+                    .filter(|tokens| tokens.len() == #count)
+                    .ok_or_else(|| {
+                        ::cartesi_rollups_evm_utils::abi::AbiError::TypeMismatch(
+                            <Self as ::cartesi_rollups_evm_utils::abi::AbiDecode>::param_type().to_string(),
+                        )
+                    })?;
+                #[allow(unused_mut, unused_variables)]
+                let mut tokens = tokens.into_iter();
+                #(
+                    let #variables = <#types as ::cartesi_rollups_evm_utils::abi::AbiDecode>::from_token(
+                        tokens.next().unwrap(),
+                    )?;
+                )*
+
+                ::std::result::Result::Ok(#construct)
+            }
+        }
+    })
+}
@@ -0,0 +1,55 @@
+use cartesi_rollups_evm_utils::abi::{self, selector, AbiDecode, AbiEncode, Bytes, FixedBytes, ParamType, Token};
+use cartesi_rollups_evm_utils::{Address, U256};
+
+const TRANSFER: [u8; 4] = selector!("transfer(address,uint256)");
+
+#[derive(AbiEncode, AbiDecode, Debug, PartialEq)]
+struct Transfer {
+    to: Address,
+    amount: U256,
+    memo: String,
+}
+
+#[derive(AbiEncode, AbiDecode, Debug, PartialEq)]
+struct Batch(Vec<Transfer>, Bytes, FixedBytes<4>, i64, [bool; 2]);
+
+#[test]
+fn test_derived_structs_round_trip() {
+    let transfer = Transfer {
+        to: Address::new([0x11; 20]),
+        amount: U256::from(100u8),
+        memo: "rent".to_owned(),
+    };
+    let batch = Batch(
+        vec![transfer],
+        Bytes(b"data".to_vec()),
+        FixedBytes(TRANSFER),
+        -1,
+        [true, false],
+    );
+
+    let expected = abi::encode(&[
+        Token::Array(vec![Token::Tuple(vec![
+            Token::Address(Address::new([0x11; 20])),
+            Token::Uint(U256::from(100u8)),
+            Token::String("rent".to_owned()),
+        ])]),
+        Token::Bytes(b"data".to_vec()),
+        Token::FixedBytes(TRANSFER.to_vec()),
+        Token::int(-1),
+        Token::FixedArray(vec![Token::Bool(true), Token::Bool(false)]),
+    ]);
+
+    assert_eq!(abi::selector("transfer(address,uint256)"), TRANSFER);
+    assert_eq!(
+        "((address,uint256,string)[],bytes,bytes4,int64,bool[2])",
+        Batch::param_type().to_string()
+    );
+    assert_eq!(expected, batch.abi_encode());
+    assert_eq!(Ok(batch), Batch::abi_decode(&expected));
+    assert!(matches!(
+        Transfer::from_token(Token::Tuple(vec![])),
+        Err(abi::AbiError::TypeMismatch(_))
+    ));
+    assert_eq!(ParamType::Uint(8), u8::param_type());
+}
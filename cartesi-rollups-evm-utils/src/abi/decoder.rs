@@ -0,0 +1,154 @@
+use crate::abi::token::fits_signed;
+use crate::abi::{AbiError, ParamType, Token};
+use crate::{Address, U256};
+
+/// Decodes `data` holding the members of a tuple of `types`, which is also how function arguments are encoded.
+///
+/// The decoding is strict: padding has to be zeroed and values have to fit their types, like the Solidity decoder
+/// requires. Offsets may point anywhere in `data`, yet the tokens may not decode more words and bytes than `data`
+/// holds, so untrusted data whose offsets alias the same tail cannot blow up the decoded size.
+pub fn decode(types: &[ParamType], data: &[u8]) -> Result<Vec<Token>, AbiError> {
+    let mut budget = data.len();
+
+    decode_tuple(types, data, 0, &mut budget)
+}
+
+/// Takes `size` bytes off the `budget` left for decoding.
+fn charge(budget: &mut usize, size: usize) -> Result<(), AbiError> {
+    *budget = budget
+        .checked_sub(size)
+        .ok_or(AbiError::InvalidData("data decodes to more than its length"))?;
+
+    Ok(())
+}
+
+fn decode_tuple(types: &[ParamType], data: &[u8], base: usize, budget: &mut usize) -> Result<Vec<Token>, AbiError> {
+    let mut position = base;
+
+    types
+        .iter()
+        .map(|param_type| {
+            let token = match param_type.is_dynamic() {
+                true => {
+                    let offset = read_usize(data, position, budget)?;
+                    let start = base
+                        .checked_add(offset)
+                        .ok_or(AbiError::InvalidData("offset out of bounds"))?;
+
+                    decode_token(param_type, data, start, budget)?
+                }
+                false => decode_token(param_type, data, position, budget)?,
+            };
+            position += param_type.head_size();
+
+            Ok(token)
+        })
+        .collect()
+}
+
+fn decode_token(param_type: &ParamType, data: &[u8], position: usize, budget: &mut usize) -> Result<Token, AbiError> {
+    let token = match param_type {
+        ParamType::Address => {
+            let word = read_word(data, position, budget)?;
+
+            if word[..12].iter().any(|&byte| byte != 0) {
+                return Err(AbiError::InvalidData("dirty address padding"));
+            }
+
+            Token::Address(Address::try_from(&word[12..]).unwrap())
+        }
+        ParamType::Bool => match U256::from_be_bytes(read_word(data, position, budget)?) {
+            value if value.is_zero() => Token::Bool(false),
+            value if value == U256::ONE => Token::Bool(true),
+            _ => return Err(AbiError::InvalidData("invalid bool")),
+        },
+        ParamType::Uint(bits) => {
+            let value = U256::from_be_bytes(read_word(data, position, budget)?);
+
+            if value.bits() as usize > *bits {
+                return Err(AbiError::InvalidData("uint out of range"));
+            }
+
+            Token::Uint(value)
+        }
+        ParamType::Int(bits) => {
+            let value = U256::from_be_bytes(read_word(data, position, budget)?);
+
+            if !fits_signed(&value, *bits) {
+                return Err(AbiError::InvalidData("int out of range"));
+            }
+
+            Token::Int(value)
+        }
+        ParamType::FixedBytes(length) => {
+            let word = read_word(data, position, budget)?;
+
+            if word[*length..].iter().any(|&byte| byte != 0) {
+                return Err(AbiError::InvalidData("dirty bytes padding"));
+            }
+
+            Token::FixedBytes(word[..*length].to_vec())
+        }
+        ParamType::Bytes => Token::Bytes(read_bytes(data, position, budget)?.to_vec()),
+        ParamType::String => {
+            let bytes = read_bytes(data, position, budget)?;
+
+            Token::String(String::from_utf8(bytes.to_vec()).map_err(|_| AbiError::InvalidData("invalid utf-8"))?)
+        }
+        ParamType::Array(inner) => {
+            let length = read_usize(data, position, budget)?;
+            let element_size = inner.head_size();
+
+            if length.saturating_mul(element_size.max(1)) > *budget {
+                return Err(AbiError::InvalidData("array length out of bounds"));
+            }
+            // Elements taking no data would not be charged otherwise.
+            if element_size == 0 {
+                charge(budget, length)?;
+            }
+
+            Token::Array(decode_tuple(
+                &vec![*inner.clone(); length],
+                data,
+                position + 32,
+                budget,
+            )?)
+        }
+        ParamType::FixedArray(inner, length) => {
+            Token::FixedArray(decode_tuple(&vec![*inner.clone(); *length], data, position, budget)?)
+        }
+        ParamType::Tuple(members) => Token::Tuple(decode_tuple(members, data, position, budget)?),
+    };
+
+    Ok(token)
+}
+
+fn read_word(data: &[u8], position: usize, budget: &mut usize) -> Result<[u8; 32], AbiError> {
+    let word = position
+        .checked_add(32)
+        .and_then(|end| data.get(position..end))
+        .map(|word| word.try_into().unwrap())
+        .ok_or(AbiError::InvalidData("unexpected end of data"))?;
+
+    charge(budget, 32)?;
+
+    Ok(word)
+}
+
+fn read_usize(data: &[u8], position: usize, budget: &mut usize) -> Result<usize, AbiError> {
+    usize::try_from(U256::from_be_bytes(read_word(data, position, budget)?))
+        .map_err(|_| AbiError::InvalidData("length or offset out of bounds"))
+}
+
+fn read_bytes<'a>(data: &'a [u8], position: usize, budget: &mut usize) -> Result<&'a [u8], AbiError> {
+    let length = read_usize(data, position, budget)?;
+    let start = position + 32;
+    let bytes = start
+        .checked_add(length)
+        .and_then(|end| data.get(start..end))
+        .ok_or(AbiError::InvalidData("unexpected end of data"))?;
+
+    charge(budget, length)?;
+
+    Ok(bytes)
+}
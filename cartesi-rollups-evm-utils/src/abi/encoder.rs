@@ -0,0 +1,69 @@
+use crate::abi::Token;
+use crate::U256;
+
+/// Encodes `tokens` as the members of a tuple, which is also how function arguments are encoded.
+pub fn encode(tokens: &[Token]) -> Vec<u8> {
+    let mut output = vec![];
+    encode_tuple(tokens, &mut output);
+
+    output
+}
+
+fn encode_tuple(tokens: &[Token], output: &mut Vec<u8>) {
+    let head_size: usize = tokens.iter().map(head_size).sum();
+    let mut tail = vec![];
+
+    for token in tokens {
+        match token.is_dynamic() {
+            true => {
+                output.extend_from_slice(&word(head_size + tail.len()));
+                encode_token(token, &mut tail);
+            }
+            false => encode_token(token, output),
+        }
+    }
+
+    output.extend_from_slice(&tail);
+}
+
+fn encode_token(token: &Token, output: &mut Vec<u8>) {
+    match token {
+        Token::Address(address) => {
+            output.extend_from_slice(&[0; 12]);
+            output.extend_from_slice(address.as_bytes());
+        }
+        Token::Bool(value) => output.extend_from_slice(&word(*value as usize)),
+        Token::Uint(value) | Token::Int(value) => output.extend_from_slice(&value.to_be_bytes()),
+        Token::FixedBytes(bytes) => pad_right(bytes, output),
+        Token::Bytes(bytes) => {
+            output.extend_from_slice(&word(bytes.len()));
+            pad_right(bytes, output);
+        }
+        Token::String(value) => {
+            output.extend_from_slice(&word(value.len()));
+            pad_right(value.as_bytes(), output);
+        }
+        Token::Array(tokens) => {
+            output.extend_from_slice(&word(tokens.len()));
+            encode_tuple(tokens, output);
+        }
+        Token::FixedArray(tokens) | Token::Tuple(tokens) => encode_tuple(tokens, output),
+    }
+}
+
+fn head_size(token: &Token) -> usize {
+    match token {
+        _ if token.is_dynamic() => 32,
+        Token::FixedArray(tokens) | Token::Tuple(tokens) => tokens.iter().map(head_size).sum(),
+        _ => 32,
+    }
+}
+
+fn word(value: usize) -> [u8; 32] {
+    U256::from(value).to_be_bytes()
+}
+
+fn pad_right(bytes: &[u8], output: &mut Vec<u8>) {
+    output.extend_from_slice(bytes);
+    output.resize(output.len() + (32 - bytes.len() % 32) % 32, 0);
+}
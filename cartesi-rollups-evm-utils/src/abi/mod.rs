@@ -0,0 +1,385 @@
+//! Items in this module encode and decode values according to the Solidity [contract ABI].
+//!
+//! Vouchers carry ABI-encoded calldata and the L1 contracts decode notices as ABI, so this is the format a DApp uses
+//! to talk to the base layer.
+//!
+//! Rust types convert to and from tokens through [`AbiEncode`] and [`AbiDecode`]. The `derive` feature adds derive
+//! macros of both for structs, along with the `selector!` macro computing selectors at compile time.
+//!
+//! # Examples
+//!
+//! ```
+//! # use cartesi_rollups_evm_utils::abi::{encode_call, Token};
+//! # use cartesi_rollups_evm_utils::{Address, U256};
+//! let recipient = Address::new([0x11; 20]);
+//! let calldata = encode_call("transfer(address,uint256)", &[recipient.into(), U256::from(100u8).into()]).unwrap();
+//!
+//! assert_eq!([0xa9, 0x05, 0x9c, 0xbb], calldata[..4]);
+//! assert_eq!(4 + 2 * 32, calldata.len());
+//! ```
+//!
+#![cfg_attr(
+    feature = "derive",
+    doc = r#"
+Structs deriving the traits encode as the tuple of their fields:
+
+```
+# use cartesi_rollups_evm_utils::abi::{selector, AbiDecode, AbiEncode};
+# use cartesi_rollups_evm_utils::{Address, U256};
+#[derive(AbiEncode, AbiDecode, Debug, PartialEq)]
+struct Transfer {
+    to: Address,
+    amount: U256,
+}
+
+let transfer = Transfer { to: Address::new([0x11; 20]), amount: U256::from(100u8) };
+let calldata = [&selector!("transfer(address,uint256)")[..], &transfer.abi_encode()].concat();
+
+assert_eq!("(address,uint256)", Transfer::param_type().to_string());
+assert_eq!([0xa9, 0x05, 0x9c, 0xbb], calldata[..4]);
+assert_eq!(Ok(transfer), Transfer::abi_decode(&calldata[4..]));
+```
+"#
+)]
+//!
+//! [contract ABI]: https://docs.soliditylang.org/en/latest/abi-spec.html
+mod decoder;
+mod encoder;
+mod param_type;
+mod token;
+mod traits;
+
+#[cfg(feature = "derive")]
+pub use cartesi_rollups_evm_macros::{selector, AbiDecode, AbiEncode};
+pub use decoder::*;
+pub use encoder::*;
+pub use param_type::*;
+pub use token::*;
+pub use traits::*;
+
+use crate::keccak256;
+use thiserror::Error;
+
+/// Defines errors of ABI encoding and decoding.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum AbiError {
+    #[error("invalid type {0:?}")]
+    InvalidType(String),
+    #[error("invalid function signature {0:?}")]
+    InvalidSignature(String),
+    #[error("arguments do not match types ({0})")]
+    TypeMismatch(String),
+    #[error("selector {actual:02x?} does not match {expected:02x?}")]
+    SelectorMismatch { expected: [u8; 4], actual: [u8; 4] },
+    #[error("invalid data: {0}")]
+    InvalidData(&'static str),
+}
+
+/// Computes the 4-byte function selector of `signature`, e.g. `transfer(address,uint256)`.
+///
+/// The signature is hashed as it is, so it has to be canonical: no spaces, no parameter names and no type aliases.
+pub fn selector(signature: &str) -> [u8; 4] {
+    keccak256(signature)[..4].try_into().unwrap()
+}
+
+/// Function signature split into its name and parameter types.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Signature {
+    pub name: String,
+    pub inputs: Vec<ParamType>,
+}
+
+impl Signature {
+    /// Parses `signature` of the form `name(type,...)`.
+    pub fn parse(signature: &str) -> Result<Self, AbiError> {
+        let invalid = || AbiError::InvalidSignature(signature.to_owned());
+        let signature = signature.trim();
+        let (name, rest) = signature.split_once('(').ok_or_else(invalid)?;
+        let inputs = rest.strip_suffix(')').ok_or_else(invalid)?;
+
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$') {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            name: name.to_owned(),
+            inputs: ParamType::parse_list(inputs)?,
+        })
+    }
+
+    /// Returns the canonical form of this signature, the one hashed into the selector.
+    pub fn canonical(&self) -> String {
+        let inputs = ParamType::Tuple(self.inputs.clone()).to_string();
+
+        format!("{}{}", self.name, inputs)
+    }
+
+    pub fn selector(&self) -> [u8; 4] {
+        selector(&self.canonical())
+    }
+}
+
+/// Encodes calldata of calling function `signature` with `args`, prefixed by the selector.
+///
+/// The signature is normalized first, so `transfer(address, uint)` works the same as `transfer(address,uint256)`.
+pub fn encode_call(signature: &str, args: &[Token]) -> Result<Vec<u8>, AbiError> {
+    let signature = Signature::parse(signature)?;
+    let inputs = ParamType::Tuple(signature.inputs.clone());
+
+    if !Token::Tuple(args.to_vec()).type_check(&inputs) {
+        return Err(AbiError::TypeMismatch(inputs.to_string()));
+    }
+
+    let mut calldata = signature.selector().to_vec();
+    calldata.extend(encode(args));
+
+    Ok(calldata)
+}
+
+/// Decodes the arguments of `calldata` calling function `signature`, checking the selector prefix.
+pub fn decode_call(signature: &str, calldata: &[u8]) -> Result<Vec<Token>, AbiError> {
+    let signature = Signature::parse(signature)?;
+    let expected = signature.selector();
+    let actual: [u8; 4] = calldata
+        .get(..4)
+        .ok_or(AbiError::InvalidData("missing selector"))?
+        .try_into()
+        .unwrap();
+
+    if actual != expected {
+        return Err(AbiError::SelectorMismatch { expected, actual });
+    }
+
+    decode(&signature.inputs, &calldata[4..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Address, U256};
+    use std::str::FromStr;
+
+    fn words(words: &[&str]) -> Vec<u8> {
+        words
+            .iter()
+            .flat_map(|word| {
+                let mut bytes = hex::decode(word).unwrap();
+                if bytes.len() < 32 {
+                    let mut padded = vec![0; 32 - bytes.len()];
+                    padded.append(&mut bytes);
+                    bytes = padded;
+                }
+                bytes
+            })
+            .collect()
+    }
+
+    fn text(value: &str) -> String {
+        format!("{:0<64}", hex::encode(value))
+    }
+
+    fn assert_round_trip(signature: &str, args: Vec<Token>, expected: Vec<u8>) {
+        let actual = encode_call(signature, &args).unwrap();
+
+        assert_eq!(hex::encode(&expected), hex::encode(&actual));
+        assert_eq!(args, decode_call(signature, &actual).unwrap());
+    }
+
+    #[test]
+    fn test_selectors_match_known_functions() {
+        assert_eq!([0xa9, 0x05, 0x9c, 0xbb], selector("transfer(address,uint256)"));
+        assert_eq!(
+            [0x23, 0xb8, 0x72, 0xdd],
+            selector("transferFrom(address,address,uint256)")
+        );
+        assert_eq!(
+            [0x42, 0x84, 0x2e, 0x0e],
+            selector("safeTransferFrom(address,address,uint256)")
+        );
+        assert_eq!(
+            selector("transfer(address,uint256)"),
+            Signature::parse("transfer(address, uint)").unwrap().selector()
+        );
+    }
+
+    #[test]
+    fn test_static_arguments_match_solidity_vector() {
+        let mut expected = hex::decode("cdcd77c0").unwrap();
+        expected.extend(words(&["45", "01"]));
+
+        assert_round_trip(
+            "baz(uint32,bool)",
+            vec![Token::Uint(U256::from(69u8)), Token::Bool(true)],
+            expected,
+        );
+    }
+
+    #[test]
+    fn test_fixed_bytes_array_matches_solidity_vector() {
+        let mut expected = hex::decode("fce353f6").unwrap();
+        expected.extend(hex::decode(text("abc") + &text("def")).unwrap());
+
+        assert_round_trip(
+            "bar(bytes3[2])",
+            vec![Token::FixedArray(vec![
+                Token::FixedBytes(b"abc".to_vec()),
+                Token::FixedBytes(b"def".to_vec()),
+            ])],
+            expected,
+        );
+    }
+
+    #[test]
+    fn test_dynamic_arguments_match_solidity_vector() {
+        let mut expected = hex::decode("a5643bf2").unwrap();
+        expected.extend(words(&["60", "01", "a0", "04", &text("dave"), "03", "01", "02", "03"]));
+
+        assert_round_trip(
+            "sam(bytes,bool,uint256[])",
+            vec![
+                Token::Bytes(b"dave".to_vec()),
+                Token::Bool(true),
+                Token::Array(vec![
+                    Token::Uint(U256::from(1u8)),
+                    Token::Uint(U256::from(2u8)),
+                    Token::Uint(U256::from(3u8)),
+                ]),
+            ],
+            expected,
+        );
+    }
+
+    #[test]
+    fn test_mixed_arguments_match_solidity_vector() {
+        let mut expected = hex::decode("8be65246").unwrap();
+        expected.extend(words(&[
+            "0123",
+            "80",
+            &text("1234567890"),
+            "e0",
+            "02",
+            "0456",
+            "0789",
+            "0d",
+            &text("Hello, world!"),
+        ]));
+
+        assert_round_trip(
+            "f(uint256,uint32[],bytes10,bytes)",
+            vec![
+                Token::Uint(U256::from(0x123u16)),
+                Token::Array(vec![
+                    Token::Uint(U256::from(0x456u16)),
+                    Token::Uint(U256::from(0x789u16)),
+                ]),
+                Token::FixedBytes(b"1234567890".to_vec()),
+                Token::Bytes(b"Hello, world!".to_vec()),
+            ],
+            expected,
+        );
+    }
+
+    #[test]
+    fn test_nested_dynamic_arrays_match_solidity_vector() {
+        let mut expected = hex::decode("2289b18c").unwrap();
+        expected.extend(words(&[
+            "40",
+            "0140",
+            "02",
+            "40",
+            "a0",
+            "02",
+            "01",
+            "02",
+            "01",
+            "03",
+            "03",
+            "60",
+            "a0",
+            "e0",
+            "03",
+            &text("one"),
+            "03",
+            &text("two"),
+            "05",
+            &text("three"),
+        ]));
+
+        let uint = |value: u8| Token::Uint(U256::from(value));
+
+        assert_round_trip(
+            "g(uint256[][],string[])",
+            vec![
+                Token::Array(vec![Token::Array(vec![uint(1), uint(2)]), Token::Array(vec![uint(3)])]),
+                Token::Array(vec!["one".into(), "two".into(), "three".into()]),
+            ],
+            expected,
+        );
+    }
+
+    #[test]
+    fn test_tuples_and_signed_integers_round_trip() {
+        let types = ParamType::parse_list("(address,int8,string)[],int256").unwrap();
+        let tokens = vec![
+            Token::Array(vec![Token::Tuple(vec![
+                Address::new([0xaa; 20]).into(),
+                Token::int(-128),
+                "cartesi".into(),
+            ])]),
+            Token::int(-1),
+        ];
+        let encoded = encode(&tokens);
+
+        assert_eq!("(address,int8,string)[]", types[0].to_string());
+        assert_eq!([0xff; 32], encoded[32..64]);
+        assert_eq!(tokens, decode(&types, &encoded).unwrap());
+        assert_eq!(
+            Some(-128),
+            tokens[0].clone().into_array().unwrap()[0].clone().into_tuple().unwrap()[1]
+                .clone()
+                .into_i128()
+        );
+    }
+
+    #[test]
+    fn test_decoding_rejects_malformed_data() {
+        let types = [ParamType::Uint(8)];
+
+        assert_eq!(
+            Err(AbiError::InvalidData("uint out of range")),
+            decode(&types, &words(&["0100"]))
+        );
+        assert_eq!(
+            Err(AbiError::InvalidData("unexpected end of data")),
+            decode(&[ParamType::Bytes], &words(&["20", "40"]))
+        );
+        assert_eq!(
+            Err(AbiError::TypeMismatch("(address)".to_owned())),
+            encode_call("f(address)", &[Token::Bool(true)])
+        );
+        assert!(ParamType::from_str("uint7").is_err());
+    }
+
+    #[test]
+    fn test_decoding_rejects_aliased_offsets() {
+        let types = [ParamType::Array(Box::new(ParamType::Array(Box::new(ParamType::Uint(
+            256,
+        )))))];
+        let canonical = encode(&[Token::Array(vec![
+            Token::Array(vec![Token::Uint(U256::from(1u8)); 3]),
+            Token::Array(vec![Token::Uint(U256::from(2u8)); 3]),
+        ])]);
+        // Four inner arrays whose offsets all point at the same three words.
+        let aliased = words(&["20", "04", "80", "80", "80", "80", "03", "01", "01", "01"]);
+
+        assert_eq!(canonical.len(), encode(&decode(&types, &canonical).unwrap()).len());
+        assert_eq!(
+            Err(AbiError::InvalidData("array length out of bounds")),
+            decode(&types, &aliased)
+        );
+        assert_eq!(
+            Err(AbiError::InvalidData("data decodes to more than its length")),
+            decode(&[ParamType::Bytes, ParamType::Bytes], &words(&["40", "40", "20", "ff"]))
+        );
+    }
+}
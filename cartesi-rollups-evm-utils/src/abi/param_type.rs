@@ -0,0 +1,219 @@
+use crate::abi::AbiError;
+use std::fmt;
+use std::str::FromStr;
+
+/// Solidity type of a function parameter, as used by the [contract ABI].
+///
+/// Parses from and displays as the canonical type name, e.g. `uint256`, `bytes32[]` or `(address,uint256)[2]`. The
+/// aliases `uint`, `int` and `byte` parse as `uint256`, `int256` and `bytes1`.
+///
+/// [contract ABI]: https://docs.soliditylang.org/en/latest/abi-spec.html
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ParamType {
+    Address,
+    Bool,
+    /// Unsigned integer of the given number of bits.
+    Uint(usize),
+    /// Signed integer of the given number of bits.
+    Int(usize),
+    /// Byte array of the given length.
+    FixedBytes(usize),
+    Bytes,
+    String,
+    Array(Box<ParamType>),
+    FixedArray(Box<ParamType>, usize),
+    Tuple(Vec<ParamType>),
+}
+
+impl ParamType {
+    /// Returns `true` if the encoding of this type is stored out of place and referenced by an offset.
+    pub fn is_dynamic(&self) -> bool {
+        match self {
+            ParamType::Bytes | ParamType::String | ParamType::Array(_) => true,
+            ParamType::FixedArray(inner, length) => *length > 0 && inner.is_dynamic(),
+            ParamType::Tuple(members) => members.iter().any(ParamType::is_dynamic),
+            _ => false,
+        }
+    }
+
+    /// Returns the number of bytes this type occupies in the head of its enclosing tuple.
+    pub(crate) fn head_size(&self) -> usize {
+        match self {
+            _ if self.is_dynamic() => 32,
+            ParamType::FixedArray(inner, length) => inner.head_size() * length,
+            ParamType::Tuple(members) => members.iter().map(ParamType::head_size).sum(),
+            _ => 32,
+        }
+    }
+
+    /// Parses comma-separated types of a tuple without the enclosing parentheses, e.g. `address,uint256`.
+    pub fn parse_list(s: &str) -> Result<Vec<ParamType>, AbiError> {
+        let mut parser = Parser::new(s);
+        let types = parser.list(None)?;
+        parser.end()?;
+
+        Ok(types)
+    }
+}
+
+impl FromStr for ParamType {
+    type Err = AbiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser::new(s);
+        let param_type = parser.param_type()?;
+        parser.end()?;
+
+        Ok(param_type)
+    }
+}
+
+impl fmt::Display for ParamType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParamType::Address => f.write_str("address"),
+            ParamType::Bool => f.write_str("bool"),
+            ParamType::Uint(bits) => write!(f, "uint{}", bits),
+            ParamType::Int(bits) => write!(f, "int{}", bits),
+            ParamType::FixedBytes(length) => write!(f, "bytes{}", length),
+            ParamType::Bytes => f.write_str("bytes"),
+            ParamType::String => f.write_str("string"),
+            ParamType::Array(inner) => write!(f, "{}[]", inner),
+            ParamType::FixedArray(inner, length) => write!(f, "{}[{}]", inner, length),
+            ParamType::Tuple(members) => {
+                f.write_str("(")?;
+                for (index, member) in members.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{}", member)?;
+                }
+                f.write_str(")")
+            }
+        }
+    }
+}
+
+/// Recursive descent parser of type names.
+struct Parser<'a> {
+    input: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self { input, position: 0 }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.position..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+
+        match self.rest().starts_with(c) {
+            true => {
+                self.position += c.len_utf8();
+                true
+            }
+            false => false,
+        }
+    }
+
+    fn end(&mut self) -> Result<(), AbiError> {
+        self.skip_whitespace();
+
+        match self.rest().is_empty() {
+            true => Ok(()),
+            false => Err(self.error()),
+        }
+    }
+
+    fn error(&self) -> AbiError {
+        AbiError::InvalidType(self.input.to_owned())
+    }
+
+    /// Parses types separated by commas until `close` or the end of input.
+    fn list(&mut self, close: Option<char>) -> Result<Vec<ParamType>, AbiError> {
+        let mut types = vec![];
+
+        self.skip_whitespace();
+        if self.rest().is_empty() || close.is_some_and(|close| self.rest().starts_with(close)) {
+            return Ok(types);
+        }
+
+        loop {
+            types.push(self.param_type()?);
+
+            if !self.eat(',') {
+                return Ok(types);
+            }
+        }
+    }
+
+    fn param_type(&mut self) -> Result<ParamType, AbiError> {
+        let mut param_type = match self.eat('(') {
+            true => {
+                let members = self.list(Some(')'))?;
+
+                if !self.eat(')') {
+                    return Err(self.error());
+                }
+
+                ParamType::Tuple(members)
+            }
+            false => self.elementary()?,
+        };
+
+        while self.eat('[') {
+            let rest = self.rest();
+            let digits = &rest[..rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len())];
+            self.position += digits.len();
+
+            if !self.eat(']') {
+                return Err(self.error());
+            }
+
+            param_type = match digits.is_empty() {
+                true => ParamType::Array(Box::new(param_type)),
+                false => ParamType::FixedArray(Box::new(param_type), digits.parse().map_err(|_| self.error())?),
+            };
+        }
+
+        Ok(param_type)
+    }
+
+    fn elementary(&mut self) -> Result<ParamType, AbiError> {
+        self.skip_whitespace();
+
+        let rest = self.rest();
+        let name = &rest[..rest.find(|c: char| !c.is_ascii_alphanumeric()).unwrap_or(rest.len())];
+        self.position += name.len();
+
+        let size = |prefix: &str| name.strip_prefix(prefix).map(|digits| digits.parse::<usize>());
+
+        let param_type = match name {
+            "address" => ParamType::Address,
+            "bool" => ParamType::Bool,
+            "string" => ParamType::String,
+            "bytes" => ParamType::Bytes,
+            "byte" => ParamType::FixedBytes(1),
+            "uint" => ParamType::Uint(256),
+            "int" => ParamType::Int(256),
+            _ => match (size("uint"), size("int"), size("bytes")) {
+                (Some(Ok(bits)), ..) if bits % 8 == 0 && (8..=256).contains(&bits) => ParamType::Uint(bits),
+                (_, Some(Ok(bits)), _) if bits % 8 == 0 && (8..=256).contains(&bits) => ParamType::Int(bits),
+                (.., Some(Ok(length))) if (1..=32).contains(&length) => ParamType::FixedBytes(length),
+                _ => return Err(self.error()),
+            },
+        };
+
+        Ok(param_type)
+    }
+}
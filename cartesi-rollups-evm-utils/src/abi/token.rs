@@ -0,0 +1,170 @@
+use crate::abi::ParamType;
+use crate::{Address, U256};
+
+/// Value of a [`ParamType`].
+///
+/// Signed integers are kept as their 256-bit two's complement, see [`Token::int`] and [`Token::into_i128`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Token {
+    Address(Address),
+    Bool(bool),
+    Uint(U256),
+    Int(U256),
+    FixedBytes(Vec<u8>),
+    Bytes(Vec<u8>),
+    String(String),
+    Array(Vec<Token>),
+    FixedArray(Vec<Token>),
+    Tuple(Vec<Token>),
+}
+
+impl Token {
+    /// Creates [`Token::Int`] holding the two's complement of `value`.
+    pub fn int(value: i128) -> Self {
+        let magnitude = U256::from(value.unsigned_abs());
+
+        match value < 0 {
+            true => Token::Int(U256::ZERO.overflowing_sub(magnitude).0),
+            false => Token::Int(magnitude),
+        }
+    }
+
+    /// Returns `true` if the encoding of this token is stored out of place and referenced by an offset.
+    pub fn is_dynamic(&self) -> bool {
+        match self {
+            Token::Bytes(_) | Token::String(_) | Token::Array(_) => true,
+            Token::FixedArray(tokens) | Token::Tuple(tokens) => tokens.iter().any(Token::is_dynamic),
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if this token holds a value of `param_type`.
+    pub fn type_check(&self, param_type: &ParamType) -> bool {
+        match (self, param_type) {
+            (Token::Address(_), ParamType::Address) => true,
+            (Token::Bool(_), ParamType::Bool) => true,
+            (Token::Uint(value), ParamType::Uint(bits)) => value.bits() as usize <= *bits,
+            (Token::Int(value), ParamType::Int(bits)) => fits_signed(value, *bits),
+            (Token::FixedBytes(bytes), ParamType::FixedBytes(length)) => bytes.len() == *length,
+            (Token::Bytes(_), ParamType::Bytes) => true,
+            (Token::String(_), ParamType::String) => true,
+            (Token::Array(tokens), ParamType::Array(inner)) => tokens.iter().all(|token| token.type_check(inner)),
+            (Token::FixedArray(tokens), ParamType::FixedArray(inner, length)) => {
+                tokens.len() == *length && tokens.iter().all(|token| token.type_check(inner))
+            }
+            (Token::Tuple(tokens), ParamType::Tuple(members)) => {
+                tokens.len() == members.len()
+                    && tokens
+                        .iter()
+                        .zip(members)
+                        .all(|(token, member)| token.type_check(member))
+            }
+            _ => false,
+        }
+    }
+
+    pub fn into_address(self) -> Option<Address> {
+        match self {
+            Token::Address(address) => Some(address),
+            _ => None,
+        }
+    }
+
+    pub fn into_bool(self) -> Option<bool> {
+        match self {
+            Token::Bool(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn into_uint(self) -> Option<U256> {
+        match self {
+            Token::Uint(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of [`Token::Int`] if it fits `i128`.
+    pub fn into_i128(self) -> Option<i128> {
+        match self {
+            Token::Int(value) if fits_signed(&value, 128) => {
+                let bytes = value.to_be_bytes();
+
+                Some(i128::from_be_bytes(bytes[16..].try_into().unwrap()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the bytes of either [`Token::Bytes`] or [`Token::FixedBytes`].
+    pub fn into_bytes(self) -> Option<Vec<u8>> {
+        match self {
+            Token::Bytes(bytes) | Token::FixedBytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    pub fn into_string(self) -> Option<String> {
+        match self {
+            Token::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the elements of either [`Token::Array`] or [`Token::FixedArray`].
+    pub fn into_array(self) -> Option<Vec<Token>> {
+        match self {
+            Token::Array(tokens) | Token::FixedArray(tokens) => Some(tokens),
+            _ => None,
+        }
+    }
+
+    pub fn into_tuple(self) -> Option<Vec<Token>> {
+        match self {
+            Token::Tuple(tokens) => Some(tokens),
+            _ => None,
+        }
+    }
+}
+
+impl From<Address> for Token {
+    fn from(address: Address) -> Self {
+        Token::Address(address)
+    }
+}
+
+impl From<bool> for Token {
+    fn from(value: bool) -> Self {
+        Token::Bool(value)
+    }
+}
+
+impl From<U256> for Token {
+    fn from(value: U256) -> Self {
+        Token::Uint(value)
+    }
+}
+
+impl From<String> for Token {
+    fn from(value: String) -> Self {
+        Token::String(value)
+    }
+}
+
+impl From<&str> for Token {
+    fn from(value: &str) -> Self {
+        Token::String(value.to_owned())
+    }
+}
+
+/// Returns `true` if the two's complement `value` sign-extends from `bits`.
+pub(crate) fn fits_signed(value: &U256, bits: usize) -> bool {
+    let bytes = value.to_be_bytes();
+    let (extension, rest) = bytes.split_at(U256::LENGTH - bits / 8);
+    let sign = match rest.first().is_some_and(|byte| byte & 0x80 != 0) {
+        true => 0xff,
+        false => 0x00,
+    };
+
+    extension.iter().all(|&byte| byte == sign)
+}
@@ -0,0 +1,265 @@
+use crate::abi::{decode, encode, AbiError, ParamType, Token};
+use crate::{Address, U256};
+
+/// The implementor of this trait converts into a [`Token`] to be ABI-encoded.
+///
+/// Derive it with the `derive` feature for a struct to encode as the tuple of its fields.
+pub trait AbiEncode {
+    fn to_token(&self) -> Token;
+
+    /// Encodes this value like `abi.encode` does, so a struct encodes as `abi.encode(field1, field2, ...)`.
+    fn abi_encode(&self) -> Vec<u8> {
+        match self.to_token() {
+            Token::Tuple(members) => encode(&members),
+            token => encode(&[token]),
+        }
+    }
+}
+
+/// The implementor of this trait converts from a [`Token`] of its ABI type.
+///
+/// Derive it with the `derive` feature for a struct to decode from the tuple of its fields.
+pub trait AbiDecode: Sized {
+    fn param_type() -> ParamType;
+
+    fn from_token(token: Token) -> Result<Self, AbiError>;
+
+    /// Decodes the output of [`AbiEncode::abi_encode`], which is what `abi.encode` produces on the base layer.
+    fn abi_decode(data: &[u8]) -> Result<Self, AbiError> {
+        let token = match Self::param_type() {
+            ParamType::Tuple(members) => Token::Tuple(decode(&members, data)?),
+            param_type => decode(&[param_type], data)?.remove(0),
+        };
+
+        Self::from_token(token)
+    }
+}
+
+/// Value of the Solidity `bytes` type, as opposed to `Vec<u8>` which is `uint8[]`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Bytes(pub Vec<u8>);
+
+/// Value of the Solidity `bytesN` type, as opposed to `[u8; N]` which is `uint8[N]`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FixedBytes<const N: usize>(pub [u8; N]);
+
+fn mismatch<T: AbiDecode>() -> AbiError {
+    AbiError::TypeMismatch(T::param_type().to_string())
+}
+
+impl AbiEncode for Token {
+    fn to_token(&self) -> Token {
+        self.clone()
+    }
+}
+
+impl AbiEncode for Address {
+    fn to_token(&self) -> Token {
+        Token::Address(*self)
+    }
+}
+
+impl AbiDecode for Address {
+    fn param_type() -> ParamType {
+        ParamType::Address
+    }
+
+    fn from_token(token: Token) -> Result<Self, AbiError> {
+        token.into_address().ok_or_else(mismatch::<Self>)
+    }
+}
+
+impl AbiEncode for bool {
+    fn to_token(&self) -> Token {
+        Token::Bool(*self)
+    }
+}
+
+impl AbiDecode for bool {
+    fn param_type() -> ParamType {
+        ParamType::Bool
+    }
+
+    fn from_token(token: Token) -> Result<Self, AbiError> {
+        token.into_bool().ok_or_else(mismatch::<Self>)
+    }
+}
+
+impl AbiEncode for U256 {
+    fn to_token(&self) -> Token {
+        Token::Uint(*self)
+    }
+}
+
+impl AbiDecode for U256 {
+    fn param_type() -> ParamType {
+        ParamType::Uint(256)
+    }
+
+    fn from_token(token: Token) -> Result<Self, AbiError> {
+        token.into_uint().ok_or_else(mismatch::<Self>)
+    }
+}
+
+macro_rules! impl_uint {
+    ($($primitive:ty),*) => {
+        $(
+            impl AbiEncode for $primitive {
+                fn to_token(&self) -> Token {
+                    Token::Uint(U256::from(*self))
+                }
+            }
+
+            impl AbiDecode for $primitive {
+                fn param_type() -> ParamType {
+                    ParamType::Uint(<$primitive>::BITS as usize)
+                }
+
+                fn from_token(token: Token) -> Result<Self, AbiError> {
+                    token
+                        .into_uint()
+                        .and_then(|value| <$primitive>::try_from(value).ok())
+                        .ok_or_else(mismatch::<Self>)
+                }
+            }
+        )*
+    };
+}
+
+impl_uint!(u8, u16, u32, u64, u128);
+
+macro_rules! impl_int {
+    ($($primitive:ty),*) => {
+        $(
+            impl AbiEncode for $primitive {
+                fn to_token(&self) -> Token {
+                    Token::int(i128::from(*self))
+                }
+            }
+
+            impl AbiDecode for $primitive {
+                fn param_type() -> ParamType {
+                    ParamType::Int(<$primitive>::BITS as usize)
+                }
+
+                fn from_token(token: Token) -> Result<Self, AbiError> {
+                    token
+                        .into_i128()
+                        .and_then(|value| <$primitive>::try_from(value).ok())
+                        .ok_or_else(mismatch::<Self>)
+                }
+            }
+        )*
+    };
+}
+
+impl_int!(i8, i16, i32, i64, i128);
+
+impl AbiEncode for String {
+    fn to_token(&self) -> Token {
+        Token::String(self.clone())
+    }
+}
+
+impl AbiDecode for String {
+    fn param_type() -> ParamType {
+        ParamType::String
+    }
+
+    fn from_token(token: Token) -> Result<Self, AbiError> {
+        token.into_string().ok_or_else(mismatch::<Self>)
+    }
+}
+
+impl AbiEncode for str {
+    fn to_token(&self) -> Token {
+        Token::String(self.to_owned())
+    }
+}
+
+impl AbiEncode for Bytes {
+    fn to_token(&self) -> Token {
+        Token::Bytes(self.0.clone())
+    }
+}
+
+impl AbiDecode for Bytes {
+    fn param_type() -> ParamType {
+        ParamType::Bytes
+    }
+
+    fn from_token(token: Token) -> Result<Self, AbiError> {
+        match token {
+            Token::Bytes(bytes) => Ok(Bytes(bytes)),
+            _ => Err(mismatch::<Self>()),
+        }
+    }
+}
+
+impl<const N: usize> AbiEncode for FixedBytes<N> {
+    fn to_token(&self) -> Token {
+        Token::FixedBytes(self.0.to_vec())
+    }
+}
+
+impl<const N: usize> AbiDecode for FixedBytes<N> {
+    fn param_type() -> ParamType {
+        ParamType::FixedBytes(N)
+    }
+
+    fn from_token(token: Token) -> Result<Self, AbiError> {
+        match token {
+            Token::FixedBytes(bytes) => bytes.try_into().map(FixedBytes).map_err(|_| mismatch::<Self>()),
+            _ => Err(mismatch::<Self>()),
+        }
+    }
+}
+
+impl<T: AbiEncode> AbiEncode for Vec<T> {
+    fn to_token(&self) -> Token {
+        Token::Array(self.iter().map(AbiEncode::to_token).collect())
+    }
+}
+
+impl<T: AbiDecode> AbiDecode for Vec<T> {
+    fn param_type() -> ParamType {
+        ParamType::Array(Box::new(T::param_type()))
+    }
+
+    fn from_token(token: Token) -> Result<Self, AbiError> {
+        match token {
+            Token::Array(tokens) => tokens.into_iter().map(T::from_token).collect(),
+            _ => Err(mismatch::<Self>()),
+        }
+    }
+}
+
+impl<T: AbiEncode, const N: usize> AbiEncode for [T; N] {
+    fn to_token(&self) -> Token {
+        Token::FixedArray(self.iter().map(AbiEncode::to_token).collect())
+    }
+}
+
+impl<T: AbiDecode, const N: usize> AbiDecode for [T; N] {
+    fn param_type() -> ParamType {
+        ParamType::FixedArray(Box::new(T::param_type()), N)
+    }
+
+    fn from_token(token: Token) -> Result<Self, AbiError> {
+        match token {
+            Token::FixedArray(tokens) => tokens
+                .into_iter()
+                .map(T::from_token)
+                .collect::<Result<Vec<_>, _>>()?
+                .try_into()
+                .map_err(|_| mismatch::<Self>()),
+            _ => Err(mismatch::<Self>()),
+        }
+    }
+}
+
+impl<T: AbiEncode + ?Sized> AbiEncode for &T {
+    fn to_token(&self) -> Token {
+        (**self).to_token()
+    }
+}
@@ -0,0 +1,199 @@
+//! Items in this module define the 20-byte Ethereum account address.
+use crate::{keccak256, ParseError};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// Ethereum account address.
+///
+/// Displays in the [EIP-55] mixed-case checksum encoding and parses from hex with or without the `0x` prefix. A
+/// mixed-case input has to carry a valid checksum, all-lowercase and all-uppercase inputs are accepted as they are.
+///
+/// # Examples
+///
+/// ```
+/// # use cartesi_rollups_evm_utils::Address;
+/// let address: Address = "0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed".parse().unwrap();
+///
+/// assert_eq!("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed", address.to_string());
+/// ```
+///
+/// [EIP-55]: https://eips.ethereum.org/EIPS/eip-55
+#[derive(Copy, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Address([u8; Address::LENGTH]);
+
+impl Address {
+    /// Number of bytes of an address.
+    pub const LENGTH: usize = 20;
+
+    /// The zero address.
+    pub const ZERO: Self = Self([0; Self::LENGTH]);
+
+    pub const fn new(bytes: [u8; Self::LENGTH]) -> Self {
+        Self(bytes)
+    }
+
+    pub const fn as_bytes(&self) -> &[u8; Self::LENGTH] {
+        &self.0
+    }
+
+    pub const fn to_bytes(self) -> [u8; Self::LENGTH] {
+        self.0
+    }
+
+    /// Returns the [EIP-55] checksum encoding with the `0x` prefix.
+    ///
+    /// [EIP-55]: https://eips.ethereum.org/EIPS/eip-55
+    pub fn to_checksum(&self) -> String {
+        let lowercase = hex::encode(self.0);
+        let hash = keccak256(lowercase.as_bytes());
+
+        let checksum = lowercase.char_indices().map(|(index, c)| {
+            let nibble = (hash[index / 2] >> (4 * (1 - index % 2))) & 0x0f;
+
+            match nibble >= 8 {
+                true => c.to_ascii_uppercase(),
+                false => c,
+            }
+        });
+
+        "0x".chars().chain(checksum).collect()
+    }
+}
+
+impl From<[u8; Address::LENGTH]> for Address {
+    fn from(bytes: [u8; Address::LENGTH]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<Address> for [u8; Address::LENGTH] {
+    fn from(address: Address) -> Self {
+        address.0
+    }
+}
+
+impl TryFrom<&[u8]> for Address {
+    type Error = ParseError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        bytes.try_into().map(Self).map_err(|_| ParseError::InvalidLength {
+            expected: Self::LENGTH,
+            actual: bytes.len(),
+        })
+    }
+}
+
+impl AsRef<[u8]> for Address {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl FromStr for Address {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits = s.strip_prefix("0x").unwrap_or(s);
+
+        if digits.len() != 2 * Self::LENGTH {
+            return Err(ParseError::InvalidLength {
+                expected: Self::LENGTH,
+                actual: digits.len() / 2,
+            });
+        }
+
+        let mut bytes = [0; Self::LENGTH];
+        hex::decode_to_slice(digits, &mut bytes)?;
+        let address = Self(bytes);
+
+        let is_mixed_case =
+            digits.chars().any(|c| c.is_ascii_lowercase()) && digits.chars().any(|c| c.is_ascii_uppercase());
+
+        if is_mixed_case && address.to_checksum()[2..] != *digits {
+            return Err(ParseError::InvalidChecksum);
+        }
+
+        Ok(address)
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_checksum())
+    }
+}
+
+impl fmt::Debug for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl fmt::LowerHex for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            f.write_str("0x")?;
+        }
+
+        f.write_str(&hex::encode(self.0))
+    }
+}
+
+/// Serializes as the checksum string into human-readable formats and as 20 raw bytes into binary formats.
+impl Serialize for Address {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match serializer.is_human_readable() {
+            true => serializer.collect_str(self),
+            false => self.0.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Address {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match deserializer.is_human_readable() {
+            true => String::deserialize(deserializer)?.parse().map_err(D::Error::custom),
+            false => <[u8; Self::LENGTH]>::deserialize(deserializer).map(Self),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checksum_matches_eip55_vectors() {
+        let vectors = [
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+            "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+            "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+        ];
+
+        for vector in vectors {
+            let address = Address::from_str(&vector.to_lowercase()).unwrap();
+
+            assert_eq!(vector, address.to_checksum());
+            assert_eq!(Ok(address), Address::from_str(vector));
+        }
+    }
+
+    #[test]
+    fn test_parsing_rejects_invalid_checksum() {
+        let actual = Address::from_str("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD");
+
+        assert_eq!(Err(ParseError::InvalidChecksum), actual);
+    }
+
+    #[test]
+    fn test_serde_round_trips_through_checksum_string() {
+        let address = Address::new([0xab; 20]);
+        let json = serde_json::to_string(&address).unwrap();
+
+        assert_eq!(format!("\"{}\"", address), json);
+        assert_eq!(address, serde_json::from_str::<Address>(&json).unwrap());
+    }
+}
@@ -0,0 +1,16 @@
+use thiserror::Error;
+
+/// Defines errors of parsing the primitive types from their textual or binary representation.
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ParseError {
+    #[error("expected {expected} bytes, got {actual}")]
+    InvalidLength { expected: usize, actual: usize },
+    #[error("invalid hex string")]
+    InvalidHex(#[from] hex::FromHexError),
+    #[error("invalid digit {0:?}")]
+    InvalidDigit(char),
+    #[error("number does not fit 256 bits")]
+    Overflow,
+    #[error("address checksum does not match")]
+    InvalidChecksum,
+}
@@ -0,0 +1,6 @@
+use sha3::{Digest, Keccak256};
+
+/// Computes Keccak-256 hash of `data` as defined by Ethereum, which differs from the standardized SHA3-256.
+pub fn keccak256(data: impl AsRef<[u8]>) -> [u8; 32] {
+    Keccak256::digest(data).into()
+}
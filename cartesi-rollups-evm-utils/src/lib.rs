@@ -1 +1,15 @@
+pub mod abi;
+mod address;
+mod error;
+mod keccak;
+pub mod outputs;
+pub mod portals;
+pub mod signatures;
+mod uint;
+mod vouchers;
 
+pub use address::*;
+pub use error::*;
+pub use keccak::*;
+pub use uint::*;
+pub use vouchers::*;
@@ -0,0 +1,558 @@
+//! Items in this module compute the claims and output proofs checked by the Cartesi Rollups contracts.
+//!
+//! The hashes of the outputs of each input are kept in a Merkle tree, and the roots of those trees are in turn kept in
+//! an epoch-wide Merkle tree, one for vouchers and one for notices. The claim of an epoch commits to both epoch roots and
+//! the hash of the machine state. An [`OutputValidityProof`] proves an output is part of a claim, following the
+//! `LibOutputValidation` library of the contracts.
+//!
+//! # Examples
+//!
+//! ```
+//! # use cartesi_rollups_evm_utils::outputs::Epoch;
+//! # use cartesi_rollups_evm_utils::Address;
+//! let destination = Address::new([0xaa; 20]);
+//! let machine_state_hash = [0; 32];
+//!
+//! let mut epoch = Epoch::new();
+//! epoch.add_input(&[(destination, b"calldata".to_vec())], &[b"notice".to_vec()]);
+//!
+//! let claim = epoch.claim(&machine_state_hash);
+//! let proof = epoch.voucher_proof(0, 0, &machine_state_hash).unwrap();
+//!
+//! assert!(proof.validate_voucher(&destination, b"calldata", &claim));
+//! ```
+use crate::abi::{encode, Token};
+use crate::{keccak256, Address, U256};
+
+/// Log2 of the size in bytes of a word of the machine memory, the leaves of its Merkle tree.
+pub const WORD_LOG2_SIZE: u32 = 3;
+/// Log2 of the size in bytes of a Keccak-256 hash, the size of a leaf of the output trees.
+pub const KECCAK_LOG2_SIZE: u32 = 5;
+/// Log2 of the size in bytes of the memory range holding the output hashes of an input.
+pub const OUTPUT_METADATA_LOG2_SIZE: u32 = 21;
+/// Log2 of the size in bytes of the memory range holding the output hashes roots of an epoch.
+pub const EPOCH_OUTPUT_LOG2_SIZE: u32 = 37;
+/// Height of the tree of the outputs of a Cartesi Rollups v2 application.
+pub const OUTPUTS_TREE_HEIGHT: usize = 63;
+
+/// Returns the hash of the voucher of `payload` for `destination`, which is `keccak256(abi.encode(destination,
+/// payload))`.
+pub fn voucher_hash(destination: &Address, payload: &[u8]) -> [u8; 32] {
+    keccak256(encode(&[Token::Address(*destination), Token::Bytes(payload.to_vec())]))
+}
+
+/// Returns the hash of the notice of `payload`, which is `keccak256(abi.encode(payload))`.
+pub fn notice_hash(payload: &[u8]) -> [u8; 32] {
+    keccak256(encode(&[Token::Bytes(payload.to_vec())]))
+}
+
+/// Returns the Merkle root of `hash` stored in the machine memory, whose leaves are 8-byte words.
+///
+/// This is `MerkleV2.getMerkleRootFromBytes(abi.encodePacked(hash), KECCAK_LOG2_SIZE)` of the contracts, that is
+/// `keccak(keccak(k(w0) ‖ k(w1)) ‖ keccak(k(w2) ‖ k(w3)))` where `k` hashes the 8-byte word `wi` of `hash`.
+pub fn memory_root(hash: &[u8; 32]) -> [u8; 32] {
+    let words: Vec<_> = hash.chunks(1 << WORD_LOG2_SIZE).map(keccak256).collect();
+
+    hash_pair(&hash_pair(&words[0], &words[1]), &hash_pair(&words[2], &words[3]))
+}
+
+/// Complete binary Merkle tree over a range filled with 32-byte words from its start.
+///
+/// The hash of an inner node is the Keccak-256 of the concatenation of its children and the rest of the range is
+/// zeroed. How a word is hashed into its leaf depends on the range, see [`MerkleTree::new`] and
+/// [`MerkleTree::with_keccak_leaves`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerkleTree {
+    /// Hashes of the non-pristine nodes, from the leaves up to the root.
+    levels: Vec<Vec<[u8; 32]>>,
+    /// Hashes of the pristine nodes of each level.
+    pristine: Vec<[u8; 32]>,
+}
+
+impl MerkleTree {
+    /// Creates the tree of the memory range of `2^log2_size` bytes starting with `words`.
+    ///
+    /// The machine hashes its memory from 8-byte words, so the leaf of a word is its [`memory_root`]. This is the tree
+    /// of the output hashes of an input.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `log2_size` is less than [`KECCAK_LOG2_SIZE`] or the range cannot hold all of `words`.
+    pub fn new(log2_size: u32, words: impl IntoIterator<Item = [u8; 32]>) -> Self {
+        Self::build(log2_size, words, |word| memory_root(&word))
+    }
+
+    /// Creates the tree of the range of `2^log2_size` bytes starting with `words`, whose leaves are the Keccak-256 of
+    /// the words.
+    ///
+    /// This is the tree of the output hashes roots of an epoch, whose leaves the contracts hash with
+    /// `keccak256(abi.encodePacked(outputHashesRootHash))`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `log2_size` is less than [`KECCAK_LOG2_SIZE`] or the range cannot hold all of `words`.
+    pub fn with_keccak_leaves(log2_size: u32, words: impl IntoIterator<Item = [u8; 32]>) -> Self {
+        Self::build(log2_size, words, keccak256)
+    }
+
+    fn build(log2_size: u32, words: impl IntoIterator<Item = [u8; 32]>, leaf: fn([u8; 32]) -> [u8; 32]) -> Self {
+        let height = (log2_size
+            .checked_sub(KECCAK_LOG2_SIZE)
+            .expect("range smaller than a word")) as usize;
+        let leaves: Vec<_> = words.into_iter().map(leaf).collect();
+        let pristine = pristine_hashes(leaf([0; 32]), height);
+
+        assert!((leaves.len() as u128) <= 1 << height, "range too small for words");
+
+        let mut levels = vec![leaves];
+        for level in 0..height {
+            let children = &levels[level];
+            let parents = children
+                .chunks(2)
+                .map(|pair| hash_pair(&pair[0], pair.get(1).unwrap_or(&pristine[level])))
+                .collect();
+            levels.push(parents);
+        }
+
+        Self { levels, pristine }
+    }
+
+    /// Returns the number of levels below the root.
+    pub fn height(&self) -> usize {
+        self.levels.len() - 1
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.levels[self.height()]
+            .first()
+            .copied()
+            .unwrap_or(self.pristine[self.height()])
+    }
+
+    /// Returns the siblings of the path from the leaf at `index` up to the root, or `None` if `index` is out of range.
+    pub fn proof(&self, index: u64) -> Option<Vec<[u8; 32]>> {
+        if u128::from(index) >= 1 << self.height() {
+            return None;
+        }
+
+        let siblings = (0..self.height())
+            .map(|level| {
+                let sibling = (index >> level) ^ 1;
+
+                usize::try_from(sibling)
+                    .ok()
+                    .and_then(|sibling| self.levels[level].get(sibling))
+                    .copied()
+                    .unwrap_or(self.pristine[level])
+            })
+            .collect();
+
+        Some(siblings)
+    }
+}
+
+/// Returns the root of the tree where the leaf at `index` has `leaf_hash` and the path has `siblings`.
+///
+/// This is the computation `MerkleV2.getRootAfterReplacementInDrive` of the contracts does.
+pub fn root_after_replacement(index: u64, leaf_hash: [u8; 32], siblings: &[[u8; 32]]) -> [u8; 32] {
+    siblings
+        .iter()
+        .enumerate()
+        .fold(leaf_hash, |hash, (level, sibling)| match (index >> level) & 1 {
+            0 => hash_pair(&hash, sibling),
+            _ => hash_pair(sibling, &hash),
+        })
+}
+
+/// Outputs of the inputs of an epoch, used to compute its claim and the proofs of its outputs.
+///
+/// Add the outputs of every input of the epoch, including inputs without outputs and rejected inputs, whose outputs are
+/// discarded. Otherwise the indices of the inputs shift and the claim does not match.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Epoch {
+    vouchers: Vec<MerkleTree>,
+    notices: Vec<MerkleTree>,
+}
+
+impl Epoch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the next input of the epoch, which wrote `vouchers` and `notices`.
+    pub fn add_input(&mut self, vouchers: &[(Address, Vec<u8>)], notices: &[Vec<u8>]) {
+        let vouchers = vouchers
+            .iter()
+            .map(|(destination, payload)| voucher_hash(destination, payload));
+        let notices = notices.iter().map(|payload| notice_hash(payload));
+
+        self.vouchers.push(MerkleTree::new(OUTPUT_METADATA_LOG2_SIZE, vouchers));
+        self.notices.push(MerkleTree::new(OUTPUT_METADATA_LOG2_SIZE, notices));
+    }
+
+    pub fn input_count(&self) -> usize {
+        self.vouchers.len()
+    }
+
+    /// Returns the root of the tree of the voucher hashes roots of each input.
+    pub fn vouchers_root(&self) -> [u8; 32] {
+        epoch_tree(&self.vouchers).root()
+    }
+
+    /// Returns the root of the tree of the notice hashes roots of each input.
+    pub fn notices_root(&self) -> [u8; 32] {
+        epoch_tree(&self.notices).root()
+    }
+
+    /// Returns the claim of this epoch, which is `keccak256(abi.encodePacked(vouchersRoot, noticesRoot,
+    /// machineStateHash))`.
+    pub fn claim(&self, machine_state_hash: &[u8; 32]) -> [u8; 32] {
+        keccak256([self.vouchers_root(), self.notices_root(), *machine_state_hash].concat())
+    }
+
+    /// Returns the proof of the voucher at `output_index` of the input at `input_index`, or `None` if there is no such
+    /// voucher.
+    pub fn voucher_proof(
+        &self,
+        input_index: usize,
+        output_index: usize,
+        machine_state_hash: &[u8; 32],
+    ) -> Option<OutputValidityProof> {
+        self.proof(&self.vouchers, input_index, output_index, machine_state_hash)
+    }
+
+    /// Returns the proof of the notice at `output_index` of the input at `input_index`, or `None` if there is no such
+    /// notice.
+    pub fn notice_proof(
+        &self,
+        input_index: usize,
+        output_index: usize,
+        machine_state_hash: &[u8; 32],
+    ) -> Option<OutputValidityProof> {
+        self.proof(&self.notices, input_index, output_index, machine_state_hash)
+    }
+
+    fn proof(
+        &self,
+        outputs: &[MerkleTree],
+        input_index: usize,
+        output_index: usize,
+        machine_state_hash: &[u8; 32],
+    ) -> Option<OutputValidityProof> {
+        let input = outputs.get(input_index)?;
+        input.levels[0].get(output_index)?;
+
+        Some(OutputValidityProof {
+            input_index_within_epoch: input_index as u64,
+            output_index_within_input: output_index as u64,
+            output_hashes_root_hash: input.root(),
+            vouchers_epoch_root_hash: self.vouchers_root(),
+            notices_epoch_root_hash: self.notices_root(),
+            machine_state_hash: *machine_state_hash,
+            output_hash_in_output_hashes_siblings: input.proof(output_index as u64)?,
+            output_hashes_in_epoch_siblings: epoch_tree(outputs).proof(input_index as u64)?,
+        })
+    }
+}
+
+/// Proof that an output is part of the claim of an epoch, the `OutputValidityProof` struct of the contracts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutputValidityProof {
+    pub input_index_within_epoch: u64,
+    pub output_index_within_input: u64,
+    pub output_hashes_root_hash: [u8; 32],
+    pub vouchers_epoch_root_hash: [u8; 32],
+    pub notices_epoch_root_hash: [u8; 32],
+    pub machine_state_hash: [u8; 32],
+    pub output_hash_in_output_hashes_siblings: Vec<[u8; 32]>,
+    pub output_hashes_in_epoch_siblings: Vec<[u8; 32]>,
+}
+
+impl OutputValidityProof {
+    /// Returns `true` if this proves the voucher of `payload` for `destination` is part of `claim`.
+    pub fn validate_voucher(&self, destination: &Address, payload: &[u8], claim: &[u8; 32]) -> bool {
+        self.validate(voucher_hash(destination, payload), self.vouchers_epoch_root_hash, claim)
+    }
+
+    /// Returns `true` if this proves the notice of `payload` is part of `claim`.
+    pub fn validate_notice(&self, payload: &[u8], claim: &[u8; 32]) -> bool {
+        self.validate(notice_hash(payload), self.notices_epoch_root_hash, claim)
+    }
+
+    /// Returns the token of the proof as the `OutputValidityProof` tuple expected by the contracts.
+    pub fn to_token(&self) -> Token {
+        let bytes32 = |hash: &[u8; 32]| Token::FixedBytes(hash.to_vec());
+        let siblings = |hashes: &[[u8; 32]]| Token::Array(hashes.iter().map(bytes32).collect());
+
+        Token::Tuple(vec![
+            Token::Uint(U256::from(self.input_index_within_epoch)),
+            Token::Uint(U256::from(self.output_index_within_input)),
+            bytes32(&self.output_hashes_root_hash),
+            bytes32(&self.vouchers_epoch_root_hash),
+            bytes32(&self.notices_epoch_root_hash),
+            bytes32(&self.machine_state_hash),
+            siblings(&self.output_hash_in_output_hashes_siblings),
+            siblings(&self.output_hashes_in_epoch_siblings),
+        ])
+    }
+
+    fn validate(&self, output_hash: [u8; 32], outputs_epoch_root_hash: [u8; 32], claim: &[u8; 32]) -> bool {
+        let epoch_hash = keccak256(
+            [
+                self.vouchers_epoch_root_hash,
+                self.notices_epoch_root_hash,
+                self.machine_state_hash,
+            ]
+            .concat(),
+        );
+        let outputs_epoch_root = root_after_replacement(
+            self.input_index_within_epoch,
+            keccak256(self.output_hashes_root_hash),
+            &self.output_hashes_in_epoch_siblings,
+        );
+        let output_hashes_root = root_after_replacement(
+            self.output_index_within_input,
+            memory_root(&output_hash),
+            &self.output_hash_in_output_hashes_siblings,
+        );
+
+        epoch_hash == *claim
+            && outputs_epoch_root == outputs_epoch_root_hash
+            && output_hashes_root == self.output_hashes_root_hash
+            && self.output_hashes_in_epoch_siblings.len() == (EPOCH_OUTPUT_LOG2_SIZE - KECCAK_LOG2_SIZE) as usize
+            && self.output_hash_in_output_hashes_siblings.len()
+                == (OUTPUT_METADATA_LOG2_SIZE - KECCAK_LOG2_SIZE) as usize
+    }
+}
+
+fn epoch_tree(outputs: &[MerkleTree]) -> MerkleTree {
+    MerkleTree::with_keccak_leaves(EPOCH_OUTPUT_LOG2_SIZE, outputs.iter().map(MerkleTree::root))
+}
+
+/// Append-only Merkle tree of every output of a Cartesi Rollups v2 application, the tree libcmt keeps.
+///
+/// The leaf of an output is the Keccak-256 of the output, e.g. the ABI-encoded `Voucher(address,uint256,bytes)` call,
+/// and the leaves after the last output are zeroed, as in libcmt and the `LibMerkle32` library of the contracts, unlike
+/// the memory ranges of [`MerkleTree`]. Only the left siblings of the path of the next leaf are kept, so
+/// the tree takes constant space however many outputs it holds.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutputsTree {
+    length: u64,
+    /// Hash of the left sibling of each level on the path of the next leaf, valid for the levels where it is a left.
+    frontier: [[u8; 32]; OUTPUTS_TREE_HEIGHT],
+}
+
+impl OutputsTree {
+    pub fn new() -> Self {
+        Self {
+            length: 0,
+            frontier: [[0; 32]; OUTPUTS_TREE_HEIGHT],
+        }
+    }
+
+    /// Returns the number of outputs in the tree, which is the index of the next one.
+    pub fn len(&self) -> u64 {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Appends `output`, returning its index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tree is full.
+    pub fn push(&mut self, output: &[u8]) -> u64 {
+        let index = self.length;
+        let mut hash = keccak256(output);
+
+        assert!(index < 1 << OUTPUTS_TREE_HEIGHT, "outputs tree is full");
+
+        for level in 0..OUTPUTS_TREE_HEIGHT {
+            if (index >> level) & 1 == 0 {
+                self.frontier[level] = hash;
+                break;
+            }
+            hash = hash_pair(&self.frontier[level], &hash);
+        }
+        self.length += 1;
+
+        index
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        let pristine = pristine_hashes([0; 32], OUTPUTS_TREE_HEIGHT);
+
+        (0..OUTPUTS_TREE_HEIGHT).fold(pristine[0], |hash, level| {
+            let is_right = (self.length >> level) & 1 == 1;
+
+            match is_right {
+                true => hash_pair(&self.frontier[level], &hash),
+                false => hash_pair(&hash, &pristine[level]),
+            }
+        })
+    }
+}
+
+impl Default for OutputsTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    keccak256([*left, *right].concat())
+}
+
+/// Returns the hashes of the pristine nodes of each level of a tree of `height`, whose pristine leaf is `leaf`.
+fn pristine_hashes(leaf: [u8; 32], height: usize) -> Vec<[u8; 32]> {
+    let mut hashes = vec![leaf];
+    for level in 0..height {
+        hashes.push(hash_pair(&hashes[level], &hashes[level]));
+    }
+
+    hashes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_root_matches_empty_tree_hashes_of_contracts() {
+        // `MerkleV2.EMPTY_TREE_HASHES` at the log2 sizes of a word, 16 bytes and a Keccak-256 hash.
+        let empty_tree_hashes = [
+            "011b4d03dd8c01f1049143cf9c4c817e4b167f1d1b83e5c6f0f10d89ba1e7bce",
+            "4d9470a821fbe90117ec357e30bad9305732fb19ddf54a07dd3e29f440619254",
+            "ae39ce8537aca75e2eff3e38c98011dfe934e700a0967732fc07b430dd656a23",
+        ]
+        .map(|hash| <[u8; 32]>::try_from(hex::decode(hash).unwrap()).unwrap());
+        let words = pristine_hashes(keccak256([0; 1 << WORD_LOG2_SIZE]), 2);
+
+        assert_eq!(empty_tree_hashes.to_vec(), words);
+        assert_eq!(empty_tree_hashes[2], memory_root(&[0; 32]));
+        assert_eq!(
+            empty_tree_hashes[2],
+            MerkleTree::new(OUTPUT_METADATA_LOG2_SIZE, []).proof(0).unwrap()[0]
+        );
+    }
+
+    #[test]
+    fn test_tree_pads_range_with_zeroed_words() {
+        let word = [0x11; 32];
+        let tree = MerkleTree::new(KECCAK_LOG2_SIZE + 2, [word]);
+        let zeroed = memory_root(&[0; 32]);
+
+        assert_eq!(2, tree.height());
+        assert_eq!(
+            hash_pair(&hash_pair(&memory_root(&word), &zeroed), &hash_pair(&zeroed, &zeroed)),
+            tree.root()
+        );
+        assert_eq!(
+            keccak256([0; 32]),
+            MerkleTree::with_keccak_leaves(KECCAK_LOG2_SIZE + 2, [])
+                .proof(0)
+                .unwrap()[0]
+        );
+        assert_eq!(
+            MerkleTree::new(KECCAK_LOG2_SIZE + 2, [[0; 32]]).root(),
+            MerkleTree::new(KECCAK_LOG2_SIZE + 2, []).root()
+        );
+        assert_eq!(None, tree.proof(4));
+    }
+
+    #[test]
+    fn test_proofs_rebuild_tree_root() {
+        let words: Vec<_> = (0..5u8).map(|byte| [byte; 32]).collect();
+        let tree = MerkleTree::new(OUTPUT_METADATA_LOG2_SIZE, words.clone());
+
+        for (index, word) in words.iter().enumerate() {
+            let proof = tree.proof(index as u64).unwrap();
+
+            assert_eq!(16, proof.len());
+            assert_eq!(
+                tree.root(),
+                root_after_replacement(index as u64, memory_root(word), &proof)
+            );
+        }
+    }
+
+    #[test]
+    fn test_outputs_tree_matches_proofs_of_its_outputs() {
+        let pristine = pristine_hashes([0; 32], OUTPUTS_TREE_HEIGHT);
+        let outputs = [b"first".as_slice(), b"second", b"third"];
+        let mut tree = OutputsTree::new();
+
+        assert_eq!(pristine[OUTPUTS_TREE_HEIGHT], tree.root());
+
+        for (index, output) in outputs.iter().enumerate() {
+            assert_eq!(index as u64, tree.push(output));
+        }
+
+        let [first, second, third] = outputs.map(keccak256);
+        let mut siblings = pristine[..OUTPUTS_TREE_HEIGHT].to_vec();
+        siblings[1] = hash_pair(&first, &second);
+
+        assert_eq!(3, tree.len());
+        assert_eq!(root_after_replacement(2, third, &siblings), tree.root());
+    }
+
+    #[test]
+    fn test_outputs_tree_pads_with_zero_hashes_of_libcmt() {
+        // Roots of the zeroed subtrees of heights 0 to 3 in libcmt's `cmt_merkle` and `LibMerkle32`.
+        let zero_hashes = [
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "ad3228b676f7d3cd4284a5443f17f1962b36e491b30a40b2405849e597ba5fb5",
+            "b4c11951957c6f8f642c4af61cd6b24640fec6dc7fc607ee8206a99e92410d30",
+            "21ddb9a356815c3fac1026b6dec5df3124afbadb485c9ba5a3e3398a04b7ba85",
+        ]
+        .map(|hash| <[u8; 32]>::try_from(hex::decode(hash).unwrap()).unwrap());
+        let zero_hashes_table = pristine_hashes([0; 32], OUTPUTS_TREE_HEIGHT);
+        let output = b"output";
+        let mut tree = OutputsTree::new();
+
+        assert_eq!(zero_hashes.to_vec(), zero_hashes_table[..4]);
+        assert_eq!(zero_hashes_table[OUTPUTS_TREE_HEIGHT], tree.root());
+
+        tree.push(output);
+
+        assert_eq!(
+            zero_hashes_table[..OUTPUTS_TREE_HEIGHT]
+                .iter()
+                .fold(keccak256(output), |hash, zero| hash_pair(&hash, zero)),
+            tree.root()
+        );
+    }
+
+    #[test]
+    fn test_output_proofs_validate_against_claim() {
+        let destination = Address::new([0xaa; 20]);
+        let machine_state_hash = [0x55; 32];
+        let mut epoch = Epoch::new();
+
+        epoch.add_input(&[], &[b"first".to_vec()]);
+        epoch.add_input(&[], &[]);
+        epoch.add_input(
+            &[(destination, b"one".to_vec()), (destination, b"two".to_vec())],
+            &[b"second".to_vec(), b"third".to_vec()],
+        );
+
+        let claim = epoch.claim(&machine_state_hash);
+        let voucher = epoch.voucher_proof(2, 1, &machine_state_hash).unwrap();
+        let notice = epoch.notice_proof(0, 0, &machine_state_hash).unwrap();
+
+        assert_eq!(
+            keccak256([epoch.vouchers_root(), epoch.notices_root(), machine_state_hash].concat()),
+            claim
+        );
+        assert!(voucher.validate_voucher(&destination, b"two", &claim));
+        assert!(!voucher.validate_voucher(&destination, b"one", &claim));
+        assert!(!voucher.validate_notice(b"two", &claim));
+        assert!(notice.validate_notice(b"first", &claim));
+        assert!(!notice.validate_notice(b"first", &[0; 32]));
+        assert_eq!(None, epoch.notice_proof(1, 0, &machine_state_hash));
+        assert_eq!(None, epoch.voucher_proof(3, 0, &machine_state_hash));
+    }
+}
@@ -0,0 +1,483 @@
+//! Items in this module decode the inputs added by the Cartesi portals.
+//!
+//! A portal transfers assets to the DApp on the base layer and then adds an input describing the deposit, so the
+//! sender of such input is the portal itself. The payload is a packed encoding of the deposit, which [`Portals`]
+//! tells apart from the inputs sent by ordinary users. The `DAppAddressRelay` works the same way, except its input
+//! carries the address of the DApp contract.
+//!
+//! # Examples
+//!
+//! ```
+//! # use cartesi_rollups_evm_utils::portals::{Deposit, Portals};
+//! # use cartesi_rollups_evm_utils::{Address, U256};
+//! let portals = Portals::default();
+//! let sender = Address::new([0x11; 20]);
+//!
+//! let mut payload = sender.to_bytes().to_vec();
+//! payload.extend(U256::from(1_000u16).to_be_bytes());
+//!
+//! match portals.parse(&portals.ether, &payload).unwrap() {
+//!     Some(Deposit::Ether(deposit)) => assert_eq!(U256::from(1_000u16), deposit.value),
+//!     _ => unreachable!(),
+//! }
+//!
+//! assert_eq!(None, portals.parse(&sender, &payload).unwrap());
+//! ```
+use crate::abi::{self, AbiError, ParamType, Token};
+use crate::{Address, U256};
+use thiserror::Error;
+
+/// Defines errors of decoding deposits.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum DepositError {
+    #[error("deposit payload ended after {actual} bytes, expected at least {expected}")]
+    TooShort { expected: usize, actual: usize },
+    #[error("invalid deposit flag {0}")]
+    InvalidFlag(u8),
+    #[error("invalid deposit data")]
+    InvalidData(#[from] AbiError),
+}
+
+/// Addresses of the portals, used to recognize which inputs are deposits.
+///
+/// The default holds the addresses of the portals deployed by Cartesi Rollups v1, which are the same on every chain.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Portals {
+    pub ether: Address,
+    pub erc20: Address,
+    pub erc721: Address,
+    pub erc1155_single: Address,
+    pub erc1155_batch: Address,
+    pub dapp_address_relay: Address,
+}
+
+impl Portals {
+    pub const ETHER: Address = Address::new([
+        0xff, 0xdb, 0xe4, 0x3d, 0x4c, 0x85, 0x5b, 0xf7, 0xe0, 0xf1, 0x05, 0xc4, 0x00, 0xa5, 0x08, 0x57, 0xf5, 0x3a,
+        0xb0, 0x44,
+    ]);
+    pub const ERC20: Address = Address::new([
+        0x9c, 0x21, 0xae, 0xb2, 0x09, 0x3c, 0x32, 0xdd, 0xbc, 0x53, 0xee, 0xf2, 0x4b, 0x87, 0x3b, 0xdc, 0xd1, 0xad,
+        0xa1, 0xdb,
+    ]);
+    pub const ERC721: Address = Address::new([
+        0x23, 0x7f, 0x8d, 0xd0, 0x94, 0xc0, 0xe4, 0x7f, 0x42, 0x36, 0xf1, 0x2b, 0x4f, 0xa0, 0x1d, 0x6d, 0xae, 0x89,
+        0xfb, 0x87,
+    ]);
+    pub const ERC1155_SINGLE: Address = Address::new([
+        0x7c, 0xfb, 0x01, 0x93, 0xca, 0x87, 0xeb, 0x6e, 0x48, 0x05, 0x68, 0x85, 0xe0, 0x26, 0x55, 0x2c, 0x3a, 0x94,
+        0x1f, 0xc4,
+    ]);
+    pub const ERC1155_BATCH: Address = Address::new([
+        0xed, 0xb5, 0x38, 0x60, 0xa6, 0xb5, 0x2b, 0xbb, 0x75, 0x61, 0xad, 0x59, 0x64, 0x16, 0xee, 0x99, 0x65, 0xb0,
+        0x55, 0xaa,
+    ]);
+    pub const DAPP_ADDRESS_RELAY: Address = Address::new([
+        0xf5, 0xde, 0x34, 0xd6, 0xbb, 0xc0, 0x44, 0x6e, 0x2a, 0x45, 0x71, 0x9e, 0x71, 0x8e, 0xfe, 0xba, 0xae, 0x17,
+        0x9d, 0xae,
+    ]);
+
+    /// Decodes the deposit in `payload` if `msg_sender` is one of the portals.
+    ///
+    /// Returns `Ok(None)` for the inputs of any other sender. Arguments are the sender from the metadata and the payload
+    /// of an advance state request.
+    pub fn parse(&self, msg_sender: &Address, payload: &[u8]) -> Result<Option<Deposit>, DepositError> {
+        let deposit = match *msg_sender {
+            sender if sender == self.ether => Deposit::Ether(EtherDeposit::decode(payload)?),
+            sender if sender == self.erc20 => Deposit::Erc20(Erc20Deposit::decode(payload)?),
+            sender if sender == self.erc721 => Deposit::Erc721(Erc721Deposit::decode(payload)?),
+            sender if sender == self.erc1155_single => Deposit::Erc1155Single(Erc1155SingleDeposit::decode(payload)?),
+            sender if sender == self.erc1155_batch => Deposit::Erc1155Batch(Erc1155BatchDeposit::decode(payload)?),
+            _ => return Ok(None),
+        };
+
+        Ok(Some(deposit))
+    }
+
+    /// Decodes the address of the DApp contract in `payload` if `msg_sender` is the `DAppAddressRelay`.
+    ///
+    /// Returns `Ok(None)` for the inputs of any other sender.
+    pub fn parse_dapp_address(&self, msg_sender: &Address, payload: &[u8]) -> Result<Option<Address>, DepositError> {
+        match *msg_sender == self.dapp_address_relay {
+            true => Packed::new(payload).address().map(Some),
+            false => Ok(None),
+        }
+    }
+}
+
+impl Default for Portals {
+    fn default() -> Self {
+        Self {
+            ether: Self::ETHER,
+            erc20: Self::ERC20,
+            erc721: Self::ERC721,
+            erc1155_single: Self::ERC1155_SINGLE,
+            erc1155_batch: Self::ERC1155_BATCH,
+            dapp_address_relay: Self::DAPP_ADDRESS_RELAY,
+        }
+    }
+}
+
+/// Deposit decoded by [`Portals::parse`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Deposit {
+    Ether(EtherDeposit),
+    Erc20(Erc20Deposit),
+    Erc721(Erc721Deposit),
+    Erc1155Single(Erc1155SingleDeposit),
+    Erc1155Batch(Erc1155BatchDeposit),
+}
+
+impl Deposit {
+    /// Returns the account on the base layer that made the deposit.
+    pub fn sender(&self) -> &Address {
+        match self {
+            Deposit::Ether(deposit) => &deposit.sender,
+            Deposit::Erc20(deposit) => &deposit.sender,
+            Deposit::Erc721(deposit) => &deposit.sender,
+            Deposit::Erc1155Single(deposit) => &deposit.sender,
+            Deposit::Erc1155Batch(deposit) => &deposit.sender,
+        }
+    }
+}
+
+/// Ether deposited through the `EtherPortal`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EtherDeposit {
+    pub sender: Address,
+    /// Amount of Wei.
+    pub value: U256,
+    pub exec_layer_data: Vec<u8>,
+}
+
+impl EtherDeposit {
+    pub fn decode(payload: &[u8]) -> Result<Self, DepositError> {
+        let mut packed = Packed::new(payload);
+
+        Ok(Self {
+            sender: packed.address()?,
+            value: packed.uint()?,
+            exec_layer_data: packed.rest().to_vec(),
+        })
+    }
+}
+
+/// ERC-20 tokens deposited through the `ERC20Portal`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Erc20Deposit {
+    /// Whether the transfer of tokens to the DApp succeeded. Nothing was deposited if it is `false`.
+    pub success: bool,
+    pub token: Address,
+    pub sender: Address,
+    pub amount: U256,
+    pub exec_layer_data: Vec<u8>,
+}
+
+impl Erc20Deposit {
+    pub fn decode(payload: &[u8]) -> Result<Self, DepositError> {
+        let mut packed = Packed::new(payload);
+
+        Ok(Self {
+            success: packed.bool()?,
+            token: packed.address()?,
+            sender: packed.address()?,
+            amount: packed.uint()?,
+            exec_layer_data: packed.rest().to_vec(),
+        })
+    }
+}
+
+/// ERC-721 token deposited through the `ERC721Portal`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Erc721Deposit {
+    pub token: Address,
+    pub sender: Address,
+    pub token_id: U256,
+    pub base_layer_data: Vec<u8>,
+    pub exec_layer_data: Vec<u8>,
+}
+
+impl Erc721Deposit {
+    pub fn decode(payload: &[u8]) -> Result<Self, DepositError> {
+        let mut packed = Packed::new(payload);
+        let token = packed.address()?;
+        let sender = packed.address()?;
+        let token_id = packed.uint()?;
+        let (base_layer_data, exec_layer_data) = layer_data(packed.rest())?;
+
+        Ok(Self {
+            token,
+            sender,
+            token_id,
+            base_layer_data,
+            exec_layer_data,
+        })
+    }
+}
+
+/// ERC-1155 tokens of a single id deposited through the `ERC1155SinglePortal`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Erc1155SingleDeposit {
+    pub token: Address,
+    pub sender: Address,
+    pub token_id: U256,
+    pub value: U256,
+    pub base_layer_data: Vec<u8>,
+    pub exec_layer_data: Vec<u8>,
+}
+
+impl Erc1155SingleDeposit {
+    pub fn decode(payload: &[u8]) -> Result<Self, DepositError> {
+        let mut packed = Packed::new(payload);
+        let token = packed.address()?;
+        let sender = packed.address()?;
+        let token_id = packed.uint()?;
+        let value = packed.uint()?;
+        let (base_layer_data, exec_layer_data) = layer_data(packed.rest())?;
+
+        Ok(Self {
+            token,
+            sender,
+            token_id,
+            value,
+            base_layer_data,
+            exec_layer_data,
+        })
+    }
+}
+
+/// ERC-1155 tokens of several ids deposited through the `ERC1155BatchPortal`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Erc1155BatchDeposit {
+    pub token: Address,
+    pub sender: Address,
+    pub token_ids: Vec<U256>,
+    /// Amount of each of `token_ids`, in the same order.
+    pub values: Vec<U256>,
+    pub base_layer_data: Vec<u8>,
+    pub exec_layer_data: Vec<u8>,
+}
+
+impl Erc1155BatchDeposit {
+    pub fn decode(payload: &[u8]) -> Result<Self, DepositError> {
+        let mut packed = Packed::new(payload);
+        let token = packed.address()?;
+        let sender = packed.address()?;
+        let types = ParamType::parse_list("uint256[],uint256[],bytes,bytes").unwrap();
+        let mut tokens = abi::decode(&types, packed.rest())?.into_iter();
+        let mut uints = || -> Vec<U256> {
+            let array = tokens.next().and_then(Token::into_array).unwrap_or_default();
+
+            array.into_iter().filter_map(Token::into_uint).collect()
+        };
+        let token_ids = uints();
+        let values = uints();
+        let mut bytes = || tokens.next().and_then(Token::into_bytes).unwrap_or_default();
+
+        if token_ids.len() != values.len() {
+            return Err(AbiError::InvalidData("token ids and values differ in length").into());
+        }
+
+        Ok(Self {
+            token,
+            sender,
+            token_ids,
+            values,
+            base_layer_data: bytes(),
+            exec_layer_data: bytes(),
+        })
+    }
+}
+
+/// Decodes the `abi.encode(baseLayerData, execLayerData)` trailing the NFT deposits.
+fn layer_data(data: &[u8]) -> Result<(Vec<u8>, Vec<u8>), DepositError> {
+    let mut tokens = abi::decode(&[ParamType::Bytes, ParamType::Bytes], data)?.into_iter();
+    let mut bytes = || tokens.next().and_then(Token::into_bytes).unwrap_or_default();
+
+    Ok((bytes(), bytes()))
+}
+
+/// Reader of fields encoded by `abi.encodePacked`.
+struct Packed<'a> {
+    payload: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Packed<'a> {
+    fn new(payload: &'a [u8]) -> Self {
+        Self { payload, position: 0 }
+    }
+
+    fn take(&mut self, length: usize) -> Result<&'a [u8], DepositError> {
+        let end = self.position + length;
+        let bytes = self.payload.get(self.position..end).ok_or(DepositError::TooShort {
+            expected: end,
+            actual: self.payload.len(),
+        })?;
+        self.position = end;
+
+        Ok(bytes)
+    }
+
+    fn bool(&mut self) -> Result<bool, DepositError> {
+        match self.take(1)?[0] {
+            0 => Ok(false),
+            1 => Ok(true),
+            flag => Err(DepositError::InvalidFlag(flag)),
+        }
+    }
+
+    fn address(&mut self) -> Result<Address, DepositError> {
+        Ok(Address::try_from(self.take(Address::LENGTH)?).unwrap())
+    }
+
+    fn uint(&mut self) -> Result<U256, DepositError> {
+        Ok(U256::from_be_bytes(self.take(U256::LENGTH)?.try_into().unwrap()))
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        let rest = &self.payload[self.position..];
+        self.position = self.payload.len();
+
+        rest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::encode;
+
+    const TOKEN: Address = Address::new([0xaa; 20]);
+    const SENDER: Address = Address::new([0xbb; 20]);
+
+    fn packed(fields: &[&[u8]]) -> Vec<u8> {
+        fields.concat()
+    }
+
+    #[test]
+    fn test_parsing_ignores_inputs_of_other_senders() {
+        let portals = Portals::default();
+
+        assert_eq!(Ok(None), portals.parse(&SENDER, b"hello"));
+        assert_eq!(Ok(None), portals.parse(&Portals::DAPP_ADDRESS_RELAY, b"hello"));
+        assert_eq!("0xFfdbe43d4c855BF7e0f105c400A50857f53AB044", portals.ether.to_string());
+        assert_eq!(
+            "0xedB53860A6B52bbb7561Ad596416ee9965B055Aa",
+            portals.erc1155_batch.to_string()
+        );
+    }
+
+    #[test]
+    fn test_relay_input_decodes_dapp_address() {
+        let portals = Portals::default();
+
+        assert_eq!(
+            Ok(Some(TOKEN)),
+            portals.parse_dapp_address(&Portals::DAPP_ADDRESS_RELAY, TOKEN.as_bytes())
+        );
+        assert_eq!(Ok(None), portals.parse_dapp_address(&SENDER, TOKEN.as_bytes()));
+        assert_eq!(
+            "0xF5DE34d6BbC0446E2a45719E718efEbaaE179daE",
+            portals.dapp_address_relay.to_string()
+        );
+    }
+
+    #[test]
+    fn test_fungible_deposits_decode_packed_fields() {
+        let portals = Portals::default();
+        let amount = U256::from(42u8).to_be_bytes();
+
+        let ether = portals.parse(&Portals::ETHER, &packed(&[SENDER.as_bytes(), &amount, b"data"]));
+        let erc20 = portals.parse(
+            &Portals::ERC20,
+            &packed(&[&[1], TOKEN.as_bytes(), SENDER.as_bytes(), &amount, b"data"]),
+        );
+
+        assert_eq!(
+            Ok(Some(Deposit::Ether(EtherDeposit {
+                sender: SENDER,
+                value: U256::from(42u8),
+                exec_layer_data: b"data".to_vec(),
+            }))),
+            ether
+        );
+        assert_eq!(
+            Ok(Some(Deposit::Erc20(Erc20Deposit {
+                success: true,
+                token: TOKEN,
+                sender: SENDER,
+                amount: U256::from(42u8),
+                exec_layer_data: b"data".to_vec(),
+            }))),
+            erc20
+        );
+    }
+
+    #[test]
+    fn test_nft_deposits_decode_layer_data() {
+        let portals = Portals::default();
+        let id = U256::from(7u8);
+        let layer_data = encode(&[Token::Bytes(b"base".to_vec()), Token::Bytes(b"exec".to_vec())]);
+        let batch_data = encode(&[
+            Token::Array(vec![Token::Uint(id), Token::Uint(U256::from(8u8))]),
+            Token::Array(vec![Token::Uint(U256::from(1u8)), Token::Uint(U256::from(2u8))]),
+            Token::Bytes(b"base".to_vec()),
+            Token::Bytes(vec![]),
+        ]);
+
+        let erc721 = portals.parse(
+            &Portals::ERC721,
+            &packed(&[TOKEN.as_bytes(), SENDER.as_bytes(), &id.to_be_bytes(), &layer_data]),
+        );
+        let single = Erc1155SingleDeposit::decode(&packed(&[
+            TOKEN.as_bytes(),
+            SENDER.as_bytes(),
+            &id.to_be_bytes(),
+            &U256::from(5u8).to_be_bytes(),
+            &layer_data,
+        ]));
+        let batch = Erc1155BatchDeposit::decode(&packed(&[TOKEN.as_bytes(), SENDER.as_bytes(), &batch_data]));
+
+        assert_eq!(
+            Ok(Some(Deposit::Erc721(Erc721Deposit {
+                token: TOKEN,
+                sender: SENDER,
+                token_id: id,
+                base_layer_data: b"base".to_vec(),
+                exec_layer_data: b"exec".to_vec(),
+            }))),
+            erc721
+        );
+        assert_eq!(U256::from(5u8), single.unwrap().value);
+        assert_eq!(
+            Ok(Erc1155BatchDeposit {
+                token: TOKEN,
+                sender: SENDER,
+                token_ids: vec![id, U256::from(8u8)],
+                values: vec![U256::from(1u8), U256::from(2u8)],
+                base_layer_data: b"base".to_vec(),
+                exec_layer_data: vec![],
+            }),
+            batch
+        );
+    }
+
+    #[test]
+    fn test_decoding_rejects_malformed_deposits() {
+        let portals = Portals::default();
+
+        assert_eq!(
+            Err(DepositError::TooShort {
+                expected: 52,
+                actual: 20
+            }),
+            portals.parse(&Portals::ETHER, SENDER.as_bytes())
+        );
+        assert_eq!(Err(DepositError::InvalidFlag(2)), Erc20Deposit::decode(&[2; 73]));
+        assert!(matches!(
+            Erc721Deposit::decode(&packed(&[TOKEN.as_bytes(), SENDER.as_bytes(), &[0; 32]])),
+            Err(DepositError::InvalidData(_))
+        ));
+    }
+}
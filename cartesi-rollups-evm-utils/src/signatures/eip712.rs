@@ -0,0 +1,359 @@
+use crate::abi::{encode, fits_signed, ParamType, Token};
+use crate::signatures::{Signature, SignatureError};
+use crate::{keccak256, Address, U256};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+
+type Result<T> = std::result::Result<T, SignatureError>;
+
+/// Name of the struct type of the domain.
+const DOMAIN_TYPE: &str = "EIP712Domain";
+
+/// Fields of the domain in the order the type is derived when [`TypedData::types`] lacks it.
+const DOMAIN_FIELDS: [(&str, &str); 5] = [
+    ("name", "string"),
+    ("version", "string"),
+    ("chainId", "uint256"),
+    ("verifyingContract", "address"),
+    ("salt", "bytes32"),
+];
+
+/// Member of a struct type of [`TypedData`].
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TypedField {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub r#type: String,
+}
+
+/// [EIP-712] typed data in the JSON format of `eth_signTypedData_v4`, which is what wallets sign.
+///
+/// Values of the message and domain follow that format too: addresses and byte arrays are hex strings, integers are
+/// numbers or decimal or hex strings. A hex string of a signed integer holds its 256-bit two's complement.
+///
+/// [EIP-712]: https://eips.ethereum.org/EIPS/eip-712
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypedData {
+    pub types: BTreeMap<String, Vec<TypedField>>,
+    pub primary_type: String,
+    pub domain: Value,
+    pub message: Value,
+}
+
+impl TypedData {
+    /// Returns the hash an account signs, that is `keccak256("\x19\x01" ‖ domainSeparator ‖ hashStruct(message))`.
+    pub fn hash(&self) -> Result<[u8; 32]> {
+        let domain_separator = self.domain_separator()?;
+        let message = self.hash_struct(&self.primary_type, &self.message)?;
+
+        Ok(keccak256(
+            [&[0x19, 0x01], domain_separator.as_slice(), &message].concat(),
+        ))
+    }
+
+    /// Recovers the address of the account that signed this typed data.
+    pub fn recover(&self, signature: &Signature) -> Result<Address> {
+        signature.recover(&self.hash()?)
+    }
+
+    pub fn domain_separator(&self) -> Result<[u8; 32]> {
+        self.hash_struct(DOMAIN_TYPE, &self.domain)
+    }
+
+    /// Returns `hashStruct` of `value` of the struct type `name`.
+    pub fn hash_struct(&self, name: &str, value: &Value) -> Result<[u8; 32]> {
+        let fields = self.fields(name)?;
+        let type_hash = keccak256(self.encode_type(name)?);
+        let mut encoded = type_hash.to_vec();
+
+        for field in fields.iter() {
+            let value = value
+                .get(&field.name)
+                .ok_or_else(|| invalid(format!("missing {}.{}", name, field.name)))?;
+            encoded.extend(self.encode_value(&field.r#type, value)?);
+        }
+
+        Ok(keccak256(encoded))
+    }
+
+    /// Returns `encodeType` of the struct type `name`, e.g. `Mail(Person from,Person to,string contents)Person(...)`.
+    pub fn encode_type(&self, name: &str) -> Result<String> {
+        let mut dependencies = BTreeSet::new();
+        self.collect_dependencies(name, &mut dependencies)?;
+        dependencies.remove(name);
+
+        [name]
+            .into_iter()
+            .chain(dependencies.iter().map(String::as_str))
+            .map(|name| {
+                let fields = self.fields(name)?;
+                let members: Vec<_> = fields
+                    .iter()
+                    .map(|field| format!("{} {}", field.r#type, field.name))
+                    .collect();
+
+                Ok(format!("{}({})", name, members.join(",")))
+            })
+            .collect()
+    }
+
+    fn fields(&self, name: &str) -> Result<Vec<TypedField>> {
+        match (self.types.get(name), name) {
+            (Some(fields), _) => Ok(fields.clone()),
+            (None, DOMAIN_TYPE) => Ok(DOMAIN_FIELDS
+                .iter()
+                .filter(|(name, _)| self.domain.get(name).is_some())
+                .map(|(name, r#type)| TypedField {
+                    name: name.to_string(),
+                    r#type: r#type.to_string(),
+                })
+                .collect()),
+            (None, _) => Err(invalid(format!("unknown type {}", name))),
+        }
+    }
+
+    fn collect_dependencies(&self, name: &str, dependencies: &mut BTreeSet<String>) -> Result<()> {
+        if !dependencies.insert(name.to_owned()) {
+            return Ok(());
+        }
+
+        for field in self.fields(name)? {
+            let base = field.r#type.split('[').next().unwrap_or_default();
+
+            if self.types.contains_key(base) {
+                self.collect_dependencies(base, dependencies)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns `encodeData` of a single member of type `r#type`.
+    fn encode_value(&self, r#type: &str, value: &Value) -> Result<[u8; 32]> {
+        if let Some(inner) = r#type.strip_suffix(']') {
+            let inner = &inner[..inner
+                .rfind('[')
+                .ok_or_else(|| invalid(format!("invalid type {}", r#type)))?];
+            let items = value
+                .as_array()
+                .ok_or_else(|| invalid(format!("expected array of {}", inner)))?;
+            let encoded = items
+                .iter()
+                .map(|item| self.encode_value(inner, item))
+                .collect::<Result<Vec<_>>>()?;
+
+            return Ok(keccak256(encoded.concat()));
+        }
+
+        if self.types.contains_key(r#type) {
+            return self.hash_struct(r#type, value);
+        }
+
+        let param_type = ParamType::from_str(r#type).map_err(|_| invalid(format!("invalid type {}", r#type)))?;
+        let mismatch = || invalid(format!("expected {} but got {}", r#type, value));
+        let token = match &param_type {
+            ParamType::String => return Ok(keccak256(value.as_str().ok_or_else(mismatch)?)),
+            ParamType::Bytes => return Ok(keccak256(hex_bytes(value).ok_or_else(mismatch)?)),
+            ParamType::Address => Token::Address(
+                value
+                    .as_str()
+                    .and_then(|address| Address::from_str(address).ok())
+                    .ok_or_else(mismatch)?,
+            ),
+            ParamType::Bool => Token::Bool(value.as_bool().ok_or_else(mismatch)?),
+            ParamType::Uint(_) => Token::Uint(uint(value).ok_or_else(mismatch)?),
+            ParamType::Int(bits) => Token::Int(int(value, *bits).ok_or_else(mismatch)?),
+            ParamType::FixedBytes(length) => {
+                let mut bytes = hex_bytes(value)
+                    .filter(|bytes| bytes.len() <= *length)
+                    .ok_or_else(mismatch)?;
+                bytes.resize(*length, 0);
+
+                Token::FixedBytes(bytes)
+            }
+            _ => return Err(mismatch()),
+        };
+        if !token.type_check(&param_type) {
+            return Err(mismatch());
+        }
+
+        Ok(encode(&[token]).try_into().unwrap())
+    }
+}
+
+fn invalid(message: String) -> SignatureError {
+    SignatureError::InvalidTypedData(message)
+}
+
+fn hex_bytes(value: &Value) -> Option<Vec<u8>> {
+    let digits = value.as_str()?;
+
+    hex::decode(digits.strip_prefix("0x").unwrap_or(digits)).ok()
+}
+
+fn uint(value: &Value) -> Option<U256> {
+    match value {
+        Value::Number(number) => number.as_u64().map(U256::from),
+        Value::String(number) => U256::from_str(number).ok(),
+        _ => None,
+    }
+}
+
+/// Returns the two's complement of the integer `value` if it fits `bits`.
+fn int(value: &Value, bits: usize) -> Option<U256> {
+    let value = match value {
+        Value::Number(number) => match number.as_i64() {
+            Some(number) => signed(number < 0, U256::from(number.unsigned_abs()))?,
+            None => U256::from(number.as_u64()?),
+        },
+        Value::String(number) if number.starts_with("0x") => U256::from_str(number).ok()?,
+        Value::String(number) => match number.strip_prefix('-') {
+            Some(magnitude) => signed(true, U256::from_str(magnitude).ok()?)?,
+            None => signed(false, U256::from_str(number).ok()?)?,
+        },
+        _ => return None,
+    };
+
+    fits_signed(&value, bits).then_some(value)
+}
+
+/// Returns the two's complement of the integer of `magnitude`, unless it does not fit 256 bits.
+fn signed(negative: bool, magnitude: U256) -> Option<U256> {
+    let value = match negative {
+        true => U256::ZERO.overflowing_sub(magnitude).0,
+        false => magnitude,
+    };
+
+    ((value.bits() == 256) == (negative && !magnitude.is_zero())).then_some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn mail() -> TypedData {
+        serde_json::from_value(json!({
+            "types": {
+                "EIP712Domain": [
+                    { "name": "name", "type": "string" },
+                    { "name": "version", "type": "string" },
+                    { "name": "chainId", "type": "uint256" },
+                    { "name": "verifyingContract", "type": "address" }
+                ],
+                "Person": [
+                    { "name": "name", "type": "string" },
+                    { "name": "wallet", "type": "address" }
+                ],
+                "Mail": [
+                    { "name": "from", "type": "Person" },
+                    { "name": "to", "type": "Person" },
+                    { "name": "contents", "type": "string" }
+                ]
+            },
+            "primaryType": "Mail",
+            "domain": {
+                "name": "Ether Mail",
+                "version": "1",
+                "chainId": 1,
+                "verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"
+            },
+            "message": {
+                "from": { "name": "Cow", "wallet": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826" },
+                "to": { "name": "Bob", "wallet": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB" },
+                "contents": "Hello, Bob!"
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_typed_data_matches_eip712_example() {
+        let typed_data = mail();
+        let signature = Signature {
+            r: hex::decode("4355c47d63924e8a72e509b65029052eb6c299d53a04e167c5775fd466751c9d")
+                .unwrap()
+                .try_into()
+                .unwrap(),
+            s: hex::decode("07299936d304c153f6443dfa05f40ff007d72911b6f72307f996231605b91562")
+                .unwrap()
+                .try_into()
+                .unwrap(),
+            v: 28,
+        };
+
+        assert_eq!(
+            "Mail(Person from,Person to,string contents)Person(string name,address wallet)",
+            typed_data.encode_type("Mail").unwrap()
+        );
+        assert_eq!(
+            "f2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f",
+            hex::encode(typed_data.domain_separator().unwrap())
+        );
+        assert_eq!(
+            "be609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2",
+            hex::encode(typed_data.hash().unwrap())
+        );
+        assert_eq!(
+            Ok(Address::from_str("0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826").unwrap()),
+            typed_data.recover(&signature)
+        );
+    }
+
+    #[test]
+    fn test_domain_type_is_derived_when_missing() {
+        let mut typed_data = mail();
+        let expected = typed_data.domain_separator().unwrap();
+        typed_data.types.remove(DOMAIN_TYPE);
+
+        assert_eq!(Ok(expected), typed_data.domain_separator());
+    }
+
+    #[test]
+    fn test_malformed_values_are_rejected() {
+        let mut typed_data = mail();
+        typed_data.message["from"]["wallet"] = json!("not an address");
+
+        assert!(matches!(typed_data.hash(), Err(SignatureError::InvalidTypedData(_))));
+
+        typed_data.message.as_object_mut().unwrap().remove("contents");
+
+        assert!(matches!(typed_data.hash(), Err(SignatureError::InvalidTypedData(_))));
+    }
+
+    #[test]
+    fn test_signed_integers_accept_numbers_and_decimal_and_hex_strings() {
+        const TWO_POW_255: &str = "57896044618658097711785492504343953926634992332820282019728792003956564819968";
+
+        let mut typed_data = mail();
+        typed_data.types.insert(
+            "Trade".to_owned(),
+            vec![
+                TypedField {
+                    name: "delta".to_owned(),
+                    r#type: "int256".to_owned(),
+                },
+                TypedField {
+                    name: "fee".to_owned(),
+                    r#type: "int8".to_owned(),
+                },
+            ],
+        );
+        let hash = |delta: Value, fee: Value| typed_data.hash_struct("Trade", &json!({ "delta": delta, "fee": fee }));
+        let minus_one = format!("0x{}", "f".repeat(64));
+        let min = format!("0x8{}", "0".repeat(63));
+
+        assert_eq!(hash(json!(-1), json!(-1)), hash(json!(minus_one), json!("-1")));
+        assert_eq!(
+            hash(json!(min), json!(127)),
+            hash(json!(format!("-{}", TWO_POW_255)), json!("0x7f"))
+        );
+        assert!(hash(json!(TWO_POW_255), json!(0)).is_err());
+        assert!(hash(json!(0), json!("0xff")).is_err());
+        assert!(hash(json!(0), json!(128)).is_err());
+        assert_eq!(hash(json!(0), json!(minus_one)), hash(json!(0), json!(-1)));
+    }
+}
@@ -0,0 +1,199 @@
+//! Items in this module authenticate payloads signed by Ethereum accounts.
+//!
+//! When a relayer adds inputs on behalf of users, the sender in the metadata is the relayer. The user then signs the
+//! payload, either as an [EIP-191] personal message or as [EIP-712] typed data, and the DApp recovers the signer from
+//! the signature. A [`NonceTracker`] keeps a relayer from replaying the same signed payload.
+//!
+//! # Examples
+//!
+//! ```
+//! # use cartesi_rollups_evm_utils::signatures::{recover_personal, Signature};
+//! # use cartesi_rollups_evm_utils::Address;
+//! # use std::str::FromStr;
+//! let signature = Signature::from_str(
+//!     "0xb91467e570a6466aa9e9876cbcd013baba02900b8979d43fe208a4a4f339f5fd\
+//!      6007e74cd82e037b800186422fc2da167c747ef045e5d18a5f5d4300f8e1a0291c",
+//! )
+//! .unwrap();
+//!
+//! let signer = recover_personal(b"Some data", &signature).unwrap();
+//!
+//! assert_eq!(Address::from_str("0x2c7536E3605D9C16a7a3D7b1898e529396a65c23").unwrap(), signer);
+//! ```
+//!
+//! [EIP-191]: https://eips.ethereum.org/EIPS/eip-191
+//! [EIP-712]: https://eips.ethereum.org/EIPS/eip-712
+mod eip712;
+mod nonces;
+
+pub use eip712::*;
+pub use nonces::*;
+
+use crate::{keccak256, Address, ParseError};
+use k256::ecdsa::{RecoveryId, Signature as EcdsaSignature, VerifyingKey};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Defines errors of verifying signatures.
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum SignatureError {
+    #[error("invalid signature")]
+    InvalidSignature,
+    #[error("invalid recovery id {0}")]
+    InvalidRecoveryId(u8),
+    #[error("invalid signature encoding")]
+    InvalidEncoding(#[from] ParseError),
+    #[error("invalid typed data: {0}")]
+    InvalidTypedData(String),
+    #[error("nonce {actual} of {signer} was expected to be {expected}")]
+    InvalidNonce {
+        signer: Address,
+        expected: u64,
+        actual: u64,
+    },
+}
+
+/// Recoverable secp256k1 signature as produced by Ethereum wallets, that is `r`, `s` and `v` packed in 65 bytes.
+///
+/// Both the legacy `v` of 27 and 28 and the raw recovery id of 0 and 1 are accepted.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Signature {
+    pub r: [u8; 32],
+    pub s: [u8; 32],
+    pub v: u8,
+}
+
+impl Signature {
+    pub const LENGTH: usize = 65;
+
+    pub fn to_bytes(&self) -> [u8; Self::LENGTH] {
+        let mut bytes = [0; Self::LENGTH];
+        bytes[..32].copy_from_slice(&self.r);
+        bytes[32..64].copy_from_slice(&self.s);
+        bytes[64] = self.v;
+
+        bytes
+    }
+
+    /// Recovers the address of the account that signed `hash`.
+    ///
+    /// Signatures with a high `s` are rejected, as each of them has a twin with a low `s` signing the same hash.
+    pub fn recover(&self, hash: &[u8; 32]) -> Result<Address, SignatureError> {
+        let recovery_id = match self.v {
+            0 | 1 => self.v,
+            27 | 28 => self.v - 27,
+            v => return Err(SignatureError::InvalidRecoveryId(v)),
+        };
+        let recovery_id = RecoveryId::from_byte(recovery_id).ok_or(SignatureError::InvalidRecoveryId(self.v))?;
+        let signature = EcdsaSignature::from_scalars(self.r, self.s).map_err(|_| SignatureError::InvalidSignature)?;
+
+        if signature.normalize_s().is_some() {
+            return Err(SignatureError::InvalidSignature);
+        }
+
+        let key = VerifyingKey::recover_from_prehash(hash, &signature, recovery_id)
+            .map_err(|_| SignatureError::InvalidSignature)?;
+        let point = key.to_encoded_point(false);
+
+        Ok(Address::try_from(&keccak256(&point.as_bytes()[1..])[12..]).unwrap())
+    }
+}
+
+impl TryFrom<&[u8]> for Signature {
+    type Error = ParseError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        if bytes.len() != Self::LENGTH {
+            return Err(ParseError::InvalidLength {
+                expected: Self::LENGTH,
+                actual: bytes.len(),
+            });
+        }
+
+        Ok(Self {
+            r: bytes[..32].try_into().unwrap(),
+            s: bytes[32..64].try_into().unwrap(),
+            v: bytes[64],
+        })
+    }
+}
+
+impl FromStr for Signature {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits = s.strip_prefix("0x").unwrap_or(s);
+
+        Self::try_from(hex::decode(digits)?.as_slice())
+    }
+}
+
+impl fmt::Debug for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{}", hex::encode(self.to_bytes()))
+    }
+}
+
+/// Returns the hash an account signs as the [EIP-191] personal message `message`, what `personal_sign` does.
+///
+/// [EIP-191]: https://eips.ethereum.org/EIPS/eip-191
+pub fn eip191_hash(message: &[u8]) -> [u8; 32] {
+    let prefix = format!("\x19Ethereum Signed Message:\n{}", message.len());
+
+    keccak256([prefix.as_bytes(), message].concat())
+}
+
+/// Recovers the address of the account that signed `message` as a personal message.
+pub fn recover_personal(message: &[u8], signature: &Signature) -> Result<Address, SignatureError> {
+    signature.recover(&eip191_hash(message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIGNATURE: &str = "0xb91467e570a6466aa9e9876cbcd013baba02900b8979d43fe208a4a4f339f5fd\
+                             6007e74cd82e037b800186422fc2da167c747ef045e5d18a5f5d4300f8e1a0291c";
+
+    #[test]
+    fn test_personal_message_matches_web3_vector() {
+        let signature = Signature::from_str(SIGNATURE).unwrap();
+        let signer = Address::from_str("0x2c7536E3605D9C16a7a3D7b1898e529396a65c23").unwrap();
+
+        assert_eq!(
+            "1da44b586eb0729ff70a73c326926f6ed5a25f5b056e7f47fbc6e58d86871655",
+            hex::encode(eip191_hash(b"Some data"))
+        );
+        assert_eq!(Ok(signer), recover_personal(b"Some data", &signature));
+        assert_ne!(Ok(signer), recover_personal(b"Other data", &signature));
+    }
+
+    #[test]
+    fn test_recovery_rejects_malformed_signatures() {
+        let signature = Signature::from_str(SIGNATURE).unwrap();
+        let hash = eip191_hash(b"Some data");
+
+        assert_eq!(
+            Err(SignatureError::InvalidRecoveryId(29)),
+            Signature { v: 29, ..signature }.recover(&hash)
+        );
+        assert_eq!(
+            Err(SignatureError::InvalidSignature),
+            Signature {
+                r: [0; 32],
+                ..signature
+            }
+            .recover(&hash)
+        );
+        assert_eq!(
+            Err(SignatureError::InvalidSignature),
+            Signature {
+                s: [0xff; 32],
+                ..signature
+            }
+            .recover(&hash)
+        );
+        assert!(Signature::from_str("0x1234").is_err());
+    }
+}
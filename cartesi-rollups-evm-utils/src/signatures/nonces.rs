@@ -0,0 +1,62 @@
+use crate::signatures::SignatureError;
+use crate::Address;
+use std::collections::HashMap;
+
+/// Expected nonce of each signer, used to reject replayed signed payloads.
+///
+/// Each signer starts at nonce zero and every accepted payload has to carry the next nonce in sequence.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NonceTracker {
+    nonces: HashMap<Address, u64>,
+}
+
+impl NonceTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the nonce the next payload of `signer` has to carry.
+    pub fn next(&self, signer: &Address) -> u64 {
+        self.nonces.get(signer).copied().unwrap_or_default()
+    }
+
+    /// Consumes `nonce` of `signer` if it is the expected one, so the same payload cannot be accepted twice.
+    pub fn consume(&mut self, signer: &Address, nonce: u64) -> Result<(), SignatureError> {
+        let expected = self.next(signer);
+
+        if nonce != expected {
+            return Err(SignatureError::InvalidNonce {
+                signer: *signer,
+                expected,
+                actual: nonce,
+            });
+        }
+        self.nonces.insert(*signer, expected + 1);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replayed_nonce_is_rejected() {
+        let signer = Address::new([0x11; 20]);
+        let mut nonces = NonceTracker::new();
+
+        nonces.consume(&signer, 0).unwrap();
+
+        assert_eq!(
+            Err(SignatureError::InvalidNonce {
+                signer,
+                expected: 1,
+                actual: 0
+            }),
+            nonces.consume(&signer, 0)
+        );
+        assert_eq!(1, nonces.next(&signer));
+        assert_eq!(0, nonces.next(&Address::ZERO));
+    }
+}
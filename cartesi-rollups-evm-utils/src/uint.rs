@@ -0,0 +1,341 @@
+//! Items in this module define the 256-bit unsigned integer used by the EVM.
+use crate::ParseError;
+use serde::de::Visitor;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+/// 256-bit unsigned integer, the native word of the EVM.
+///
+/// Displays in decimal and parses from decimal or from hex with the `0x` prefix. Arithmetic is checked, so an
+/// overflow is reported as [`None`] instead of wrapping around.
+///
+/// # Examples
+///
+/// ```
+/// # use cartesi_rollups_evm_utils::U256;
+/// let wei: U256 = "1000000000000000000".parse().unwrap();
+///
+/// assert_eq!(Some(U256::from(2_000_000_000_000_000_000u128)), wei.checked_add(wei));
+/// assert_eq!(None, U256::ZERO.checked_sub(wei));
+/// ```
+#[derive(Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct U256([u64; 4]);
+
+impl U256 {
+    /// Number of bytes of the big-endian representation.
+    pub const LENGTH: usize = 32;
+
+    pub const ZERO: Self = Self([0; 4]);
+
+    pub const ONE: Self = Self([1, 0, 0, 0]);
+
+    pub const MAX: Self = Self([u64::MAX; 4]);
+
+    pub fn from_be_bytes(bytes: [u8; Self::LENGTH]) -> Self {
+        let mut limbs = [0; 4];
+
+        for (index, chunk) in bytes.chunks_exact(8).rev().enumerate() {
+            limbs[index] = u64::from_be_bytes(chunk.try_into().unwrap());
+        }
+
+        Self(limbs)
+    }
+
+    pub fn to_be_bytes(self) -> [u8; Self::LENGTH] {
+        let mut bytes = [0; Self::LENGTH];
+
+        for (index, chunk) in bytes.chunks_exact_mut(8).rev().enumerate() {
+            chunk.copy_from_slice(&self.0[index].to_be_bytes());
+        }
+
+        bytes
+    }
+
+    /// Interprets up to 32 big-endian `bytes` as a number.
+    pub fn from_be_slice(bytes: &[u8]) -> Result<Self, ParseError> {
+        if bytes.len() > Self::LENGTH {
+            return Err(ParseError::Overflow);
+        }
+
+        let mut buffer = [0; Self::LENGTH];
+        buffer[Self::LENGTH - bytes.len()..].copy_from_slice(bytes);
+
+        Ok(Self::from_be_bytes(buffer))
+    }
+
+    pub fn is_zero(&self) -> bool {
+        *self == Self::ZERO
+    }
+
+    /// Returns the number of bits needed to represent this number.
+    pub fn bits(&self) -> u32 {
+        (0..4)
+            .rev()
+            .find(|&index| self.0[index] != 0)
+            .map_or(0, |index| 64 * index as u32 + 64 - self.0[index].leading_zeros())
+    }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        let (sum, overflow) = self.overflowing_add(other);
+
+        (!overflow).then_some(sum)
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        let (difference, overflow) = self.overflowing_sub(other);
+
+        (!overflow).then_some(difference)
+    }
+
+    pub fn checked_mul(self, other: Self) -> Option<Self> {
+        let mut product = [0u64; 8];
+
+        for i in 0..4 {
+            let mut carry = 0u128;
+
+            for j in 0..4 {
+                let value = product[i + j] as u128 + self.0[i] as u128 * other.0[j] as u128 + carry;
+                product[i + j] = value as u64;
+                carry = value >> 64;
+            }
+            product[i + 4] = carry as u64;
+        }
+
+        match product[4..].iter().all(|&limb| limb == 0) {
+            true => Some(Self(product[..4].try_into().unwrap())),
+            false => None,
+        }
+    }
+
+    /// Adds `other` modulo 2^256, returning whether the addition overflowed.
+    pub fn overflowing_add(self, other: Self) -> (Self, bool) {
+        let mut limbs = [0; 4];
+        let mut carry = false;
+
+        for (index, limb) in limbs.iter_mut().enumerate() {
+            let (value, overflow_a) = self.0[index].overflowing_add(other.0[index]);
+            let (value, overflow_b) = value.overflowing_add(carry as u64);
+            *limb = value;
+            carry = overflow_a || overflow_b;
+        }
+
+        (Self(limbs), carry)
+    }
+
+    /// Subtracts `other` modulo 2^256, returning whether the subtraction underflowed.
+    pub fn overflowing_sub(self, other: Self) -> (Self, bool) {
+        let mut limbs = [0; 4];
+        let mut borrow = false;
+
+        for (index, limb) in limbs.iter_mut().enumerate() {
+            let (value, overflow_a) = self.0[index].overflowing_sub(other.0[index]);
+            let (value, overflow_b) = value.overflowing_sub(borrow as u64);
+            *limb = value;
+            borrow = overflow_a || overflow_b;
+        }
+
+        (Self(limbs), borrow)
+    }
+
+    /// Divides by a small `divisor`, returning the quotient and the remainder.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `divisor` is zero.
+    pub fn div_rem_u64(self, divisor: u64) -> (Self, u64) {
+        assert_ne!(divisor, 0, "division by zero");
+
+        let mut limbs = [0; 4];
+        let mut remainder = 0u128;
+
+        for index in (0..4).rev() {
+            let value = (remainder << 64) | self.0[index] as u128;
+            limbs[index] = (value / divisor as u128) as u64;
+            remainder = value % divisor as u128;
+        }
+
+        (Self(limbs), remainder as u64)
+    }
+
+    fn from_str_radix(digits: &str, radix: u32) -> Result<Self, ParseError> {
+        if digits.is_empty() {
+            return Err(ParseError::InvalidLength { expected: 1, actual: 0 });
+        }
+
+        digits.chars().try_fold(Self::ZERO, |value, c| {
+            let digit = c.to_digit(radix).ok_or(ParseError::InvalidDigit(c))?;
+
+            value
+                .checked_mul(Self::from(radix))
+                .and_then(|value| value.checked_add(Self::from(digit)))
+                .ok_or(ParseError::Overflow)
+        })
+    }
+}
+
+macro_rules! impl_from_primitive {
+    ($($primitive:ty),*) => {
+        $(
+            impl From<$primitive> for U256 {
+                fn from(value: $primitive) -> Self {
+                    Self([value as u64, (value as u128 >> 64) as u64, 0, 0])
+                }
+            }
+
+            impl TryFrom<U256> for $primitive {
+                type Error = ParseError;
+
+                fn try_from(value: U256) -> Result<Self, Self::Error> {
+                    match value.bits() <= <$primitive>::BITS {
+                        true => Ok((value.0[0] as u128 | (value.0[1] as u128) << 64) as $primitive),
+                        false => Err(ParseError::Overflow),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_from_primitive!(u8, u16, u32, u64, u128, usize);
+
+impl From<bool> for U256 {
+    fn from(value: bool) -> Self {
+        Self::from(value as u8)
+    }
+}
+
+impl Ord for U256 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.iter().rev().cmp(other.0.iter().rev())
+    }
+}
+
+impl PartialOrd for U256 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl FromStr for U256 {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix("0x") {
+            Some(digits) => Self::from_str_radix(digits, 16),
+            None => Self::from_str_radix(s, 10),
+        }
+    }
+}
+
+impl fmt::Display for U256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut digits = vec![];
+        let mut value = *self;
+
+        loop {
+            let (quotient, remainder) = value.div_rem_u64(10);
+            digits.push(b'0' + remainder as u8);
+            value = quotient;
+
+            if value.is_zero() {
+                break;
+            }
+        }
+        digits.reverse();
+
+        f.pad_integral(true, "", std::str::from_utf8(&digits).unwrap())
+    }
+}
+
+impl fmt::Debug for U256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl fmt::LowerHex for U256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = hex::encode(self.to_be_bytes());
+        let digits = digits.trim_start_matches('0');
+
+        f.pad_integral(true, "0x", if digits.is_empty() { "0" } else { digits })
+    }
+}
+
+/// Serializes as a decimal string into human-readable formats and as 32 big-endian bytes into binary formats.
+impl Serialize for U256 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match serializer.is_human_readable() {
+            true => serializer.collect_str(self),
+            false => self.to_be_bytes().serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for U256 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct U256Visitor;
+
+        impl Visitor<'_> for U256Visitor {
+            type Value = U256;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an unsigned integer or a decimal or 0x-prefixed hex string")
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, value: u64) -> Result<Self::Value, E> {
+                Ok(U256::from(value))
+            }
+
+            fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                value.parse().map_err(E::custom)
+            }
+        }
+
+        match deserializer.is_human_readable() {
+            true => deserializer.deserialize_any(U256Visitor),
+            false => <[u8; U256::LENGTH]>::deserialize(deserializer).map(U256::from_be_bytes),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_and_parse_round_trip_max() {
+        let max = "115792089237316195423570985008687907853269984665640564039457584007913129639935";
+
+        assert_eq!(max, U256::MAX.to_string());
+        assert_eq!(Ok(U256::MAX), U256::from_str(max));
+        assert_eq!(Ok(U256::MAX), U256::from_str(&format!("0x{}", "f".repeat(64))));
+        assert_eq!(Err(ParseError::Overflow), U256::from_str(&format!("{}0", max)));
+    }
+
+    #[test]
+    fn test_arithmetic_is_checked() {
+        assert_eq!(None, U256::MAX.checked_add(U256::ONE));
+        assert_eq!(None, U256::ZERO.checked_sub(U256::ONE));
+        assert_eq!(
+            Some(U256::from(u64::MAX as u128 * u64::MAX as u128)),
+            U256::from(u64::MAX).checked_mul(U256::from(u64::MAX))
+        );
+        assert_eq!(None, U256::MAX.checked_mul(U256::from(2u8)));
+    }
+
+    #[test]
+    fn test_big_endian_bytes_round_trip() {
+        let value = U256::from_str("0x0102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f20").unwrap();
+        let bytes = value.to_be_bytes();
+
+        assert_eq!((1..=32).collect::<Vec<u8>>(), bytes);
+        assert_eq!(value, U256::from_be_bytes(bytes));
+        assert_eq!(
+            "0x102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f20",
+            format!("{:#x}", value)
+        );
+    }
+}
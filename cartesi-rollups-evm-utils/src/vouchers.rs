@@ -0,0 +1,187 @@
+//! Items in this module build the vouchers withdrawing the assets a DApp holds on the base layer.
+//!
+//! A voucher is a call the DApp contract executes once the voucher is proven, so withdrawing an asset amounts to
+//! encoding the call that moves it out: a transfer on the token contract, or `withdrawEther` on the DApp contract
+//! itself. [`VoucherBuilder`] encodes those calls for Ether and every token standard the portals deposit.
+use crate::abi::{encode, selector, Token};
+use crate::{Address, U256};
+
+/// Builds the vouchers that withdraw assets owned by a DApp.
+///
+/// Each method returns the destination and payload of the voucher, ready to be passed to `MachineIo::write_voucher`.
+/// Tokens are transferred by calling the token contract, while Ether is withdrawn by calling `withdrawEther` on the
+/// DApp contract, which is why the builder needs its address.
+///
+/// # Examples
+///
+/// ```
+/// # use cartesi_rollups_evm_utils::{Address, VoucherBuilder, U256};
+/// let vouchers = VoucherBuilder::new(Address::new([0xda; 20]));
+/// let token = Address::new([0xaa; 20]);
+///
+/// let (destination, payload) = vouchers.erc20_transfer(&token, &Address::new([0xbb; 20]), U256::from(100u8));
+///
+/// assert_eq!(token, destination);
+/// assert_eq!([0xa9, 0x05, 0x9c, 0xbb], payload[..4]);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct VoucherBuilder {
+    dapp: Address,
+}
+
+impl VoucherBuilder {
+    /// Creates a builder of the vouchers of the DApp contract at `dapp`.
+    pub fn new(dapp: Address) -> Self {
+        Self { dapp }
+    }
+
+    pub fn dapp(&self) -> &Address {
+        &self.dapp
+    }
+
+    /// Withdraws `value` Wei to `receiver` by calling `withdrawEther(address,uint256)` on the DApp contract.
+    pub fn ether_withdrawal(&self, receiver: &Address, value: U256) -> (Address, Vec<u8>) {
+        let payload = call("withdrawEther(address,uint256)", &[(*receiver).into(), value.into()]);
+
+        (self.dapp, payload)
+    }
+
+    /// Transfers `amount` of ERC-20 `token` to `receiver` by calling `transfer(address,uint256)`.
+    pub fn erc20_transfer(&self, token: &Address, receiver: &Address, amount: U256) -> (Address, Vec<u8>) {
+        let payload = call("transfer(address,uint256)", &[(*receiver).into(), amount.into()]);
+
+        (*token, payload)
+    }
+
+    /// Transfers the ERC-721 `token` of `token_id` to `receiver` by calling
+    /// `safeTransferFrom(address,address,uint256)`.
+    pub fn erc721_transfer(&self, token: &Address, receiver: &Address, token_id: U256) -> (Address, Vec<u8>) {
+        let payload = call(
+            "safeTransferFrom(address,address,uint256)",
+            &[self.dapp.into(), (*receiver).into(), token_id.into()],
+        );
+
+        (*token, payload)
+    }
+
+    /// Transfers `value` ERC-1155 `token` of `token_id` to `receiver` by calling
+    /// `safeTransferFrom(address,address,uint256,uint256,bytes)`.
+    pub fn erc1155_transfer(
+        &self,
+        token: &Address,
+        receiver: &Address,
+        token_id: U256,
+        value: U256,
+        data: &[u8],
+    ) -> (Address, Vec<u8>) {
+        let payload = call(
+            "safeTransferFrom(address,address,uint256,uint256,bytes)",
+            &[
+                self.dapp.into(),
+                (*receiver).into(),
+                token_id.into(),
+                value.into(),
+                Token::Bytes(data.to_vec()),
+            ],
+        );
+
+        (*token, payload)
+    }
+
+    /// Transfers ERC-1155 `token` to `receiver` by calling
+    /// `safeBatchTransferFrom(address,address,uint256[],uint256[],bytes)`.
+    ///
+    /// Each of `transfers` is a token id and the value of it to transfer.
+    pub fn erc1155_batch_transfer(
+        &self,
+        token: &Address,
+        receiver: &Address,
+        transfers: &[(U256, U256)],
+        data: &[u8],
+    ) -> (Address, Vec<u8>) {
+        let (token_ids, values) = transfers
+            .iter()
+            .map(|&(token_id, value)| (Token::Uint(token_id), Token::Uint(value)))
+            .unzip();
+        let payload = call(
+            "safeBatchTransferFrom(address,address,uint256[],uint256[],bytes)",
+            &[
+                self.dapp.into(),
+                (*receiver).into(),
+                Token::Array(token_ids),
+                Token::Array(values),
+                Token::Bytes(data.to_vec()),
+            ],
+        );
+
+        (*token, payload)
+    }
+}
+
+fn call(signature: &str, args: &[Token]) -> Vec<u8> {
+    let mut payload = selector(signature).to_vec();
+    payload.extend(encode(args));
+
+    payload
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::decode_call;
+
+    const DAPP: Address = Address::new([0xda; 20]);
+    const TOKEN: Address = Address::new([0xaa; 20]);
+    const RECEIVER: Address = Address::new([0xbb; 20]);
+
+    #[test]
+    fn test_withdrawals_call_expected_functions() {
+        let vouchers = VoucherBuilder::new(DAPP);
+        let one = U256::from(1u8);
+
+        let cases = [
+            (vouchers.ether_withdrawal(&RECEIVER, one), DAPP, "522f6815"),
+            (vouchers.erc20_transfer(&TOKEN, &RECEIVER, one), TOKEN, "a9059cbb"),
+            (vouchers.erc721_transfer(&TOKEN, &RECEIVER, one), TOKEN, "42842e0e"),
+            (
+                vouchers.erc1155_transfer(&TOKEN, &RECEIVER, one, one, &[]),
+                TOKEN,
+                "f242432a",
+            ),
+            (
+                vouchers.erc1155_batch_transfer(&TOKEN, &RECEIVER, &[(one, one)], &[]),
+                TOKEN,
+                "2eb2c2d6",
+            ),
+        ];
+
+        for ((destination, payload), expected_destination, expected_selector) in cases {
+            assert_eq!(expected_destination, destination);
+            assert_eq!(expected_selector, hex::encode(&payload[..4]));
+        }
+    }
+
+    #[test]
+    fn test_batch_transfer_encodes_ids_and_values() {
+        let vouchers = VoucherBuilder::new(DAPP);
+        let transfers = [(U256::from(7u8), U256::from(70u8)), (U256::from(8u8), U256::from(80u8))];
+
+        let (_, payload) = vouchers.erc1155_batch_transfer(&TOKEN, &RECEIVER, &transfers, b"data");
+        let args = decode_call(
+            "safeBatchTransferFrom(address,address,uint256[],uint256[],bytes)",
+            &payload,
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec![
+                Token::Address(DAPP),
+                Token::Address(RECEIVER),
+                Token::Array(vec![Token::Uint(U256::from(7u8)), Token::Uint(U256::from(8u8))]),
+                Token::Array(vec![Token::Uint(U256::from(70u8)), Token::Uint(U256::from(80u8))]),
+                Token::Bytes(b"data".to_vec()),
+            ],
+            args
+        );
+    }
+}
@@ -0,0 +1,5 @@
+mod machine;
+mod messages;
+
+pub use cartesi_rollups::*;
+pub use machine::*;
@@ -0,0 +1,150 @@
+use crate::messages::{Finish, Index, Output, Request, Voucher};
+use cartesi_rollups::{Address, FinishStatus, MachineIo, RollupsError, RollupsRequest, U256};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::cell::Cell;
+use std::env;
+
+/// Environment variable holding the URL of `rollup-http-server`, read by [`HttpMachine::from_env`].
+pub const ROLLUP_HTTP_SERVER_URL: &str = "ROLLUP_HTTP_SERVER_URL";
+
+/// Machine talking to `rollup-http-server`, for DApps running as an ordinary process.
+///
+/// This is how the host-mode node and HTTP dispatcher setups run a DApp. Every [`MachineIo`] call is a `POST` to the
+/// route of the same name, e.g. `/finish` or `/voucher`, and [`submit`] keeps asking for the next request while the
+/// server answers `202 Accepted`, meaning there is none yet.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use cartesi_rollups_http::{FinishStatus, HttpMachine, MachineIo};
+/// let machine = HttpMachine::new("http://127.0.0.1:5004");
+/// let request = machine.submit(FinishStatus::Accept).unwrap();
+/// ```
+///
+/// [`submit`]: MachineIo::submit
+#[derive(Debug)]
+pub struct HttpMachine {
+    agent: ureq::Agent,
+    url: String,
+    app_contract: Cell<Option<Address>>,
+}
+
+impl HttpMachine {
+    /// Creates a machine talking to the server at `url`, e.g. `http://127.0.0.1:5004`.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            agent: ureq::Agent::new(),
+            url: url.into().trim_end_matches('/').to_owned(),
+            app_contract: Cell::new(None),
+        }
+    }
+
+    /// Creates a machine talking to the server at the URL in [`ROLLUP_HTTP_SERVER_URL`].
+    pub fn from_env() -> Result<Self, env::VarError> {
+        env::var(ROLLUP_HTTP_SERVER_URL).map(Self::new)
+    }
+
+    /// Sends the requests through `agent`, e.g. one with timeouts.
+    pub fn with_agent(mut self, agent: ureq::Agent) -> Self {
+        self.agent = agent;
+        self
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Posts `body` to `route`, the body carrying a payload of `length` bytes.
+    fn post(&self, route: &str, body: &impl Serialize, length: usize) -> Result<ureq::Response, RollupsError> {
+        self.agent
+            .post(&format!("{}/{}", self.url, route))
+            .send_json(body)
+            .map_err(|error| http_error(error, length))
+    }
+
+    fn post_output(&self, route: &str, body: &impl Serialize, length: usize) -> Result<usize, RollupsError> {
+        read_json::<Index>(self.post(route, body, length)?).map(|index| index.index as usize)
+    }
+}
+
+impl MachineIo for HttpMachine {
+    fn write_notice(&self, payload: &[u8]) -> Result<usize, RollupsError> {
+        self.post_output("notice", &Output { payload }, payload.len())
+    }
+
+    fn write_voucher(&self, address: &Address, payload: &[u8]) -> Result<usize, RollupsError> {
+        self.write_voucher_with_value(address, &U256::ZERO, payload)
+    }
+
+    /// Writes a voucher with `value`, which only servers of Cartesi Rollups v2 accept.
+    ///
+    /// The value of a voucher without value is left out, so such a voucher works with any server.
+    fn write_voucher_with_value(&self, address: &Address, value: &U256, payload: &[u8]) -> Result<usize, RollupsError> {
+        let voucher = Voucher {
+            destination: address,
+            value: (!value.is_zero()).then_some(value),
+            payload,
+        };
+
+        self.post_output("voucher", &voucher, payload.len())
+    }
+
+    fn write_report(&self, payload: &[u8]) -> Result<(), RollupsError> {
+        self.post("report", &Output { payload }, payload.len()).map(|_| ())
+    }
+
+    fn submit(&self, status: FinishStatus) -> Result<RollupsRequest, RollupsError> {
+        let finish = Finish {
+            status: match status {
+                FinishStatus::Accept => "accept",
+                FinishStatus::Reject => "reject",
+            },
+        };
+
+        loop {
+            let response = self.post("finish", &finish, 0)?;
+
+            if response.status() == 202 {
+                log::debug!("no pending rollup request, trying again");
+                continue;
+            }
+
+            let request = RollupsRequest::from(read_json::<Request>(response)?);
+
+            if let RollupsRequest::AdvanceState { metadata, .. } = &request {
+                self.app_contract
+                    .set(metadata.app_contract().or(self.app_contract.get()));
+            }
+
+            return Ok(request);
+        }
+    }
+
+    fn throw_exception(&self, payload: &[u8]) -> Result<(), RollupsError> {
+        self.post("exception", &Output { payload }, payload.len()).map(|_| ())
+    }
+
+    /// Returns the address of the application contract, known from the first advance state request of a Cartesi
+    /// Rollups v2 server on.
+    fn dapp_address(&self) -> Option<Address> {
+        self.app_contract.get()
+    }
+}
+
+/// Maps `error` of a request carrying a payload of `length` bytes to [`RollupsError`].
+fn http_error(error: ureq::Error, length: usize) -> RollupsError {
+    match error {
+        ureq::Error::Status(413, _) => RollupsError::PayloadTooLarge {
+            length,
+            source: Box::new(error),
+        },
+        error => RollupsError::Other(Box::new(error)),
+    }
+}
+
+fn read_json<T: DeserializeOwned>(response: ureq::Response) -> Result<T, RollupsError> {
+    response
+        .into_json()
+        .map_err(|error| RollupsError::Other(Box::new(error)))
+}
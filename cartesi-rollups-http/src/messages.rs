@@ -0,0 +1,152 @@
+//! Items in this module define the JSON bodies exchanged with `rollup-http-server`.
+//!
+//! Payloads travel as `0x`-prefixed hex strings. Advance state metadata is read in both the format of the 0.x server
+//! and the one of Cartesi Rollups v2, which adds `chain_id`, `app_contract` and `prev_randao`.
+use cartesi_rollups::{Address, MetadataVersion, RollupsMetadata, RollupsRequest, U256};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[derive(Serialize, Debug)]
+pub(crate) struct Finish {
+    pub status: &'static str,
+}
+
+#[derive(Serialize, Debug)]
+pub(crate) struct Output<'a> {
+    #[serde(serialize_with = "serialize_hex")]
+    pub payload: &'a [u8],
+}
+
+#[derive(Serialize, Debug)]
+pub(crate) struct Voucher<'a> {
+    pub destination: &'a Address,
+    #[serde(serialize_with = "serialize_value", skip_serializing_if = "Option::is_none")]
+    pub value: Option<&'a U256>,
+    #[serde(serialize_with = "serialize_hex")]
+    pub payload: &'a [u8],
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct Index {
+    pub index: u64,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "request_type", content = "data", rename_all = "snake_case")]
+pub(crate) enum Request {
+    AdvanceState {
+        metadata: Metadata,
+        #[serde(deserialize_with = "deserialize_hex")]
+        payload: Vec<u8>,
+    },
+    InspectState {
+        #[serde(deserialize_with = "deserialize_hex")]
+        payload: Vec<u8>,
+    },
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct Metadata {
+    msg_sender: Address,
+    #[serde(default)]
+    epoch_index: u64,
+    input_index: u64,
+    block_number: u64,
+    #[serde(alias = "block_timestamp")]
+    timestamp: u64,
+    chain_id: Option<u64>,
+    app_contract: Option<Address>,
+    prev_randao: Option<U256>,
+}
+
+impl From<Request> for RollupsRequest {
+    fn from(request: Request) -> Self {
+        match request {
+            Request::AdvanceState { metadata, payload } => RollupsRequest::AdvanceState {
+                metadata: metadata.into(),
+                payload,
+            },
+            Request::InspectState { payload } => RollupsRequest::InspectState { payload },
+        }
+    }
+}
+
+impl From<Metadata> for RollupsMetadata {
+    fn from(metadata: Metadata) -> Self {
+        let version = match (metadata.chain_id, metadata.app_contract, metadata.prev_randao) {
+            (Some(chain_id), Some(app_contract), Some(prev_randao)) => MetadataVersion::V2 {
+                chain_id,
+                app_contract,
+                prev_randao,
+            },
+            _ => MetadataVersion::V1,
+        };
+
+        RollupsMetadata {
+            msg_sender: metadata.msg_sender,
+            epoch_index: metadata.epoch_index,
+            input_index: metadata.input_index,
+            block_number: metadata.block_number,
+            timestamp: metadata.timestamp,
+            version,
+        }
+    }
+}
+
+fn serialize_hex<S: Serializer>(bytes: &&[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(&format_args!("0x{}", hex::encode(bytes)))
+}
+
+/// Serializes the value of a voucher as the 32-byte hex string the server expects.
+fn serialize_value<S: Serializer>(value: &Option<&U256>, serializer: S) -> Result<S::Ok, S::Error> {
+    let value = value.copied().unwrap_or(U256::ZERO);
+
+    serializer.collect_str(&format_args!("0x{}", hex::encode(value.to_be_bytes())))
+}
+
+fn deserialize_hex<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    let string = String::deserialize(deserializer)?;
+
+    hex::decode(string.strip_prefix("0x").unwrap_or(&string)).map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metadata_of_v1_server_is_read() {
+        let request: Request = serde_json::from_str(
+            r#"{
+                "request_type": "advance_state",
+                "data": {
+                    "metadata": {
+                        "msg_sender": "0xa1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1",
+                        "epoch_index": 1,
+                        "input_index": 2,
+                        "block_number": 3,
+                        "timestamp": 4
+                    },
+                    "payload": "0x"
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let RollupsRequest::AdvanceState { metadata, payload } = request.into() else {
+            panic!("expected advance state request");
+        };
+
+        assert!(payload.is_empty());
+        assert_eq!(Address::new([0xa1; 20]), metadata.msg_sender);
+        assert_eq!(
+            (1, 2, 3, 4),
+            (
+                metadata.epoch_index,
+                metadata.input_index,
+                metadata.block_number,
+                metadata.timestamp
+            )
+        );
+        assert_eq!(MetadataVersion::V1, metadata.version);
+    }
+}
@@ -0,0 +1,128 @@
+use cartesi_rollups_http::{Address, FinishStatus, HttpMachine, MachineIo, RollupsError, RollupsRequest, U256};
+use serde_json::{json, Value};
+use std::thread;
+use tiny_http::{Response, Server};
+
+/// Serves `responses` in order, returning the route and JSON body of every request.
+fn serve(responses: Vec<(u16, Value)>) -> (String, thread::JoinHandle<Vec<(String, Value)>>) {
+    let server = Server::http("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", server.server_addr().to_ip().unwrap());
+
+    let handle = thread::spawn(move || {
+        responses
+            .into_iter()
+            .map(|(status, body)| {
+                let mut request = server.recv().unwrap();
+                let mut content = String::new();
+                request.as_reader().read_to_string(&mut content).unwrap();
+                let received = (request.url().to_owned(), serde_json::from_str(&content).unwrap());
+
+                let body = match body {
+                    Value::Null => String::new(),
+                    body => body.to_string(),
+                };
+                request
+                    .respond(Response::from_string(body).with_status_code(status))
+                    .unwrap();
+
+                received
+            })
+            .collect()
+    });
+
+    (url, handle)
+}
+
+#[test]
+fn test_machine_talks_to_rollup_http_server() {
+    let app_contract = Address::new([0xda; 20]);
+    let destination = Address::new([0x70; 20]);
+    let (url, server) = serve(vec![
+        (202, Value::Null),
+        (
+            200,
+            json!({
+                "request_type": "advance_state",
+                "data": {
+                    "metadata": {
+                        "chain_id": 31337,
+                        "app_contract": app_contract.to_string(),
+                        "msg_sender": "0xa1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1",
+                        "block_number": 42,
+                        "block_timestamp": 1700000000,
+                        "prev_randao": "0x07",
+                        "input_index": 3
+                    },
+                    "payload": "0x010203"
+                }
+            }),
+        ),
+        (200, json!({ "index": 0 })),
+        (200, json!({ "index": 1 })),
+        (200, Value::Null),
+        (413, Value::Null),
+        (
+            200,
+            json!({ "request_type": "inspect_state", "data": { "payload": "0x717565" } }),
+        ),
+        (200, Value::Null),
+    ]);
+    let machine = HttpMachine::new(url);
+
+    let RollupsRequest::AdvanceState { metadata, payload } = machine.submit(FinishStatus::Accept).unwrap() else {
+        panic!("expected advance state request");
+    };
+
+    assert_eq!(vec![1, 2, 3], payload);
+    assert_eq!(Address::new([0xa1; 20]), metadata.msg_sender);
+    assert_eq!(
+        (3, 42, 1_700_000_000),
+        (metadata.input_index, metadata.block_number, metadata.timestamp)
+    );
+    assert_eq!(Some(31337), metadata.chain_id());
+    assert_eq!(Some(U256::from(7u8)), metadata.prev_randao());
+    assert_eq!(Some(app_contract), machine.dapp_address());
+
+    assert_eq!(
+        0,
+        machine
+            .write_voucher_with_value(&destination, &U256::from(5u8), b"call")
+            .unwrap()
+    );
+    assert_eq!(1, machine.write_notice(b"notice").unwrap());
+    machine.write_report(b"report").unwrap();
+    assert!(matches!(
+        machine.write_voucher(&destination, &[0; 4]),
+        Err(RollupsError::PayloadTooLarge { length: 4, .. })
+    ));
+
+    let RollupsRequest::InspectState { payload } = machine.submit(FinishStatus::Reject).unwrap() else {
+        panic!("expected inspect state request");
+    };
+
+    assert_eq!(b"que".to_vec(), payload);
+    machine.throw_exception(b"oops").unwrap();
+
+    let requests = server.join().unwrap();
+    let value = format!("0x{}", hex::encode(U256::from(5u8).to_be_bytes()));
+
+    assert_eq!(
+        vec![
+            ("/finish".to_owned(), json!({ "status": "accept" })),
+            ("/finish".to_owned(), json!({ "status": "accept" })),
+            (
+                "/voucher".to_owned(),
+                json!({ "destination": destination.to_string(), "value": value, "payload": "0x63616c6c" })
+            ),
+            ("/notice".to_owned(), json!({ "payload": "0x6e6f74696365" })),
+            ("/report".to_owned(), json!({ "payload": "0x7265706f7274" })),
+            (
+                "/voucher".to_owned(),
+                json!({ "destination": destination.to_string(), "payload": "0x00000000" })
+            ),
+            ("/finish".to_owned(), json!({ "status": "reject" })),
+            ("/exception".to_owned(), json!({ "payload": "0x6f6f7073" })),
+        ],
+        requests
+    );
+}
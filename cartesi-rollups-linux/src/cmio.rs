@@ -0,0 +1,453 @@
+//! Items in this module implement the cmio machine interface of Cartesi Rollups v2, the one libcmt drives.
+//!
+//! Advance state requests arrive ABI-encoded as `EvmAdvance` calls, carrying [`MetadataVersion::V2`] metadata, and
+//! outputs leave ABI-encoded as `Voucher` and `Notice` calls. The hashes of the outputs are kept in an
+//! [`OutputsTree`], whose root is handed to the host when accepting a request.
+use crate::conversions::device_error;
+use cartesi_rollups::{
+    Address, FinishStatus, MachineIo, MetadataVersion, RollupsError, RollupsMetadata, RollupsRequest, U256,
+};
+use cartesi_rollups_bindings as bindings;
+use cartesi_rollups_bindings::CmioMapping;
+use cartesi_rollups_evm_utils::abi::{decode_call, encode_call, Token};
+use cartesi_rollups_evm_utils::outputs::OutputsTree;
+use std::cell::RefCell;
+use std::fs::File;
+use std::io;
+use std::os::fd::{AsRawFd, OwnedFd};
+use std::path::Path;
+
+/// Cmio device driver path
+pub const CMIO_DEVICE_NAME: &str = "/dev/cmio";
+
+/// Signature of the call an advance state request is encoded as.
+pub const EVM_ADVANCE_SIGNATURE: &str = "EvmAdvance(uint256,address,address,uint256,uint256,uint256,uint256,bytes)";
+/// Signature of the call a voucher is encoded as.
+pub const VOUCHER_SIGNATURE: &str = "Voucher(address,uint256,bytes)";
+/// Signature of the call a notice is encoded as.
+pub const NOTICE_SIGNATURE: &str = "Notice(bytes)";
+
+/// The implementor of this trait yields to the host through the buffers of the cmio device.
+///
+/// Errors are the [`Errno`] the driver would return, so [`CmioMachine`] maps them the same way whatever the device.
+///
+/// [`Errno`]: nix::errno::Errno
+pub trait CmioDevice {
+    /// Yields to the host with `command`, `reason` and `data`, returning the reason and data the host replies with.
+    ///
+    /// Blocks until the host resumes the machine.
+    fn yield_to_host(&self, command: u8, reason: u16, data: u32) -> nix::Result<(u16, u32)>;
+
+    /// Copies `data` to the start of the transmit buffer.
+    fn write_tx(&self, data: &[u8]) -> nix::Result<()>;
+
+    /// Replaces the contents of `buffer` with the first `length` bytes of the receive buffer.
+    fn read_rx(&self, length: usize, buffer: &mut Vec<u8>) -> nix::Result<()>;
+}
+
+/// Cmio device of a Cartesi Rollups v2 machine, with its buffers mapped into the process.
+///
+/// The device owns the file descriptor and closes it on drop.
+#[derive(Debug)]
+pub struct CmioIoctlDevice {
+    fd: OwnedFd,
+    mapping: RefCell<CmioMapping>,
+}
+
+impl CmioIoctlDevice {
+    /// Creates a device owning `fd`, mapping its buffers.
+    pub fn new(fd: OwnedFd) -> nix::Result<Self> {
+        let raw_fd = fd.as_raw_fd();
+        let mapping = bindings::cmio_get_setup(raw_fd).and_then(|setup| CmioMapping::new(raw_fd, &setup))?;
+
+        Ok(Self {
+            fd,
+            mapping: RefCell::new(mapping),
+        })
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, io::Error> {
+        let cmio_file = File::options().read(true).write(true).open(path)?;
+
+        Ok(Self::new(cmio_file.into())?)
+    }
+}
+
+impl CmioDevice for CmioIoctlDevice {
+    fn yield_to_host(&self, command: u8, reason: u16, data: u32) -> nix::Result<(u16, u32)> {
+        let request = bindings::cmio_yield_pack(bindings::HTIF_DEVICE_YIELD, command, reason, data);
+        let (_, _, reason, data) = bindings::cmio_yield_unpack(bindings::cmio_yield(self.fd.as_raw_fd(), request)?);
+
+        Ok((reason, data))
+    }
+
+    fn write_tx(&self, data: &[u8]) -> nix::Result<()> {
+        self.mapping.borrow_mut().write_tx(data)
+    }
+
+    fn read_rx(&self, length: usize, buffer: &mut Vec<u8>) -> nix::Result<()> {
+        let mapping = self.mapping.borrow();
+        let rx = mapping.rx(length)?;
+
+        buffer.clear();
+        buffer.extend_from_slice(rx);
+
+        Ok(())
+    }
+}
+
+/// Machine talking to the cmio device of a Cartesi Rollups v2 machine.
+///
+/// Outputs are indexed among every output of the application, not among the outputs of the current input, and a
+/// voucher may send Ether along with its call through [`MachineIo::write_voucher_with_value`]. The address of the
+/// application contract is known from the first advance state request on.
+pub struct CmioMachine<D = CmioIoctlDevice> {
+    device: D,
+    state: RefCell<State>,
+}
+
+struct State {
+    outputs: OutputsTree,
+    /// Outputs as of the last accepted input, the ones the outputs of a rejected request roll back to.
+    accepted: OutputsTree,
+    advancing: bool,
+    app_contract: Option<Address>,
+    /// Buffer the payload of every request is read into.
+    buffer: Vec<u8>,
+}
+
+impl CmioMachine {
+    pub fn open_default_device() -> Result<Self, io::Error> {
+        Self::open(CMIO_DEVICE_NAME)
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, io::Error> {
+        Ok(Self::with_device(CmioIoctlDevice::open(path)?))
+    }
+}
+
+impl<D: CmioDevice> CmioMachine<D> {
+    pub fn with_device(device: D) -> Self {
+        Self {
+            device,
+            state: RefCell::new(State {
+                outputs: OutputsTree::new(),
+                accepted: OutputsTree::new(),
+                advancing: false,
+                app_contract: None,
+                buffer: Vec::new(),
+            }),
+        }
+    }
+
+    pub fn device(&self) -> &D {
+        &self.device
+    }
+
+    /// Returns the root of the tree of the outputs written so far.
+    pub fn outputs_root(&self) -> [u8; 32] {
+        self.state.borrow().outputs.root()
+    }
+
+    fn write_output(&self, output: &[u8]) -> Result<usize, RollupsError> {
+        self.transmit(
+            bindings::HTIF_YIELD_CMD_AUTOMATIC,
+            bindings::HTIF_YIELD_AUTOMATIC_REASON_TX_OUTPUT,
+            output,
+        )?;
+
+        Ok(self.state.borrow_mut().outputs.push(output) as usize)
+    }
+
+    /// Writes `payload` to the transmit buffer and yields with `command` and `reason`.
+    fn transmit(&self, command: u8, reason: u16, payload: &[u8]) -> Result<(), RollupsError> {
+        let length = u32::try_from(payload.len()).map_err(|error| RollupsError::PayloadTooLarge {
+            length: payload.len(),
+            source: Box::new(error),
+        })?;
+
+        self.device
+            .write_tx(payload)
+            .and_then(|_| self.device.yield_to_host(command, reason, length))
+            .map(|_| ())
+            .map_err(|e| device_error(e, payload.len()))
+    }
+}
+
+impl<D: CmioDevice> MachineIo for CmioMachine<D> {
+    fn write_notice(&self, payload: &[u8]) -> Result<usize, RollupsError> {
+        let notice = encode_call(NOTICE_SIGNATURE, &[Token::Bytes(payload.to_vec())]).unwrap();
+
+        self.write_output(&notice)
+    }
+
+    fn write_voucher(&self, address: &Address, payload: &[u8]) -> Result<usize, RollupsError> {
+        self.write_voucher_with_value(address, &U256::ZERO, payload)
+    }
+
+    fn write_voucher_with_value(&self, address: &Address, value: &U256, payload: &[u8]) -> Result<usize, RollupsError> {
+        let voucher = encode_call(
+            VOUCHER_SIGNATURE,
+            &[
+                Token::Address(*address),
+                Token::Uint(*value),
+                Token::Bytes(payload.to_vec()),
+            ],
+        )
+        .unwrap();
+
+        self.write_output(&voucher)
+    }
+
+    fn write_report(&self, payload: &[u8]) -> Result<(), RollupsError> {
+        self.transmit(
+            bindings::HTIF_YIELD_CMD_AUTOMATIC,
+            bindings::HTIF_YIELD_AUTOMATIC_REASON_TX_REPORT,
+            payload,
+        )
+    }
+
+    fn submit(&self, status: FinishStatus) -> Result<RollupsRequest, RollupsError> {
+        let mut state = self.state.borrow_mut();
+        let state = &mut *state;
+
+        match status == FinishStatus::Accept && state.advancing {
+            true => state.accepted = state.outputs.clone(),
+            false => state.outputs = state.accepted.clone(),
+        }
+
+        let finish = match status {
+            FinishStatus::Accept => self
+                .device
+                .write_tx(&state.outputs.root())
+                .map(|_| (bindings::HTIF_YIELD_MANUAL_REASON_RX_ACCEPTED, 32)),
+            FinishStatus::Reject => Ok((bindings::HTIF_YIELD_MANUAL_REASON_RX_REJECTED, 0)),
+        };
+        let (reason, length) = finish
+            .and_then(|(reason, data)| self.device.yield_to_host(bindings::HTIF_YIELD_CMD_MANUAL, reason, data))
+            .inspect_err(|e| {
+                log::error!("error yielding finish request, details: {}", e);
+            })
+            .map_err(|e| device_error(e, 0))?;
+
+        self.device
+            .read_rx(length as usize, &mut state.buffer)
+            .map_err(|e| device_error(e, length as usize))?;
+
+        match reason {
+            bindings::HTIF_YIELD_REASON_ADVANCE => {
+                let (metadata, payload) = decode_advance(&state.buffer)?;
+
+                log::debug!(
+                    "advance: {{ msg_sender: {} app_contract: {} block_number: {} timestamp: {} input_index: {} }}",
+                    metadata.msg_sender,
+                    metadata.app_contract().unwrap(),
+                    metadata.block_number,
+                    metadata.timestamp,
+                    metadata.input_index
+                );
+
+                state.advancing = true;
+                state.app_contract = metadata.app_contract();
+
+                Ok(RollupsRequest::AdvanceState { metadata, payload })
+            }
+            bindings::HTIF_YIELD_REASON_INSPECT => {
+                log::debug!("inspect: {{ length: {} }}", state.buffer.len());
+
+                state.advancing = false;
+
+                Ok(RollupsRequest::InspectState {
+                    payload: state.buffer.clone(),
+                })
+            }
+            reason => Err(RollupsError::UnsupportedRequestType(u32::from(reason))),
+        }
+    }
+
+    fn throw_exception(&self, payload: &[u8]) -> Result<(), RollupsError> {
+        self.transmit(
+            bindings::HTIF_YIELD_CMD_MANUAL,
+            bindings::HTIF_YIELD_MANUAL_REASON_TX_EXCEPTION,
+            payload,
+        )
+    }
+
+    fn dapp_address(&self) -> Option<Address> {
+        self.state.borrow().app_contract
+    }
+}
+
+/// Decodes the `EvmAdvance` call of an advance state request into its metadata and payload.
+fn decode_advance(data: &[u8]) -> Result<(RollupsMetadata, Vec<u8>), RollupsError> {
+    let tokens = decode_call(EVM_ADVANCE_SIGNATURE, data).map_err(|error| RollupsError::Other(Box::new(error)))?;
+    // The tokens match the types of the signature once decoded.
+    let [chain_id, app_contract, msg_sender, block_number, timestamp, prev_randao, input_index, payload] =
+        <[Token; 8]>::try_from(tokens).unwrap();
+
+    let metadata = RollupsMetadata {
+        msg_sender: msg_sender.into_address().unwrap(),
+        epoch_index: 0,
+        input_index: to_u64(input_index)?,
+        block_number: to_u64(block_number)?,
+        timestamp: to_u64(timestamp)?,
+        version: MetadataVersion::V2 {
+            chain_id: to_u64(chain_id)?,
+            app_contract: app_contract.into_address().unwrap(),
+            prev_randao: prev_randao.into_uint().unwrap(),
+        },
+    };
+
+    Ok((metadata, payload.into_bytes().unwrap()))
+}
+
+fn to_u64(token: Token) -> Result<u64, RollupsError> {
+    u64::try_from(token.into_uint().unwrap()).map_err(|error| RollupsError::Other(Box::new(error)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    /// Host replying to each manual yield with the next of its requests.
+    #[derive(Default)]
+    struct FakeHost {
+        requests: RefCell<VecDeque<(u16, Vec<u8>)>>,
+        rx: RefCell<Vec<u8>>,
+        tx: RefCell<Vec<u8>>,
+        /// Yields along with the contents of the transmit buffer they handed over.
+        yields: RefCell<Vec<(u8, u16, Vec<u8>)>>,
+    }
+
+    impl CmioDevice for FakeHost {
+        fn yield_to_host(&self, command: u8, reason: u16, data: u32) -> nix::Result<(u16, u32)> {
+            let transmitted = self.tx.borrow()[..data as usize].to_vec();
+            self.yields.borrow_mut().push((command, reason, transmitted));
+
+            if command == bindings::HTIF_YIELD_CMD_AUTOMATIC {
+                return Ok((reason, 0));
+            }
+
+            let (reason, request) = self
+                .requests
+                .borrow_mut()
+                .pop_front()
+                .ok_or(nix::errno::Errno::ENODATA)?;
+            let length = request.len() as u32;
+            *self.rx.borrow_mut() = request;
+
+            Ok((reason, length))
+        }
+
+        fn write_tx(&self, data: &[u8]) -> nix::Result<()> {
+            *self.tx.borrow_mut() = data.to_vec();
+            Ok(())
+        }
+
+        fn read_rx(&self, length: usize, buffer: &mut Vec<u8>) -> nix::Result<()> {
+            *buffer = self.rx.borrow()[..length].to_vec();
+            Ok(())
+        }
+    }
+
+    fn advance(input_index: u8, payload: &[u8]) -> (u16, Vec<u8>) {
+        let call = encode_call(
+            EVM_ADVANCE_SIGNATURE,
+            &[
+                Token::Uint(U256::from(31337u32)),
+                Token::Address(Address::new([0xda; 20])),
+                Token::Address(Address::new([0xa1; 20])),
+                Token::Uint(U256::from(42u8)),
+                Token::Uint(U256::from(1_700_000_000u32)),
+                Token::Uint(U256::from(7u8)),
+                Token::Uint(U256::from(input_index)),
+                Token::Bytes(payload.to_vec()),
+            ],
+        )
+        .unwrap();
+
+        (bindings::HTIF_YIELD_REASON_ADVANCE, call)
+    }
+
+    #[test]
+    fn test_advance_requests_carry_v2_metadata() {
+        let host = FakeHost::default();
+        host.requests.borrow_mut().extend([
+            advance(3, b"payload"),
+            (bindings::HTIF_YIELD_REASON_INSPECT, b"query".to_vec()),
+        ]);
+        let machine = CmioMachine::with_device(host);
+
+        assert_eq!(None, machine.dapp_address());
+        assert_eq!([0x41, 0x5b, 0xf3, 0x63], machine.device().requests.borrow()[0].1[..4]);
+
+        match machine.submit(FinishStatus::Accept).unwrap() {
+            RollupsRequest::AdvanceState { metadata, payload } => {
+                assert_eq!(Address::new([0xa1; 20]), metadata.msg_sender);
+                assert_eq!(3, metadata.input_index);
+                assert_eq!(42, metadata.block_number);
+                assert_eq!(1_700_000_000, metadata.timestamp);
+                assert_eq!(Some(31337), metadata.chain_id());
+                assert_eq!(Some(Address::new([0xda; 20])), metadata.app_contract());
+                assert_eq!(Some(U256::from(7u8)), metadata.prev_randao());
+                assert_eq!(b"payload".to_vec(), payload);
+            }
+            request => panic!("unexpected request {:?}", request),
+        }
+
+        assert_eq!(Some(Address::new([0xda; 20])), machine.dapp_address());
+
+        match machine.submit(FinishStatus::Accept).unwrap() {
+            RollupsRequest::InspectState { payload } => assert_eq!(b"query".to_vec(), payload),
+            request => panic!("unexpected request {:?}", request),
+        }
+    }
+
+    #[test]
+    fn test_outputs_are_hashed_into_accepted_root() {
+        let destination = Address::new([0x70; 20]);
+        let host = FakeHost::default();
+        host.requests
+            .borrow_mut()
+            .extend([advance(0, b""), advance(1, b""), advance(2, b"")]);
+        let machine = CmioMachine::with_device(host);
+
+        machine.submit(FinishStatus::Accept).unwrap();
+        assert_eq!(
+            0,
+            machine
+                .write_voucher_with_value(&destination, &U256::from(5u8), b"call")
+                .unwrap()
+        );
+        assert_eq!(1, machine.write_notice(b"notice").unwrap());
+        let root = machine.outputs_root();
+
+        machine.submit(FinishStatus::Accept).unwrap();
+        assert_eq!(2, machine.write_notice(b"discarded").unwrap());
+
+        machine.submit(FinishStatus::Reject).unwrap();
+        machine.write_report(b"report").unwrap();
+
+        let yields = machine.device().yields.borrow();
+        let (voucher, notice) = (&yields[1].2, &yields[2].2);
+        let mut expected = OutputsTree::new();
+        expected.push(voucher);
+        expected.push(notice);
+
+        assert_eq!([0x23, 0x7a, 0x81, 0x6f], voucher[..4]);
+        assert_eq!([0xc2, 0x58, 0xd6, 0xe5], notice[..4]);
+        assert_eq!(expected.root(), root);
+        assert_eq!(
+            (
+                bindings::HTIF_YIELD_CMD_MANUAL,
+                bindings::HTIF_YIELD_MANUAL_REASON_RX_ACCEPTED
+            ),
+            (yields[3].0, yields[3].1)
+        );
+        assert_eq!(root.to_vec(), yields[3].2);
+        assert_eq!(bindings::HTIF_YIELD_MANUAL_REASON_RX_REJECTED, yields[5].1);
+        assert_eq!(root, machine.outputs_root());
+        assert_eq!(b"report".to_vec(), yields[6].2);
+    }
+}
@@ -1,54 +1,124 @@
 use crate::rollups::{Exception, Notice, Report, RollupRequest, Voucher};
-use cartesi_rollups::{RollupsMetadata, RollupsRequest};
-use std::string::FromUtf8Error;
+use crate::BorrowedRequest;
+use cartesi_rollups::{Address, MetadataVersion, RollupsError, RollupsMetadata, RollupsRequest};
+use nix::errno::Errno;
+use std::io;
 
-impl TryFrom<&[u8]> for Notice {
-    type Error = FromUtf8Error;
-    fn try_from(payload: &[u8]) -> Result<Self, Self::Error> {
-        String::from_utf8(payload.to_vec()).map(|payload| Self { payload })
+/// Maps `errno` reported by the rollup device while transferring a payload of `length` bytes to [`RollupsError`].
+pub(crate) fn device_error(errno: Errno, length: usize) -> RollupsError {
+    match errno {
+        Errno::ENOBUFS | Errno::E2BIG | Errno::EMSGSIZE => RollupsError::PayloadTooLarge {
+            length,
+            source: Box::new(io::Error::from(errno)),
+        },
+        errno => RollupsError::Device(io::Error::from(errno)),
     }
 }
 
-impl TryFrom<(&[u8; 20], &[u8])> for Voucher {
-    type Error = FromUtf8Error;
-    fn try_from(voucher: (&[u8; 20], &[u8])) -> Result<Self, Self::Error> {
-        let destination = String::from_utf8(voucher.0.to_vec())?;
-        let payload = String::from_utf8(voucher.1.to_vec())?;
+impl<'a> From<&'a [u8]> for Notice<'a> {
+    fn from(payload: &'a [u8]) -> Self {
+        Self { payload }
+    }
+}
 
-        Ok(Self { destination, payload })
+impl<'a> From<(&'a [u8; 20], &'a [u8])> for Voucher<'a> {
+    fn from((destination, payload): (&'a [u8; 20], &'a [u8])) -> Self {
+        Self { destination, payload }
     }
 }
 
-impl TryFrom<&[u8]> for Report {
-    type Error = FromUtf8Error;
-    fn try_from(payload: &[u8]) -> Result<Self, Self::Error> {
-        String::from_utf8(payload.to_vec()).map(|payload| Self { payload })
+impl<'a> From<&'a [u8]> for Report<'a> {
+    fn from(payload: &'a [u8]) -> Self {
+        Self { payload }
     }
 }
 
-impl TryFrom<&[u8]> for Exception {
-    type Error = FromUtf8Error;
-    fn try_from(payload: &[u8]) -> Result<Self, Self::Error> {
-        String::from_utf8(payload.to_vec()).map(|payload| Self { payload })
+impl<'a> From<&'a [u8]> for Exception<'a> {
+    fn from(payload: &'a [u8]) -> Self {
+        Self { payload }
     }
 }
 
-impl From<RollupRequest> for RollupsRequest {
-    fn from(request: RollupRequest) -> Self {
+impl<'a> From<RollupRequest<'a>> for BorrowedRequest<'a> {
+    fn from(request: RollupRequest<'a>) -> Self {
         match request {
-            RollupRequest::Inspect(request) => RollupsRequest::InspectState {
-                payload: request.payload.into_bytes(),
+            RollupRequest::Inspect(request) => BorrowedRequest::InspectState {
+                payload: request.payload,
             },
-            RollupRequest::Advance(request) => RollupsRequest::AdvanceState {
+            RollupRequest::Advance(request) => BorrowedRequest::AdvanceState {
                 metadata: RollupsMetadata {
-                    msg_sender: request.metadata.msg_sender,
+                    msg_sender: Address::new(request.metadata.msg_sender),
                     epoch_index: request.metadata.epoch_index,
                     input_index: request.metadata.input_index,
                     block_number: request.metadata.block_number,
                     timestamp: request.metadata.timestamp,
+                    version: MetadataVersion::V1,
                 },
-                payload: request.payload.into_bytes(),
+                payload: request.payload,
+            },
+        }
+    }
+}
+
+/// Copies the payload out of the buffer of the machine, as [`RollupsRequest`] owns it.
+impl From<BorrowedRequest<'_>> for RollupsRequest {
+    fn from(request: BorrowedRequest<'_>) -> Self {
+        match request {
+            BorrowedRequest::AdvanceState { metadata, payload } => RollupsRequest::AdvanceState {
+                metadata,
+                payload: payload.to_vec(),
+            },
+            BorrowedRequest::InspectState { payload } => RollupsRequest::InspectState {
+                payload: payload.to_vec(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buffer_errnos_are_payload_too_large() {
+        for errno in [Errno::ENOBUFS, Errno::E2BIG, Errno::EMSGSIZE] {
+            let error = device_error(errno, 7);
+
+            assert!(matches!(error, RollupsError::PayloadTooLarge { length: 7, .. }));
+            assert_eq!(Some(errno as i32), error.errno());
+        }
+    }
+
+    #[test]
+    fn test_other_errnos_are_device_errors() {
+        for errno in [
+            Errno::EOPNOTSUPP,
+            Errno::ENODATA,
+            Errno::EINVAL,
+            Errno::EIO,
+            Errno::EBADF,
+        ] {
+            let error = device_error(errno, 7);
+
+            assert!(matches!(error, RollupsError::Device(_)));
+            assert_eq!(Some(errno as i32), error.errno());
+        }
+    }
+
+    #[test]
+    fn test_errors_not_from_device_have_no_errno() {
+        let errors = [
+            RollupsError::QueueExhausted("done".into()),
+            RollupsError::PayloadTooLarge {
+                length: 7,
+                source: "limit".into(),
             },
+            RollupsError::UnsupportedRequestType(9),
+            RollupsError::Other(Box::new(io::Error::from_raw_os_error(Errno::EIO as i32))),
+        ];
+
+        for error in errors {
+            assert_eq!(None, error.errno());
         }
     }
 }
@@ -0,0 +1,95 @@
+//! Items in this module abstract the IOCTL surface of the rollup device.
+use cartesi_rollups_bindings as bindings;
+use cartesi_rollups_bindings::{rollup_address, rollup_finish, rollup_input_metadata, RollupBytes};
+use std::fs::File;
+use std::io;
+use std::os::fd::{AsRawFd, OwnedFd};
+use std::path::Path;
+
+/// The implementor of this trait performs the IOCTL requests of the rollup device.
+///
+/// Errors are the [`Errno`] the driver would return, so [`LinuxMachine`] maps them the same way whatever the device.
+///
+/// [`Errno`]: nix::errno::Errno
+/// [`LinuxMachine`]: crate::LinuxMachine
+pub trait RollupDevice {
+    /// Finishes the current request, accepting or rejecting it, and announces the next one.
+    ///
+    /// Blocks until there is a next request.
+    fn finish(&self, accept: bool) -> nix::Result<rollup_finish>;
+
+    /// Reads the advance state request announced by `finish`, its payload into `payload`.
+    fn read_advance_state(
+        &self,
+        finish: &rollup_finish,
+        payload: &mut RollupBytes,
+    ) -> nix::Result<rollup_input_metadata>;
+
+    /// Reads the inspect state request announced by `finish`, its payload into `payload`.
+    fn read_inspect_state(&self, finish: &rollup_finish, payload: &mut RollupBytes) -> nix::Result<()>;
+
+    /// Writes a voucher, returning its index among the vouchers of the current input.
+    fn write_voucher(&self, destination: rollup_address, payload: &[u8]) -> nix::Result<u64>;
+
+    /// Writes a notice, returning its index among the notices of the current input.
+    fn write_notice(&self, payload: &[u8]) -> nix::Result<u64>;
+
+    fn write_report(&self, payload: &[u8]) -> nix::Result<()>;
+
+    fn throw_exception(&self, payload: &[u8]) -> nix::Result<()>;
+}
+
+/// Rollup device of a Cartesi machine, driven through IOCTL calls on its file descriptor.
+///
+/// The device owns the file descriptor and closes it on drop.
+#[derive(Debug)]
+pub struct IoctlDevice {
+    fd: OwnedFd,
+}
+
+impl IoctlDevice {
+    /// Creates a device owning `fd`.
+    pub fn new(fd: OwnedFd) -> Self {
+        Self { fd }
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, io::Error> {
+        let rollup_file = File::open(path)?;
+
+        Ok(Self::new(rollup_file.into()))
+    }
+}
+
+impl RollupDevice for IoctlDevice {
+    fn finish(&self, accept: bool) -> nix::Result<rollup_finish> {
+        bindings::rollup_finish_request(self.fd.as_raw_fd(), accept)
+    }
+
+    fn read_advance_state(
+        &self,
+        finish: &rollup_finish,
+        payload: &mut RollupBytes,
+    ) -> nix::Result<rollup_input_metadata> {
+        bindings::rollup_read_advance_state_request(self.fd.as_raw_fd(), finish, payload)
+    }
+
+    fn read_inspect_state(&self, finish: &rollup_finish, payload: &mut RollupBytes) -> nix::Result<()> {
+        bindings::rollup_read_inspect_state_request(self.fd.as_raw_fd(), finish, payload)
+    }
+
+    fn write_voucher(&self, destination: rollup_address, payload: &[u8]) -> nix::Result<u64> {
+        bindings::rollup_write_voucher(self.fd.as_raw_fd(), destination, payload)
+    }
+
+    fn write_notice(&self, payload: &[u8]) -> nix::Result<u64> {
+        bindings::rollup_write_notice(self.fd.as_raw_fd(), payload)
+    }
+
+    fn write_report(&self, payload: &[u8]) -> nix::Result<()> {
+        bindings::rollup_write_report(self.fd.as_raw_fd(), payload)
+    }
+
+    fn throw_exception(&self, payload: &[u8]) -> nix::Result<()> {
+        bindings::rollup_throw_exception(self.fd.as_raw_fd(), payload)
+    }
+}
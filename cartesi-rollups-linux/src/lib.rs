@@ -1,6 +1,16 @@
+#[cfg(feature = "cmio")]
+mod cmio;
 mod conversions;
+mod device;
 mod machine;
 mod rollups;
+mod simulated;
+mod stdio;
 
 pub use cartesi_rollups::*;
+#[cfg(feature = "cmio")]
+pub use cmio::*;
+pub use device::*;
 pub use machine::*;
+pub use simulated::*;
+pub use stdio::*;
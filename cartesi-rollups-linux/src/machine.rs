@@ -1,20 +1,44 @@
 use crate::rollups;
 use crate::rollups::{Exception, Notice, Report, Voucher};
-use cartesi_rollups::{MachineIo, RollupsRequest};
-use std::error::Error;
-use std::fs::File;
+use crate::{IoctlDevice, RollupDevice};
+use cartesi_rollups::{Address, FinishStatus, MachineIo, RollupsError, RollupsMetadata, RollupsRequest};
+use cartesi_rollups_bindings::RollupBytes;
+use std::cell::RefCell;
 use std::io;
-use std::os::unix::prelude::{IntoRawFd, RawFd};
+use std::os::fd::OwnedFd;
 use std::path::Path;
 
-pub struct LinuxMachine {
-    /// File descriptor pointing to the rollup device.
-    fd: RawFd,
+/// Request whose payload borrows the buffer of the [`LinuxMachine`] it was read into.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BorrowedRequest<'a> {
+    AdvanceState {
+        metadata: RollupsMetadata,
+        payload: &'a [u8],
+    },
+    InspectState {
+        payload: &'a [u8],
+    },
+}
+
+/// Machine talking to the rollup device of a Cartesi machine.
+///
+/// The device is the [`IoctlDevice`] of the Cartesi machine by default. A [`SimulatedDevice`] runs the same logic on
+/// any host.
+///
+/// Requests from [`MachineIo::submit`] own their payload, copied once out of the buffer of the machine. Use
+/// [`LinuxMachine::submit_with`] to borrow it instead.
+///
+/// [`SimulatedDevice`]: crate::SimulatedDevice
+pub struct LinuxMachine<D = IoctlDevice> {
+    device: D,
+    /// Buffer the payload of every request is read into, grown to the largest payload so far.
+    buffer: RefCell<RollupBytes>,
 }
 
 impl LinuxMachine {
-    pub fn new(fd: RawFd) -> Self {
-        Self { fd }
+    /// Creates a machine owning `fd`, closed when the machine is dropped.
+    pub fn new(fd: OwnedFd) -> Self {
+        Self::with_device(IoctlDevice::new(fd))
     }
 
     pub fn open_default_device() -> Result<Self, io::Error> {
@@ -22,41 +46,184 @@ impl LinuxMachine {
     }
 
     pub fn open(path: impl AsRef<Path>) -> Result<Self, io::Error> {
-        let rollup_file = File::open(path)?;
-        let fd = rollup_file.into_raw_fd();
+        Ok(Self::with_device(IoctlDevice::open(path)?))
+    }
+}
+
+impl<D: RollupDevice> LinuxMachine<D> {
+    pub fn with_device(device: D) -> Self {
+        Self {
+            device,
+            buffer: RefCell::new(RollupBytes::new()),
+        }
+    }
+
+    pub fn device(&self) -> &D {
+        &self.device
+    }
+
+    /// Finishes the previous request with `status` and passes the next one to `handler`, borrowing its payload.
+    ///
+    /// The payload stays in the buffer of the machine, which is reused by the next request, so `handler` may write
+    /// outputs but must not submit.
+    pub fn submit_with<T>(
+        &self,
+        status: FinishStatus,
+        handler: impl FnOnce(BorrowedRequest<'_>) -> T,
+    ) -> Result<T, RollupsError> {
+        let accept = matches!(status, FinishStatus::Accept);
+        let finish = rollups::perform_rollup_finish_request(&self.device, accept)?;
+
+        let mut buffer = self.buffer.borrow_mut();
+        let request = rollups::handle_rollup_requests(&self.device, finish, &mut buffer)?;
+
+        Ok(handler(request.into()))
+    }
+}
+
+impl<D: RollupDevice> MachineIo for LinuxMachine<D> {
+    fn write_notice(&self, payload: &[u8]) -> Result<usize, RollupsError> {
+        rollups::write_notice(&self.device, &Notice::from(payload)).map(|v| v as usize)
+    }
+
+    fn write_voucher(&self, address: &Address, payload: &[u8]) -> Result<usize, RollupsError> {
+        rollups::write_voucher(&self.device, &Voucher::from((address.as_bytes(), payload))).map(|v| v as usize)
+    }
 
-        Ok(Self::new(fd))
+    fn write_report(&self, payload: &[u8]) -> Result<(), RollupsError> {
+        rollups::write_report(&self.device, &Report::from(payload))
+    }
+
+    fn submit(&self, status: FinishStatus) -> Result<RollupsRequest, RollupsError> {
+        self.submit_with(status, |request| request.into())
+    }
+
+    fn throw_exception(&self, payload: &[u8]) -> Result<(), RollupsError> {
+        rollups::throw_exception(&self.device, &Exception::from(payload))
     }
 }
 
-impl MachineIo for LinuxMachine {
-    fn write_notice(&self, payload: &[u8]) -> Result<usize, Box<dyn Error>> {
-        let mut notice = Notice::try_from(payload)?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MetadataVersion, RollupsMetadata, SimulatedDevice};
+    use nix::errno::Errno;
 
-        rollups::write_notice(self.fd, &mut notice).map(|v| v as usize)
+    fn advance(input_index: u64, payload: &[u8]) -> RollupsRequest {
+        RollupsRequest::AdvanceState {
+            metadata: RollupsMetadata {
+                msg_sender: Address::new([0xa1; 20]),
+                epoch_index: 1,
+                input_index,
+                block_number: 42,
+                timestamp: 1_700_000_000,
+                version: MetadataVersion::V1,
+            },
+            payload: payload.to_vec(),
+        }
     }
 
-    fn write_voucher(&self, address: &[u8; 20], payload: &[u8]) -> Result<usize, Box<dyn Error>> {
-        let mut voucher = Voucher::try_from((address, payload))?;
+    fn inspect(payload: &[u8]) -> RollupsRequest {
+        RollupsRequest::InspectState {
+            payload: payload.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_requests_are_read_from_device() {
+        let machine = LinuxMachine::with_device(SimulatedDevice::new([
+            advance(7, b"a longer first payload"),
+            inspect(b"short"),
+        ]));
+
+        match machine.submit(FinishStatus::Accept).unwrap() {
+            RollupsRequest::AdvanceState { metadata, payload } => {
+                assert_eq!(Address::new([0xa1; 20]), metadata.msg_sender);
+                assert_eq!(1, metadata.epoch_index);
+                assert_eq!(7, metadata.input_index);
+                assert_eq!(42, metadata.block_number);
+                assert_eq!(1_700_000_000, metadata.timestamp);
+                assert_eq!(b"a longer first payload".to_vec(), payload);
+            }
+            request => panic!("unexpected request {:?}", request),
+        }
+
+        let capacity = machine.buffer.borrow().capacity();
+
+        match machine.submit(FinishStatus::Accept).unwrap() {
+            RollupsRequest::InspectState { payload } => assert_eq!(b"short".to_vec(), payload),
+            request => panic!("unexpected request {:?}", request),
+        }
 
-        rollups::write_voucher(self.fd, &mut voucher).map(|v| v as usize)
+        assert_eq!(capacity, machine.buffer.borrow().capacity());
+
+        let error = machine.submit(FinishStatus::Accept).unwrap_err();
+
+        assert_eq!(Some(Errno::ENODATA as i32), error.errno());
     }
 
-    fn write_report(&self, payload: &[u8]) -> Result<(), Box<dyn Error>> {
-        let report = Report::try_from(payload)?;
+    #[test]
+    fn test_submit_with_borrows_payload_from_buffer() {
+        let machine = LinuxMachine::with_device(SimulatedDevice::new([advance(3, b"borrowed"), inspect(b"query")]));
+
+        let length = machine
+            .submit_with(FinishStatus::Accept, |request| {
+                let BorrowedRequest::AdvanceState { metadata, payload } = request else {
+                    panic!("unexpected request {:?}", request);
+                };
+
+                assert_eq!(3, metadata.input_index);
+                assert_eq!(0, machine.write_notice(payload).unwrap());
+                payload.len()
+            })
+            .unwrap();
 
-        rollups::write_report(self.fd, &report)
+        assert_eq!(8, length);
+        assert_eq!(
+            RollupsRequest::InspectState {
+                payload: b"query".to_vec()
+            },
+            machine.submit(FinishStatus::Accept).unwrap()
+        );
+        assert_eq!(vec![b"borrowed".to_vec()], machine.device().notices());
     }
 
-    fn submit(&self) -> Result<RollupsRequest, Box<dyn Error>> {
-        let finish = rollups::perform_rollup_finish_request(self.fd, true)?;
+    #[test]
+    fn test_outputs_follow_driver_rules() {
+        let destination = Address::new([0x70; 20]);
+        let machine = LinuxMachine::with_device(SimulatedDevice::new([advance(0, b""), advance(1, b""), inspect(b"")]));
 
-        Ok(rollups::handle_rollup_requests(self.fd, finish).map(Into::into)?)
+        machine.submit(FinishStatus::Accept).unwrap();
+        assert_eq!(0, machine.write_voucher(&destination, b"first").unwrap());
+        assert_eq!(1, machine.write_voucher(&destination, b"second").unwrap());
+        assert_eq!(0, machine.write_notice(b"kept").unwrap());
+
+        machine.submit(FinishStatus::Accept).unwrap();
+        assert_eq!(0, machine.write_notice(b"discarded").unwrap());
+        machine.write_report(b"report").unwrap();
+
+        machine.submit(FinishStatus::Reject).unwrap();
+        let error = machine.write_voucher(&destination, b"inspecting").unwrap_err();
+
+        assert_eq!(Some(Errno::EOPNOTSUPP as i32), error.errno());
+        assert_eq!(
+            vec![(destination, b"first".to_vec()), (destination, b"second".to_vec())],
+            machine.device().vouchers()
+        );
+        assert_eq!(vec![b"kept".to_vec()], machine.device().notices());
+        assert_eq!(vec![b"report".to_vec()], machine.device().reports());
     }
 
-    fn throw_exception(&self, payload: &[u8]) -> Result<(), Box<dyn Error>> {
-        let exception = Exception::try_from(payload)?;
+    #[test]
+    fn test_output_limit_is_payload_too_large() {
+        let machine = LinuxMachine::with_device(SimulatedDevice::new([advance(0, b"")]).with_output_limit(4));
+
+        machine.submit(FinishStatus::Accept).unwrap();
+        machine.write_notice(b"fits").unwrap();
 
-        rollups::throw_exception(self.fd, &exception)
+        assert!(matches!(
+            machine.write_notice(b"too large"),
+            Err(RollupsError::PayloadTooLarge { length: 9, .. })
+        ));
     }
 }
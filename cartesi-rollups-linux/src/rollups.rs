@@ -1,32 +1,16 @@
 //! Implements Rust api to use Linux rollup device
+use crate::conversions::device_error;
+use crate::RollupDevice;
+use cartesi_rollups::RollupsError;
 use cartesi_rollups_bindings as bindings;
-use serde::{Deserialize, Serialize};
-use std::io;
-use std::io::ErrorKind;
-use std::os::unix::prelude::RawFd;
-use thiserror::Error;
+use cartesi_rollups_bindings::RollupBytes;
 
-pub use bindings::CARTESI_ROLLUP_ADDRESS_SIZE;
 pub use bindings::CARTESI_ROLLUP_ADVANCE_STATE;
 pub use bindings::CARTESI_ROLLUP_INSPECT_STATE;
 
 /// Rollup device driver path
 pub const ROLLUP_DEVICE_NAME: &str = "/dev/rollup";
 
-#[derive(Debug, Default, Error)]
-#[error("rollup error: {}", message)]
-pub struct RollupError {
-    message: String,
-}
-
-impl RollupError {
-    pub fn new(message: &str) -> Self {
-        Self {
-            message: message.to_owned(),
-        }
-    }
-}
-
 #[derive(Debug, Default, Clone, PartialEq, Eq, Copy)]
 pub struct RollupFinish {
     pub accept_previous_request: bool,
@@ -60,95 +44,74 @@ impl From<bindings::rollup_finish> for RollupFinish {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone)]
 pub struct AdvanceMetadata {
-    pub msg_sender: String,
+    pub msg_sender: [u8; bindings::CARTESI_ROLLUP_ADDRESS_SIZE],
     pub epoch_index: u64,
     pub input_index: u64,
     pub block_number: u64,
     pub timestamp: u64,
 }
 
-impl AdvanceMetadata {
-    /// Returns the sender address with the `Ox` prefix stripped if present.
-    pub fn msg_sender_without_prefix(&self) -> &str {
-        match self.msg_sender.starts_with("0x") {
-            true => &self.msg_sender[2..],
-            false => &self.msg_sender,
-        }
-    }
-}
-
 impl From<bindings::rollup_input_metadata> for AdvanceMetadata {
     fn from(other: bindings::rollup_input_metadata) -> Self {
-        let address = format!("0x{}", hex::encode(other.msg_sender));
-
         Self {
             input_index: other.input_index,
             epoch_index: other.epoch_index,
             timestamp: other.timestamp,
             block_number: other.block_number,
-            msg_sender: address,
+            msg_sender: other.msg_sender,
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AdvanceRequest {
+/// Advance state request whose payload borrows the buffer it was read into.
+#[derive(Debug, Clone)]
+pub struct AdvanceRequest<'a> {
     pub metadata: AdvanceMetadata,
-    pub payload: String,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct InspectRequest {
-    pub payload: String,
+    pub payload: &'a [u8],
 }
 
-pub enum RollupRequest {
-    Inspect(InspectRequest),
-    Advance(AdvanceRequest),
+/// Inspect state request whose payload borrows the buffer it was read into.
+#[derive(Debug, Clone)]
+pub struct InspectRequest<'a> {
+    pub payload: &'a [u8],
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct InspectReport {
-    pub reports: Vec<Report>,
+pub enum RollupRequest<'a> {
+    Inspect(InspectRequest<'a>),
+    Advance(AdvanceRequest<'a>),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Notice {
-    pub payload: String,
+#[derive(Debug, Clone)]
+pub struct Notice<'a> {
+    pub payload: &'a [u8],
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Voucher {
-    pub destination: String,
-    pub payload: String,
-}
-
-impl Voucher {
-    /// Returns the destination address with the `Ox` prefix stripped if present.
-    pub fn destination_without_prefix(&self) -> &str {
-        match self.destination.starts_with("0x") {
-            true => &self.destination[2..],
-            false => &self.destination,
-        }
-    }
+#[derive(Debug, Clone)]
+pub struct Voucher<'a> {
+    pub destination: &'a [u8; bindings::CARTESI_ROLLUP_ADDRESS_SIZE],
+    pub payload: &'a [u8],
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Report {
-    pub payload: String,
+#[derive(Debug, Clone)]
+pub struct Report<'a> {
+    pub payload: &'a [u8],
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Exception {
-    pub payload: String,
+#[derive(Debug, Clone)]
+pub struct Exception<'a> {
+    pub payload: &'a [u8],
 }
 
-pub fn finish_request(fd: RawFd, finish: &mut RollupFinish, accept: bool) -> Result<(), Box<dyn std::error::Error>> {
+pub fn finish_request<D: RollupDevice + ?Sized>(
+    device: &D,
+    finish: &mut RollupFinish,
+    accept: bool,
+) -> Result<(), RollupsError> {
     log::debug!("writing rollup finish request, yielding");
 
-    let finish_c = bindings::rollup_finish_request(fd, accept)?;
+    let finish_c = device.finish(accept).map_err(|e| device_error(e, 0))?;
 
     *finish = RollupFinish::from(finish_c);
 
@@ -157,195 +120,122 @@ pub fn finish_request(fd: RawFd, finish: &mut RollupFinish, accept: bool) -> Res
     Ok(())
 }
 
-pub fn read_advance_state_request(
-    fd: RawFd,
+/// Reads an advance state request, its payload into `buffer`.
+pub fn read_advance_state_request<'a, D: RollupDevice + ?Sized>(
+    device: &D,
     finish: &mut RollupFinish,
-) -> Result<AdvanceRequest, Box<dyn std::error::Error>> {
+    buffer: &'a mut RollupBytes,
+) -> Result<AdvanceRequest<'a>, RollupsError> {
     let finish_c = bindings::rollup_finish::from(&mut *finish);
-    let mut bytes_c = Box::new(bindings::rollup_bytes {
-        data: std::ptr::null::<std::os::raw::c_uchar>() as *mut std::os::raw::c_uchar,
-        length: 0,
-    });
-
-    let input_metadata_c = bindings::rollup_read_advance_state_request(fd, &finish_c, &mut bytes_c)?;
 
-    if bytes_c.length == 0 {
-        log::info!("read zero size payload from advance state request");
-    }
+    let length = finish.next_request_payload_length.max(0) as usize;
+    let input_metadata_c = device
+        .read_advance_state(&finish_c, buffer)
+        .map_err(|e| device_error(e, length))?;
 
-    let mut payload: Vec<u8> = Vec::with_capacity(bytes_c.length as usize);
-
-    if bytes_c.length > 0 {
-        unsafe {
-            std::ptr::copy(bytes_c.data, payload.as_mut_ptr(), bytes_c.length as usize);
-            payload.set_len(bytes_c.length as usize);
-        }
+    if finish.next_request_payload_length == 0 {
+        log::debug!("read zero size payload from advance state request");
     }
 
     let result = AdvanceRequest {
         metadata: AdvanceMetadata::from(input_metadata_c),
-        payload: "0x".to_string() + &hex::encode(&payload),
+        payload: buffer,
     };
     *finish = RollupFinish::from(finish_c);
 
     Ok(result)
 }
 
-pub fn read_inspect_state_request(
-    fd: RawFd,
+/// Reads an inspect state request, its payload into `buffer`.
+pub fn read_inspect_state_request<'a, D: RollupDevice + ?Sized>(
+    device: &D,
     finish: &mut RollupFinish,
-) -> Result<InspectRequest, Box<dyn std::error::Error>> {
+    buffer: &'a mut RollupBytes,
+) -> Result<InspectRequest<'a>, RollupsError> {
     let finish_c = bindings::rollup_finish::from(&mut *finish);
-    let mut bytes_c = Box::new(bindings::rollup_bytes {
-        data: std::ptr::null::<std::os::raw::c_uchar>() as *mut std::os::raw::c_uchar,
-        length: 0,
-    });
 
-    bindings::rollup_read_inspect_state_request(fd, &finish_c, &mut bytes_c)?;
+    let length = finish.next_request_payload_length.max(0) as usize;
+    device
+        .read_inspect_state(&finish_c, buffer)
+        .map_err(|e| device_error(e, length))?;
 
-    let mut payload: Vec<u8> = Vec::with_capacity(bytes_c.length as usize);
-    unsafe {
-        std::ptr::copy(bytes_c.data, payload.as_mut_ptr(), bytes_c.length as usize);
-        payload.set_len(bytes_c.length as usize);
-    }
-    let result = InspectRequest {
-        payload: format!("0x{}", hex::encode(&payload)),
-    };
+    let result = InspectRequest { payload: buffer };
     *finish = RollupFinish::from(finish_c);
 
     Ok(result)
 }
 
-pub fn write_notice(fd: RawFd, notice: &mut Notice) -> Result<u64, Box<dyn std::error::Error>> {
-    log::debug!(
-        "Notice: {{ length: {} payload: {} }}",
-        notice.payload.len(),
-        notice.payload
-    );
+pub fn write_notice<D: RollupDevice + ?Sized>(device: &D, notice: &Notice) -> Result<u64, RollupsError> {
+    log::debug!("notice: {{ length: {} }}", notice.payload.len());
 
-    let binary_payload = hex::decode(&notice.payload[2..]).map_err(|_| {
-        Box::new(RollupError::new(
-            "Error decoding notice payload, payload must be in Ethereum hex binary format",
-        ))
-    })?;
-    let mut buffer: Vec<u8> = Vec::with_capacity(binary_payload.len());
-    let mut bytes_c = Box::new(bindings::rollup_bytes {
-        data: buffer.as_mut_ptr() as *mut std::os::raw::c_uchar,
-        length: binary_payload.len() as u64,
-    });
-
-    let notice_index = unsafe {
-        std::ptr::copy(binary_payload.as_ptr(), buffer.as_mut_ptr(), binary_payload.len());
-        bindings::rollup_write_notice(fd, &mut bytes_c)
-    }?;
+    let notice_index = device
+        .write_notice(notice.payload)
+        .map_err(|e| device_error(e, notice.payload.len()))?;
 
     log::debug!("notice with id {} successfully written!", notice_index);
 
     Ok(notice_index)
 }
 
-pub fn write_voucher(fd: RawFd, voucher: &mut Voucher) -> Result<u64, Box<dyn std::error::Error>> {
+pub fn write_voucher<D: RollupDevice + ?Sized>(device: &D, voucher: &Voucher) -> Result<u64, RollupsError> {
     log::debug!(
-        "voucher: {{ destination: 0x{} length: {} payload: {} }}",
-        voucher.destination_without_prefix(),
-        voucher.payload.len(),
-        voucher.payload
+        "voucher: {{ destination: 0x{} length: {} }}",
+        hex::encode(voucher.destination),
+        voucher.payload.len()
     );
 
-    let binary_payload = hex::decode(&voucher.payload[2..]).map_err(|_| {
-        Box::new(RollupError::new(
-            "Error decoding voucher payload, payload must be in Ethereum hex binary format",
-        ))
-    })?;
-    let mut buffer: Vec<u8> = Vec::with_capacity(binary_payload.len());
-    let mut bytes_c = Box::new(bindings::rollup_bytes {
-        data: buffer.as_mut_ptr() as *mut std::os::raw::c_uchar,
-        length: binary_payload.len() as u64,
-    });
-    let destination_c = hex::decode(&voucher.destination[2..])
-        .map_err(|e| Box::new(RollupError::new(&format!("destination address not valid: {}", e))))?;
-
-    let voucher_index = unsafe {
-        std::ptr::copy(binary_payload.as_ptr(), buffer.as_mut_ptr(), binary_payload.len());
-        bindings::rollup_write_voucher(fd, destination_c.try_into().unwrap(), &mut bytes_c)
-    }?;
+    let voucher_index = device
+        .write_voucher(*voucher.destination, voucher.payload)
+        .map_err(|e| device_error(e, voucher.payload.len()))?;
 
     log::debug!("voucher with id {} successfully written!", voucher_index);
 
     Ok(voucher_index)
 }
 
-pub fn write_report(fd: RawFd, report: &Report) -> Result<(), Box<dyn std::error::Error>> {
-    log::debug!(
-        "report: {{ length: {} payload: {}}}",
-        report.payload.len(),
-        report.payload
-    );
-
-    let binary_payload = match hex::decode(&report.payload[2..]) {
-        Ok(payload) => payload,
-        Err(_err) => {
-            return Err(Box::new(RollupError::new(
-                "Error decoding report payload, payload must be in Ethereum hex binary format",
-            )));
-        }
-    };
-    let mut buffer: Vec<u8> = Vec::with_capacity(binary_payload.len());
-    let mut bytes_c = Box::new(bindings::rollup_bytes {
-        data: buffer.as_mut_ptr() as *mut std::os::raw::c_uchar,
-        length: binary_payload.len() as u64,
-    });
+pub fn write_report<D: RollupDevice + ?Sized>(device: &D, report: &Report) -> Result<(), RollupsError> {
+    log::debug!("report: {{ length: {} }}", report.payload.len());
 
-    unsafe {
-        std::ptr::copy(binary_payload.as_ptr(), buffer.as_mut_ptr(), binary_payload.len());
-        bindings::rollup_write_report(fd, &mut bytes_c)
-    }?;
+    device
+        .write_report(report.payload)
+        .map_err(|e| device_error(e, report.payload.len()))?;
 
     log::debug!("report successfully written!");
 
     Ok(())
 }
 
-pub fn throw_exception(fd: RawFd, exception: &Exception) -> Result<(), Box<dyn std::error::Error>> {
-    log::debug!(
-        "exception: {{ length: {} payload: {}}}",
-        exception.payload.len(),
-        exception.payload
-    );
+pub fn throw_exception<D: RollupDevice + ?Sized>(device: &D, exception: &Exception) -> Result<(), RollupsError> {
+    log::debug!("exception: {{ length: {} }}", exception.payload.len());
 
-    let binary_payload = hex::decode(&exception.payload[2..]).map_err(|_| {
-        Box::new(RollupError::new(
-            "Error decoding report payload, payload must be in Ethereum hex binary format",
-        ))
-    })?;
-    let mut buffer: Vec<u8> = Vec::with_capacity(binary_payload.len());
-    let mut bytes_c = Box::new(bindings::rollup_bytes {
-        data: buffer.as_mut_ptr() as *mut std::os::raw::c_uchar,
-        length: binary_payload.len() as u64,
-    });
-
-    unsafe {
-        std::ptr::copy(binary_payload.as_ptr(), buffer.as_mut_ptr(), binary_payload.len());
-        bindings::rollup_throw_exception(fd, &mut bytes_c)
-    }?;
+    device
+        .throw_exception(exception.payload)
+        .map_err(|e| device_error(e, exception.payload.len()))?;
 
     log::debug!("exception successfully thrown!");
 
     Ok(())
 }
 
-pub fn perform_rollup_finish_request(fd: RawFd, accept: bool) -> io::Result<RollupFinish> {
+pub fn perform_rollup_finish_request<D: RollupDevice + ?Sized>(
+    device: &D,
+    accept: bool,
+) -> Result<RollupFinish, RollupsError> {
     let mut finish = RollupFinish::default();
 
-    finish_request(fd, &mut finish, accept).map(|_| finish).map_err(|e| {
-        log::error!("error inserting finish request, details: {}", e.to_string());
-
-        io::Error::new(ErrorKind::Other, e.to_string())
-    })
+    finish_request(device, &mut finish, accept)
+        .map(|_| finish)
+        .inspect_err(|e| {
+            log::error!("error inserting finish request, details: {}", e);
+        })
 }
 
-/// Read advance/inspect request from rollup device
-pub fn handle_rollup_requests(fd: RawFd, mut finish_request: RollupFinish) -> Result<RollupRequest, io::Error> {
+/// Read advance/inspect request from rollup device, its payload into `buffer`
+pub fn handle_rollup_requests<'a, D: RollupDevice + ?Sized>(
+    device: &D,
+    mut finish_request: RollupFinish,
+    buffer: &'a mut RollupBytes,
+) -> Result<RollupRequest<'a>, RollupsError> {
     let next_request_type = finish_request.next_request_type as u32;
 
     match next_request_type {
@@ -353,12 +243,11 @@ pub fn handle_rollup_requests(fd: RawFd, mut finish_request: RollupFinish) -> Re
             log::debug!("handle advance state request...");
 
             // Read advance request from rollup device
-            let advance_request = read_advance_state_request(fd, &mut finish_request)
-                .map_err(|e| io::Error::new(ErrorKind::Other, e.to_string()))?;
+            let advance_request = read_advance_state_request(device, &mut finish_request, buffer)?;
 
-            log::info!(
+            log::debug!(
                 "advance: {{ msg_sender: {} block_number: {} timestamp: {} epoch_index: {} input_index: {} }}",
-                advance_request.metadata.msg_sender_without_prefix(),
+                hex::encode(advance_request.metadata.msg_sender),
                 advance_request.metadata.block_number,
                 advance_request.metadata.timestamp,
                 advance_request.metadata.epoch_index,
@@ -372,18 +261,13 @@ pub fn handle_rollup_requests(fd: RawFd, mut finish_request: RollupFinish) -> Re
             log::debug!("handle inspect state request...");
 
             // Read inspect request from rollup device
-            let inspect_request = read_inspect_state_request(fd, &mut finish_request)
-                .map_err(|e| io::Error::new(ErrorKind::Other, e.to_string()))?;
+            let inspect_request = read_inspect_state_request(device, &mut finish_request, buffer)?;
 
-            log::info!(
-                "inspect: {{ length: {} payload: {}}}",
-                inspect_request.payload.len(),
-                inspect_request.payload
-            );
+            log::debug!("inspect: {{ length: {} }}", inspect_request.payload.len());
 
             // Send newly read inspect request to http service
             Ok(RollupRequest::Inspect(inspect_request))
         }
-        _ => Err(io::Error::new(ErrorKind::Unsupported, "request type unsupported")),
+        request_type => Err(RollupsError::UnsupportedRequestType(request_type)),
     }
 }
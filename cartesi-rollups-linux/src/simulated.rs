@@ -0,0 +1,225 @@
+use crate::RollupDevice;
+use cartesi_rollups::{Address, RollupsRequest};
+use cartesi_rollups_bindings::{
+    rollup_address, rollup_finish, rollup_input_metadata, RollupBytes, CARTESI_ROLLUP_ADVANCE_STATE,
+    CARTESI_ROLLUP_INSPECT_STATE,
+};
+use nix::errno::Errno;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+
+/// In-process [`RollupDevice`] following the rules of the rollup driver, to run a [`LinuxMachine`] off-device.
+///
+/// It serves its requests in order and behaves as the driver does:
+///
+/// * finishing announces the type and payload length of the next request, and fails with [`Errno::ENODATA`] once there
+///   are no requests left or after an exception was thrown;
+/// * reading a request of the other type than the one announced fails with [`Errno::EOPNOTSUPP`], so does writing a
+///   voucher or notice while inspecting, or writing anything before the first request;
+/// * vouchers and notices are indexed from zero within each input, and rejecting an input discards them;
+/// * reports are kept regardless of whether the input is accepted.
+///
+/// [`LinuxMachine`]: crate::LinuxMachine
+#[derive(Debug)]
+pub struct SimulatedDevice {
+    state: RefCell<State>,
+    /// Largest output payload accepted, longer ones fail with [`Errno::ENOBUFS`].
+    output_limit: usize,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    requests: VecDeque<RollupsRequest>,
+    current: Option<RollupsRequest>,
+    halted: bool,
+    pending_vouchers: Vec<(Address, Vec<u8>)>,
+    pending_notices: Vec<Vec<u8>>,
+    vouchers: Vec<(Address, Vec<u8>)>,
+    notices: Vec<Vec<u8>>,
+    reports: Vec<Vec<u8>>,
+    exception: Option<Vec<u8>>,
+}
+
+impl SimulatedDevice {
+    pub fn new(requests: impl IntoIterator<Item = RollupsRequest>) -> Self {
+        Self {
+            state: RefCell::new(State {
+                requests: requests.into_iter().collect(),
+                ..Default::default()
+            }),
+            output_limit: usize::MAX,
+        }
+    }
+
+    pub fn with_output_limit(mut self, output_limit: usize) -> Self {
+        self.output_limit = output_limit;
+        self
+    }
+
+    /// Returns the vouchers of the accepted inputs.
+    pub fn vouchers(&self) -> Vec<(Address, Vec<u8>)> {
+        self.state.borrow().vouchers.clone()
+    }
+
+    /// Returns the notices of the accepted inputs.
+    pub fn notices(&self) -> Vec<Vec<u8>> {
+        self.state.borrow().notices.clone()
+    }
+
+    pub fn reports(&self) -> Vec<Vec<u8>> {
+        self.state.borrow().reports.clone()
+    }
+
+    pub fn exception(&self) -> Option<Vec<u8>> {
+        self.state.borrow().exception.clone()
+    }
+
+    fn check_output(&self, payload: &[u8]) -> nix::Result<()> {
+        match payload.len() > self.output_limit {
+            true => Err(Errno::ENOBUFS),
+            false => Ok(()),
+        }
+    }
+}
+
+impl Default for SimulatedDevice {
+    fn default() -> Self {
+        Self::new([])
+    }
+}
+
+impl State {
+    fn is_advancing(&self) -> bool {
+        matches!(self.current, Some(RollupsRequest::AdvanceState { .. }))
+    }
+}
+
+impl RollupDevice for SimulatedDevice {
+    fn finish(&self, accept: bool) -> nix::Result<rollup_finish> {
+        let mut state = self.state.borrow_mut();
+        let vouchers = std::mem::take(&mut state.pending_vouchers);
+        let notices = std::mem::take(&mut state.pending_notices);
+
+        if accept && state.is_advancing() {
+            state.vouchers.extend(vouchers);
+            state.notices.extend(notices);
+        }
+
+        state.current = match state.halted {
+            true => None,
+            false => state.requests.pop_front(),
+        };
+
+        let (next_request_type, payload) = match state.current.as_ref().ok_or(Errno::ENODATA)? {
+            RollupsRequest::AdvanceState { payload, .. } => (CARTESI_ROLLUP_ADVANCE_STATE, payload),
+            RollupsRequest::InspectState { payload } => (CARTESI_ROLLUP_INSPECT_STATE, payload),
+        };
+
+        Ok(rollup_finish {
+            accept_previous_request: accept,
+            next_request_type: next_request_type as i32,
+            next_request_payload_length: payload.len().try_into().map_err(|_| Errno::E2BIG)?,
+        })
+    }
+
+    fn read_advance_state(
+        &self,
+        finish: &rollup_finish,
+        payload: &mut RollupBytes,
+    ) -> nix::Result<rollup_input_metadata> {
+        let state = self.state.borrow();
+
+        match &state.current {
+            Some(RollupsRequest::AdvanceState {
+                metadata,
+                payload: input,
+            }) => {
+                read_payload(finish, input, payload)?;
+
+                Ok(rollup_input_metadata {
+                    msg_sender: metadata.msg_sender.to_bytes(),
+                    block_number: metadata.block_number,
+                    timestamp: metadata.timestamp,
+                    epoch_index: metadata.epoch_index,
+                    input_index: metadata.input_index,
+                })
+            }
+            _ => Err(Errno::EOPNOTSUPP),
+        }
+    }
+
+    fn read_inspect_state(&self, finish: &rollup_finish, payload: &mut RollupBytes) -> nix::Result<()> {
+        match &self.state.borrow().current {
+            Some(RollupsRequest::InspectState { payload: query }) => read_payload(finish, query, payload),
+            _ => Err(Errno::EOPNOTSUPP),
+        }
+    }
+
+    fn write_voucher(&self, destination: rollup_address, payload: &[u8]) -> nix::Result<u64> {
+        let mut state = self.state.borrow_mut();
+
+        if !state.is_advancing() {
+            return Err(Errno::EOPNOTSUPP);
+        }
+        self.check_output(payload)?;
+        state
+            .pending_vouchers
+            .push((Address::new(destination), payload.to_vec()));
+
+        Ok(state.pending_vouchers.len() as u64 - 1)
+    }
+
+    fn write_notice(&self, payload: &[u8]) -> nix::Result<u64> {
+        let mut state = self.state.borrow_mut();
+
+        if !state.is_advancing() {
+            return Err(Errno::EOPNOTSUPP);
+        }
+        self.check_output(payload)?;
+        state.pending_notices.push(payload.to_vec());
+
+        Ok(state.pending_notices.len() as u64 - 1)
+    }
+
+    fn write_report(&self, payload: &[u8]) -> nix::Result<()> {
+        let mut state = self.state.borrow_mut();
+
+        if state.current.is_none() {
+            return Err(Errno::EOPNOTSUPP);
+        }
+        self.check_output(payload)?;
+        state.reports.push(payload.to_vec());
+
+        Ok(())
+    }
+
+    fn throw_exception(&self, payload: &[u8]) -> nix::Result<()> {
+        let mut state = self.state.borrow_mut();
+
+        if state.current.is_none() {
+            return Err(Errno::EOPNOTSUPP);
+        }
+        self.check_output(payload)?;
+        state.pending_vouchers.clear();
+        state.pending_notices.clear();
+        state.exception = Some(payload.to_vec());
+        state.current = None;
+        state.halted = true;
+
+        Ok(())
+    }
+}
+
+/// Copies `input` into `payload`, which has to be sized after the length announced by `finish` as on the device.
+fn read_payload(finish: &rollup_finish, input: &[u8], payload: &mut RollupBytes) -> nix::Result<()> {
+    let announced: usize = finish
+        .next_request_payload_length
+        .try_into()
+        .map_err(|_| Errno::EINVAL)?;
+
+    if announced < input.len() {
+        return Err(Errno::ENOBUFS);
+    }
+
+    payload.copy_from_slice(input)
+}
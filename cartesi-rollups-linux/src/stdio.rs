@@ -0,0 +1,251 @@
+//! Items in this module drive a DApp with JSON lines, so any harness can run it as a native process.
+//!
+//! Each line read is a [`RollupsRequest`], each line written is an [`Event`]:
+//!
+//! ```text
+//! < {"type":"advance_state","metadata":{"msg_sender":"0x…","epoch_index":0,"input_index":0,"block_number":0,"timestamp":0},"payload":"0x01"}
+//! > {"type":"notice","index":0,"payload":"0x01"}
+//! > {"type":"finish","status":"accept"}
+//! < {"type":"inspect_state","payload":"0x"}
+//! ```
+//!
+//! Blank lines are skipped. Reaching the end of the input is [`RollupsError::QueueExhausted`]. Writing a notice or a
+//! voucher while inspecting fails with the error of the rollup device.
+use crate::conversions::device_error;
+use cartesi_rollups::{Address, Event, FinishStatus, MachineIo, RollupsError, RollupsRequest, U256};
+use nix::errno::Errno;
+use std::cell::{Cell, RefCell};
+use std::io;
+use std::io::{BufRead, StdinLock, Stdout, Write};
+
+/// Machine reading requests and writing outputs as JSON lines, see the [module-level documentation](./index.html).
+///
+/// It reads stdin and writes stdout by default, so the logs of the DApp have to go to stderr, where `env_logger` writes
+/// them. Notices and vouchers are indexed within each input, as on the rollup device.
+pub struct StdioMachine<R = StdinLock<'static>, W = Stdout> {
+    reader: RefCell<R>,
+    writer: RefCell<W>,
+    notices: Cell<usize>,
+    vouchers: Cell<usize>,
+    inspecting: Cell<bool>,
+    app_contract: Cell<Option<Address>>,
+}
+
+impl StdioMachine {
+    /// Creates a machine reading stdin and writing stdout.
+    pub fn new() -> Self {
+        Self::with_io(io::stdin().lock(), io::stdout())
+    }
+}
+
+impl Default for StdioMachine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<R: BufRead, W: Write> StdioMachine<R, W> {
+    pub fn with_io(reader: R, writer: W) -> Self {
+        Self {
+            reader: RefCell::new(reader),
+            writer: RefCell::new(writer),
+            notices: Cell::new(0),
+            vouchers: Cell::new(0),
+            inspecting: Cell::new(false),
+            app_contract: Cell::new(None),
+        }
+    }
+
+    pub fn into_inner(self) -> (R, W) {
+        (self.reader.into_inner(), self.writer.into_inner())
+    }
+
+    fn write_event(&self, event: &Event) -> Result<(), RollupsError> {
+        let mut writer = self.writer.borrow_mut();
+
+        serde_json::to_writer(&mut *writer, event)
+            .map_err(io::Error::from)
+            .and_then(|_| writer.write_all(b"\n"))
+            .and_then(|_| writer.flush())
+            .map_err(RollupsError::Device)
+    }
+
+    /// Fails like the rollup device when writing a notice or a voucher of `length` bytes while inspecting.
+    fn check_advancing(&self, length: usize) -> Result<(), RollupsError> {
+        match self.inspecting.get() {
+            true => Err(device_error(Errno::EOPNOTSUPP, length)),
+            false => Ok(()),
+        }
+    }
+
+    /// Reads the next non-blank line.
+    fn read_line(&self) -> Result<Option<String>, RollupsError> {
+        let mut reader = self.reader.borrow_mut();
+        let mut line = String::new();
+
+        loop {
+            line.clear();
+
+            if reader.read_line(&mut line).map_err(RollupsError::Device)? == 0 {
+                return Ok(None);
+            }
+            if !line.trim().is_empty() {
+                return Ok(Some(line));
+            }
+        }
+    }
+}
+
+impl<R: BufRead, W: Write> MachineIo for StdioMachine<R, W> {
+    fn write_notice(&self, payload: &[u8]) -> Result<usize, RollupsError> {
+        self.check_advancing(payload.len())?;
+        let index = self.notices.get();
+
+        self.write_event(&Event::Notice {
+            index,
+            payload: payload.to_vec(),
+        })?;
+        self.notices.set(index + 1);
+
+        Ok(index)
+    }
+
+    fn write_voucher(&self, address: &Address, payload: &[u8]) -> Result<usize, RollupsError> {
+        self.write_voucher_with_value(address, &U256::ZERO, payload)
+    }
+
+    fn write_voucher_with_value(&self, address: &Address, value: &U256, payload: &[u8]) -> Result<usize, RollupsError> {
+        self.check_advancing(payload.len())?;
+        let index = self.vouchers.get();
+
+        self.write_event(&Event::Voucher {
+            index,
+            destination: *address,
+            value: *value,
+            payload: payload.to_vec(),
+        })?;
+        self.vouchers.set(index + 1);
+
+        Ok(index)
+    }
+
+    fn write_report(&self, payload: &[u8]) -> Result<(), RollupsError> {
+        self.write_event(&Event::Report {
+            payload: payload.to_vec(),
+        })
+    }
+
+    fn submit(&self, status: FinishStatus) -> Result<RollupsRequest, RollupsError> {
+        self.write_event(&Event::Finish { status })?;
+        self.notices.set(0);
+        self.vouchers.set(0);
+
+        let line = self
+            .read_line()?
+            .ok_or_else(|| RollupsError::QueueExhausted(io::Error::from(io::ErrorKind::UnexpectedEof).into()))?;
+        let request: RollupsRequest =
+            serde_json::from_str(&line).map_err(|error| RollupsError::Other(Box::new(error)))?;
+
+        if let RollupsRequest::AdvanceState { metadata, .. } = &request {
+            self.app_contract
+                .set(metadata.app_contract().or(self.app_contract.get()));
+        }
+        self.inspecting
+            .set(matches!(request, RollupsRequest::InspectState { .. }));
+
+        Ok(request)
+    }
+
+    fn throw_exception(&self, payload: &[u8]) -> Result<(), RollupsError> {
+        self.write_event(&Event::Exception {
+            payload: payload.to_vec(),
+        })
+    }
+
+    fn dapp_address(&self) -> Option<Address> {
+        self.app_contract.get()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requests_and_outputs_are_json_lines() {
+        let input = concat!(
+            r#"{"type":"advance_state","metadata":{"msg_sender":"0xa1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1","#,
+            r#""epoch_index":0,"input_index":1,"block_number":2,"timestamp":3},"payload":"0x0102"}"#,
+            "\n\n",
+            r#"{"type":"inspect_state","payload":"0x71"}"#,
+            "\n",
+        );
+        let machine = StdioMachine::with_io(input.as_bytes(), Vec::new());
+        let destination = Address::new([0x70; 20]);
+
+        let RollupsRequest::AdvanceState { metadata, payload } = machine.submit(FinishStatus::Accept).unwrap() else {
+            panic!("expected advance state request");
+        };
+
+        assert_eq!(vec![1, 2], payload);
+        assert_eq!(Address::new([0xa1; 20]), metadata.msg_sender);
+        assert_eq!(1, metadata.input_index);
+        assert_eq!(0, machine.write_notice(b"n").unwrap());
+        assert_eq!(1, machine.write_notice(b"o").unwrap());
+        assert_eq!(0, machine.write_voucher(&destination, b"v").unwrap());
+
+        let RollupsRequest::InspectState { payload } = machine.submit(FinishStatus::Reject).unwrap() else {
+            panic!("expected inspect state request");
+        };
+
+        assert_eq!(b"q".to_vec(), payload);
+        machine.write_report(b"r").unwrap();
+        for result in [machine.write_notice(b"i"), machine.write_voucher(&destination, b"i")] {
+            let Err(RollupsError::Device(error)) = result else {
+                panic!("expected device error");
+            };
+            assert_eq!(Some(Errno::EOPNOTSUPP as i32), error.raw_os_error());
+        }
+        assert!(matches!(
+            machine.submit(FinishStatus::Accept),
+            Err(RollupsError::QueueExhausted(_))
+        ));
+
+        let (_, output) = machine.into_inner();
+        let events: Vec<Event> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(
+            vec![
+                Event::Finish {
+                    status: FinishStatus::Accept
+                },
+                Event::Notice {
+                    index: 0,
+                    payload: b"n".to_vec()
+                },
+                Event::Notice {
+                    index: 1,
+                    payload: b"o".to_vec()
+                },
+                Event::Voucher {
+                    index: 0,
+                    destination,
+                    value: U256::ZERO,
+                    payload: b"v".to_vec()
+                },
+                Event::Finish {
+                    status: FinishStatus::Reject
+                },
+                Event::Report { payload: b"r".to_vec() },
+                Event::Finish {
+                    status: FinishStatus::Accept
+                },
+            ],
+            events
+        );
+    }
+}
@@ -0,0 +1,25 @@
+use cartesi_rollups_evm_utils::abi::AbiError;
+use cartesi_rollups_evm_utils::ParseError;
+use std::io;
+use thiserror::Error;
+
+/// Defines errors of loading state into a [`Simulator`] and of running it.
+///
+/// A voucher that reverts is not an error, but an [`Execution`] with a failed [`Status`].
+///
+/// [`Simulator`]: crate::Simulator
+/// [`Execution`]: crate::Execution
+/// [`Status`]: crate::Status
+#[derive(Error, Debug)]
+pub enum SimulatorError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error("invalid bytecode")]
+    InvalidBytecode(#[from] ParseError),
+    #[error("invalid event signature")]
+    InvalidEvent(#[from] AbiError),
+    #[error("transaction is invalid: {0}")]
+    InvalidTransaction(String),
+}
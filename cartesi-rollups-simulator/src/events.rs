@@ -0,0 +1,226 @@
+use cartesi_rollups_evm_utils::abi::{decode, AbiError, ParamType, Token};
+use cartesi_rollups_evm_utils::{keccak256, Address};
+
+/// Signatures of the events of the token standards, which a [`Simulator`] decodes without being told about them.
+///
+/// The `Transfer` and `Approval` events of ERC-20 and ERC-721 share a topic, they differ in which parameters are
+/// indexed.
+///
+/// [`Simulator`]: crate::Simulator
+pub const STANDARD_EVENTS: [&str; 7] = [
+    "Transfer(address indexed from, address indexed to, uint256 value)",
+    "Approval(address indexed owner, address indexed spender, uint256 value)",
+    "Transfer(address indexed from, address indexed to, uint256 indexed tokenId)",
+    "Approval(address indexed owner, address indexed approved, uint256 indexed tokenId)",
+    "ApprovalForAll(address indexed owner, address indexed operator, bool approved)",
+    "TransferSingle(address indexed operator, address indexed from, address indexed to, uint256 id, uint256 value)",
+    "TransferBatch(address indexed operator, address indexed from, address indexed to, uint256[] ids, uint256[] values)",
+];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EventParam {
+    pub name: String,
+    pub kind: ParamType,
+    pub indexed: bool,
+}
+
+/// Event declaration as written in Solidity, e.g. `Transfer(address indexed from, address indexed to, uint256 value)`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EventSignature {
+    pub name: String,
+    pub inputs: Vec<EventParam>,
+}
+
+impl EventSignature {
+    /// Parses `signature` of the form `name(type [indexed] [name],...)`.
+    ///
+    /// Parameter names are optional, unnamed parameters are named after their position.
+    pub fn parse(signature: &str) -> Result<Self, AbiError> {
+        let invalid = || AbiError::InvalidSignature(signature.to_owned());
+        let (name, rest) = signature.trim().split_once('(').ok_or_else(invalid)?;
+        let params = rest.strip_suffix(')').ok_or_else(invalid)?;
+
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$') {
+            return Err(invalid());
+        }
+
+        let inputs = split_params(params)
+            .into_iter()
+            .enumerate()
+            .map(|(index, param)| {
+                let mut words = param.split_whitespace();
+                let kind = words.next().ok_or_else(invalid)?.parse()?;
+                let mut words = words.peekable();
+                let indexed = words.next_if_eq(&"indexed").is_some();
+                let name = words.next().map_or_else(|| index.to_string(), str::to_owned);
+
+                match words.next() {
+                    Some(_) => Err(invalid()),
+                    None => Ok(EventParam { name, kind, indexed }),
+                }
+            })
+            .collect::<Result<_, AbiError>>()?;
+
+        Ok(Self {
+            name: name.to_owned(),
+            inputs,
+        })
+    }
+
+    /// Returns the first topic of the logs of this event, the hash of its canonical signature.
+    pub fn topic(&self) -> [u8; 32] {
+        let kinds = self.inputs.iter().map(|param| param.kind.clone()).collect();
+
+        keccak256(format!("{}{}", self.name, ParamType::Tuple(kinds)))
+    }
+
+    /// Decodes a log of this event, or returns `None` if the log belongs to another event.
+    ///
+    /// An indexed parameter of a reference type, like a string, an array or a struct, decodes to the hash the log holds
+    /// in place of its value.
+    pub fn decode(&self, topics: &[[u8; 32]], data: &[u8]) -> Option<Vec<(String, Token)>> {
+        let indexed_count = self.inputs.iter().filter(|param| param.indexed).count();
+
+        if topics.first() != Some(&self.topic()) || topics.len() != indexed_count + 1 {
+            return None;
+        }
+
+        let kinds: Vec<_> = self
+            .inputs
+            .iter()
+            .filter(|param| !param.indexed)
+            .map(|param| param.kind.clone())
+            .collect();
+        let mut values = decode(&kinds, data).ok()?.into_iter();
+        let mut topics = topics[1..].iter();
+
+        self.inputs
+            .iter()
+            .map(|param| {
+                let token = match param.indexed {
+                    true if is_reference_type(&param.kind) => Token::FixedBytes(topics.next()?.to_vec()),
+                    true => decode(std::slice::from_ref(&param.kind), topics.next()?)
+                        .ok()?
+                        .remove(0),
+                    false => values.next()?,
+                };
+
+                Some((param.name.clone(), token))
+            })
+            .collect()
+    }
+}
+
+/// Log emitted while executing a voucher.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Event {
+    pub address: Address,
+    pub topics: Vec<[u8; 32]>,
+    pub data: Vec<u8>,
+    /// Name and parameters of the event, if its signature is known to the simulator.
+    pub decoded: Option<DecodedEvent>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecodedEvent {
+    pub name: String,
+    pub params: Vec<(String, Token)>,
+}
+
+impl DecodedEvent {
+    pub fn param(&self, name: &str) -> Option<&Token> {
+        self.params
+            .iter()
+            .find(|(param, _)| param == name)
+            .map(|(_, token)| token)
+    }
+}
+
+fn is_reference_type(kind: &ParamType) -> bool {
+    !matches!(
+        kind,
+        ParamType::Address | ParamType::Bool | ParamType::Int(_) | ParamType::Uint(_) | ParamType::FixedBytes(_)
+    )
+}
+
+/// Splits a parameter list at its top-level commas, leaving the commas of tuple types alone.
+fn split_params(params: &str) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+
+    for (index, c) in params.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&params[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    if !params.trim().is_empty() {
+        parts.push(&params[start..]);
+    }
+
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cartesi_rollups_evm_utils::abi::encode;
+    use cartesi_rollups_evm_utils::U256;
+
+    fn topic(address: Address) -> [u8; 32] {
+        encode(&[address.into()]).try_into().unwrap()
+    }
+
+    #[test]
+    fn test_erc20_and_erc721_transfers_are_told_apart() {
+        let erc20 = EventSignature::parse(STANDARD_EVENTS[0]).unwrap();
+        let erc721 = EventSignature::parse(STANDARD_EVENTS[2]).unwrap();
+        let from = Address::new([0x11; 20]);
+        let to = Address::new([0x22; 20]);
+        let value = encode(&[U256::from(7u8).into()]);
+        let topics = [erc20.topic(), topic(from), topic(to)];
+
+        assert_eq!(
+            "ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef",
+            hex::encode(erc20.topic())
+        );
+        assert_eq!(erc20.topic(), erc721.topic());
+        assert_eq!(
+            Some(vec![
+                ("from".to_owned(), Token::Address(from)),
+                ("to".to_owned(), Token::Address(to)),
+                ("value".to_owned(), Token::Uint(U256::from(7u8))),
+            ]),
+            erc20.decode(&topics, &value)
+        );
+        assert_eq!(None, erc721.decode(&topics, &value));
+    }
+
+    #[test]
+    fn test_unnamed_and_tuple_params_are_parsed() {
+        let event = EventSignature::parse("Settled((address,uint256) indexed, string)").unwrap();
+
+        assert_eq!(
+            vec![
+                EventParam {
+                    name: "0".to_owned(),
+                    kind: "(address,uint256)".parse().unwrap(),
+                    indexed: true,
+                },
+                EventParam {
+                    name: "1".to_owned(),
+                    kind: ParamType::String,
+                    indexed: false,
+                },
+            ],
+            event.inputs
+        );
+        assert!(EventSignature::parse("Broken(uint256 indexed value extra)").is_err());
+    }
+}
@@ -0,0 +1,18 @@
+//! Offline execution of vouchers against local EVM bytecode and state.
+//!
+//! A [`Simulator`] executes each `(destination, payload)` voucher as a call from the DApp contract, the way the base
+//! layer does once the voucher is proven, and reports whether it succeeded along with its revert reason and events. This
+//! checks vouchers of a DApp before deploying it, using the vouchers that `FakeCartesiMachine` collects in its `Data`.
+//!
+//! Only the vouchers of `FakeCartesiMachine` are accepted as they are: `TestMachineIo` of the `integration` feature
+//! collects notices and reports but no vouchers, so vouchers of a DApp running on a Cartesi machine have to be gathered
+//! as `(destination, payload)` pairs by other means.
+mod error;
+mod events;
+mod simulator;
+mod state;
+
+pub use error::*;
+pub use events::*;
+pub use simulator::*;
+pub use state::*;
@@ -0,0 +1,315 @@
+use crate::{AccountState, DecodedEvent, Event, EventSignature, SimulatorError, State, STANDARD_EVENTS};
+use cartesi_rollups_evm_utils::abi::{decode, ParamType};
+use cartesi_rollups_evm_utils::{Address, U256};
+use revm::db::{CacheDB, EmptyDB};
+use revm::primitives::{
+    AccountInfo, Address as EvmAddress, Bytecode, Bytes, ExecutionResult, TxKind, KECCAK_EMPTY, U256 as EvmU256,
+};
+use revm::{DatabaseRef, Evm};
+use std::fmt;
+use std::path::Path;
+
+/// Gas available to each voucher unless [`Simulator::with_gas_limit`] says otherwise.
+pub const DEFAULT_GAS_LIMIT: u64 = 30_000_000;
+
+/// Selector of `Error(string)`, what `require` and `revert` with a message revert with.
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+
+/// Selector of `Panic(uint256)`, what failed assertions and arithmetic errors revert with.
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// Embedded EVM executing vouchers of a DApp against local state.
+///
+/// Each voucher is a call from the DApp contract to its destination with the voucher payload as calldata, the call the
+/// DApp contract makes when the voucher is executed on the base layer. The state changes of each call are kept, so a
+/// sequence of vouchers runs as it would on chain.
+///
+/// # Examples
+///
+/// ```
+/// # use cartesi_rollups_evm_utils::Address;
+/// # use cartesi_rollups_simulator::{AccountState, Simulator, SimulatorError, Status};
+/// # fn check(vouchers: &[(Address, Vec<u8>)]) -> Result<(), SimulatorError> {
+/// let dapp = Address::new([0xda; 20]);
+/// let token = Address::new([0x70; 20]);
+/// let mut simulator = Simulator::new(dapp)
+///     .load_state("state.json")?
+///     .with_account(token, AccountState::load_contract("out/Token.sol/Token.json")?);
+///
+/// for execution in simulator.execute_all(vouchers)? {
+///     if let Status::Revert(reason) = &execution.status {
+///         println!("voucher reverted with {}", reason);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Simulator {
+    dapp: Address,
+    db: CacheDB<EmptyDB>,
+    events: Vec<EventSignature>,
+    gas_limit: u64,
+}
+
+impl Simulator {
+    /// Creates a simulator with empty state, executing the vouchers of the DApp contract at `dapp`.
+    pub fn new(dapp: Address) -> Self {
+        let events = STANDARD_EVENTS
+            .iter()
+            .map(|signature| EventSignature::parse(signature).unwrap())
+            .collect();
+
+        Self {
+            dapp,
+            db: CacheDB::new(EmptyDB::default()),
+            events,
+            gas_limit: DEFAULT_GAS_LIMIT,
+        }
+    }
+
+    pub fn dapp(&self) -> &Address {
+        &self.dapp
+    }
+
+    /// Adds the accounts of `state`, replacing the ones already at the same addresses.
+    pub fn with_state(self, state: State) -> Self {
+        state.accounts.into_iter().fold(self, |simulator, (address, account)| {
+            simulator.with_account(address, account)
+        })
+    }
+
+    /// Adds the accounts of the state file at `path`, see [`State`] for its format.
+    pub fn load_state(self, path: impl AsRef<Path>) -> Result<Self, SimulatorError> {
+        Ok(self.with_state(State::load(path)?))
+    }
+
+    pub fn with_account(mut self, address: Address, account: AccountState) -> Self {
+        let address = to_evm_address(&address);
+        let info = AccountInfo {
+            balance: to_evm_uint(&account.balance),
+            nonce: account.nonce,
+            code_hash: KECCAK_EMPTY,
+            code: Some(Bytecode::new_raw(account.code.into())),
+        };
+
+        self.db.insert_account_info(address, info);
+        self.db
+            .replace_account_storage(
+                address,
+                account
+                    .storage
+                    .iter()
+                    .map(|(slot, value)| (to_evm_uint(slot), to_evm_uint(value)))
+                    .collect(),
+            )
+            .unwrap();
+
+        self
+    }
+
+    /// Decodes the events matching `signature`, e.g. `Deposited(address indexed owner, uint256 amount)`, in addition
+    /// to the ones of [`STANDARD_EVENTS`].
+    pub fn with_event(mut self, signature: &str) -> Result<Self, SimulatorError> {
+        self.events.push(EventSignature::parse(signature)?);
+
+        Ok(self)
+    }
+
+    pub fn with_gas_limit(mut self, gas_limit: u64) -> Self {
+        self.gas_limit = gas_limit;
+        self
+    }
+
+    pub fn balance(&self, address: &Address) -> U256 {
+        let info = self.db.basic_ref(to_evm_address(address)).unwrap();
+
+        info.map_or(U256::ZERO, |info| from_evm_uint(&info.balance))
+    }
+
+    pub fn storage(&self, address: &Address, slot: &U256) -> U256 {
+        let value = self.db.storage_ref(to_evm_address(address), to_evm_uint(slot)).unwrap();
+
+        from_evm_uint(&value)
+    }
+
+    /// Executes the voucher with `destination` and `payload`, keeping its state changes if it succeeds.
+    ///
+    /// A voucher that reverts or runs out of gas is reported in the returned [`Execution`], an error means the call
+    /// could not be made at all.
+    pub fn execute(&mut self, destination: &Address, payload: &[u8]) -> Result<Execution, SimulatorError> {
+        let result = Evm::builder()
+            .with_db(&mut self.db)
+            .modify_cfg_env(|cfg| cfg.disable_eip3607 = true)
+            .modify_tx_env(|tx| {
+                tx.caller = to_evm_address(&self.dapp);
+                tx.transact_to = TxKind::Call(to_evm_address(destination));
+                tx.data = Bytes::copy_from_slice(payload);
+                tx.value = EvmU256::ZERO;
+                tx.gas_limit = self.gas_limit;
+                tx.gas_price = EvmU256::ZERO;
+                tx.nonce = None;
+            })
+            .build()
+            .transact_commit()
+            .map_err(|error| SimulatorError::InvalidTransaction(error.to_string()))?;
+
+        let execution = match result {
+            ExecutionResult::Success {
+                gas_used, logs, output, ..
+            } => Execution {
+                status: Status::Success(output.into_data().to_vec()),
+                gas_used,
+                events: logs
+                    .into_iter()
+                    .map(|log| {
+                        let topics: Vec<[u8; 32]> = log.topics().iter().map(|topic| topic.0).collect();
+                        let data = log.data.data.to_vec();
+
+                        Event {
+                            address: from_evm_address(&log.address),
+                            decoded: self.decode_event(&topics, &data),
+                            topics,
+                            data,
+                        }
+                    })
+                    .collect(),
+            },
+            ExecutionResult::Revert { gas_used, output } => Execution {
+                status: Status::Revert(Revert::decode(&output)),
+                gas_used,
+                events: vec![],
+            },
+            ExecutionResult::Halt { reason, gas_used } => Execution {
+                status: Status::Halt(format!("{:?}", reason)),
+                gas_used,
+                events: vec![],
+            },
+        };
+
+        Ok(execution)
+    }
+
+    /// Executes `vouchers` in order, as collected in the `vouchers` of the `Data` of a `FakeCartesiMachine`.
+    ///
+    /// `TestMachineIo` does not collect vouchers, see the [crate-level documentation](crate).
+    pub fn execute_all<'a>(
+        &mut self,
+        vouchers: impl IntoIterator<Item = &'a (Address, Vec<u8>)>,
+    ) -> Result<Vec<Execution>, SimulatorError> {
+        vouchers
+            .into_iter()
+            .map(|(destination, payload)| self.execute(destination, payload))
+            .collect()
+    }
+
+    fn decode_event(&self, topics: &[[u8; 32]], data: &[u8]) -> Option<DecodedEvent> {
+        self.events.iter().find_map(|event| {
+            event.decode(topics, data).map(|params| DecodedEvent {
+                name: event.name.clone(),
+                params,
+            })
+        })
+    }
+}
+
+/// Outcome of executing a voucher.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Execution {
+    pub status: Status,
+    pub gas_used: u64,
+    /// Logs of a successful execution, empty otherwise.
+    pub events: Vec<Event>,
+}
+
+impl Execution {
+    pub fn is_success(&self) -> bool {
+        matches!(self.status, Status::Success(_))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Status {
+    /// Data returned by the call.
+    Success(Vec<u8>),
+    Revert(Revert),
+    /// Exceptional halt, like running out of gas or an invalid opcode, formatted for display.
+    Halt(String),
+}
+
+/// Reason a voucher reverted with.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Revert {
+    /// Message of `require` or `revert`.
+    Error(String),
+    /// Code of a failed assertion or arithmetic error.
+    Panic(U256),
+    /// Custom error or empty revert data, left encoded.
+    Custom(Vec<u8>),
+}
+
+impl Revert {
+    pub fn decode(data: &[u8]) -> Self {
+        let (selector, arguments) = data.split_at(data.len().min(4));
+        let decoded = match selector {
+            s if s == ERROR_SELECTOR => decode(&[ParamType::String], arguments)
+                .ok()
+                .and_then(|mut tokens| tokens.remove(0).into_string())
+                .map(Self::Error),
+            s if s == PANIC_SELECTOR => decode(&[ParamType::Uint(256)], arguments)
+                .ok()
+                .and_then(|mut tokens| tokens.remove(0).into_uint())
+                .map(Self::Panic),
+            _ => None,
+        };
+
+        decoded.unwrap_or_else(|| Self::Custom(data.to_vec()))
+    }
+}
+
+impl fmt::Display for Revert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Error(message) => write!(f, "Error({:?})", message),
+            Self::Panic(code) => write!(f, "Panic({:#x})", code),
+            Self::Custom(data) if data.is_empty() => f.write_str("empty revert data"),
+            Self::Custom(data) => write!(f, "custom error 0x{}", hex::encode(data)),
+        }
+    }
+}
+
+fn to_evm_address(address: &Address) -> EvmAddress {
+    EvmAddress::from(address.to_bytes())
+}
+
+fn from_evm_address(address: &EvmAddress) -> Address {
+    Address::new(address.into_array())
+}
+
+fn to_evm_uint(value: &U256) -> EvmU256 {
+    EvmU256::from_be_bytes(value.to_be_bytes())
+}
+
+fn from_evm_uint(value: &EvmU256) -> U256 {
+    U256::from_be_bytes(value.to_be_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cartesi_rollups_evm_utils::abi::{encode_call, Token};
+
+    #[test]
+    fn test_revert_reasons_are_decoded() {
+        let error = encode_call("Error(string)", &[Token::from("insufficient balance")]).unwrap();
+        let panic = encode_call("Panic(uint256)", &[U256::from(0x11u8).into()]).unwrap();
+        let custom = encode_call("Unauthorized(address)", &[Address::ZERO.into()]).unwrap();
+
+        assert_eq!(Revert::Error("insufficient balance".to_owned()), Revert::decode(&error));
+        assert_eq!(Revert::Panic(U256::from(0x11u8)), Revert::decode(&panic));
+        assert_eq!(Revert::Custom(custom.clone()), Revert::decode(&custom));
+        assert_eq!(Revert::Custom(vec![]), Revert::decode(&[]));
+        assert_eq!(Revert::Custom(error[..8].to_vec()), Revert::decode(&error[..8]));
+        assert_eq!("Panic(0x11)", Revert::decode(&panic).to_string());
+    }
+}
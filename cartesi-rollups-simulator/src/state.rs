@@ -0,0 +1,151 @@
+use crate::SimulatorError;
+use cartesi_rollups_evm_utils::{Address, ParseError, U256};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Accounts loaded into a [`Simulator`] before executing vouchers.
+///
+/// Serializes as a map from address to account, the format of the `alloc` of a genesis file, so a state dumped by a
+/// local node loads as it is:
+///
+/// ```json
+/// {
+///     "0x5FbDB2315678afecb367f032d93F642f64180aa3": {
+///         "balance": "0x0",
+///         "code": "0x6080...",
+///         "storage": { "0x0": "0x2a" }
+///     }
+/// }
+/// ```
+///
+/// [`Simulator`]: crate::Simulator
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct State {
+    pub accounts: BTreeMap<Address, AccountState>,
+}
+
+impl State {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads a state file in the format described by [`State`].
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SimulatorError> {
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+
+    pub fn with_account(mut self, address: Address, account: AccountState) -> Self {
+        self.accounts.insert(address, account);
+        self
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AccountState {
+    pub balance: U256,
+    pub nonce: u64,
+    #[serde(with = "hex_bytes")]
+    pub code: Vec<u8>,
+    pub storage: BTreeMap<U256, U256>,
+}
+
+impl AccountState {
+    /// Returns an account with the runtime bytecode `code`.
+    pub fn contract(code: impl Into<Vec<u8>>) -> Self {
+        Self {
+            code: code.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Returns an account with the runtime bytecode read from the file at `path`.
+    ///
+    /// The file is either the hex output of `solc --bin-runtime` or a JSON artifact of Hardhat or Foundry, whose
+    /// `deployedBytecode` holds the code.
+    pub fn load_contract(path: impl AsRef<Path>) -> Result<Self, SimulatorError> {
+        let contents = fs::read_to_string(path)?;
+        let contents = contents.trim();
+
+        let code = match contents.starts_with('{') {
+            true => {
+                let artifact: Value = serde_json::from_str(contents)?;
+                let code = &artifact["deployedBytecode"];
+                let code = code
+                    .get("object")
+                    .unwrap_or(code)
+                    .as_str()
+                    .ok_or_else(|| serde_json::Error::custom("artifact has no deployedBytecode"))?;
+
+                decode_hex(code)?
+            }
+            false => decode_hex(contents)?,
+        };
+
+        Ok(Self::contract(code))
+    }
+
+    pub fn with_balance(mut self, balance: U256) -> Self {
+        self.balance = balance;
+        self
+    }
+
+    pub fn with_storage(mut self, slot: U256, value: U256) -> Self {
+        self.storage.insert(slot, value);
+        self
+    }
+}
+
+fn decode_hex(digits: &str) -> Result<Vec<u8>, ParseError> {
+    Ok(hex::decode(digits.strip_prefix("0x").unwrap_or(digits))?)
+}
+
+mod hex_bytes {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("0x{}", hex::encode(bytes)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        decode_hex(&String::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_parses_genesis_alloc() {
+        let contract = Address::new([0x11; 20]);
+        let state: State = serde_json::from_str(
+            r#"{
+                "0x1111111111111111111111111111111111111111": {
+                    "balance": "0x10",
+                    "code": "0x6000",
+                    "storage": { "0x1": "42" }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let expected = State::new().with_account(
+            contract,
+            AccountState::contract([0x60, 0x00])
+                .with_balance(U256::from(16u8))
+                .with_storage(U256::from(1u8), U256::from(42u8)),
+        );
+
+        assert_eq!(expected, state);
+        assert_eq!(
+            state,
+            serde_json::from_value(serde_json::to_value(&state).unwrap()).unwrap()
+        );
+    }
+}
@@ -0,0 +1,53 @@
+use cartesi_rollups::{Address, MachineIo, VoucherBuilder, U256};
+use cartesi_rollups_evm_utils::abi::Token;
+use cartesi_rollups_simulator::{AccountState, Revert, Simulator, Status};
+use cartesi_rollups_test::{Data, FakeCartesiMachine};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+#[test]
+fn test_vouchers_of_fake_machine_are_executed() {
+    let dapp = Address::new([0xda; 20]);
+    let alice = Address::new([0xa1; 20]);
+    // Emits `Transfer(msg.sender, to, amount)` and stores `amount` at slot 0.
+    let token = Address::new([0x70; 20]);
+    // Reverts with `Error("insufficient balance")`.
+    let broke_token = Address::new([0x71; 20]);
+
+    let data = Rc::new(RefCell::new(Data::default()));
+    let machine = FakeCartesiMachine::new([], data.clone());
+    let vouchers = VoucherBuilder::new(dapp);
+
+    for (destination, payload) in [
+        vouchers.erc20_transfer(&token, &alice, U256::from(30u8)),
+        vouchers.erc20_transfer(&broke_token, &alice, U256::from(30u8)),
+    ] {
+        machine.write_voucher(&destination, &payload).unwrap();
+    }
+
+    let mut simulator = Simulator::new(dapp)
+        .load_state(format!("{}/state.json", FIXTURES))
+        .unwrap()
+        .with_account(
+            broke_token,
+            AccountState::load_contract(format!("{}/Reverter.json", FIXTURES)).unwrap(),
+        );
+    let executions = simulator.execute_all(&data.borrow().vouchers).unwrap();
+
+    let transfer = executions[0].events[0].decoded.as_ref().unwrap();
+
+    assert!(executions[0].is_success());
+    assert_eq!(token, executions[0].events[0].address);
+    assert_eq!("Transfer", transfer.name);
+    assert_eq!(Some(&Token::Address(dapp)), transfer.param("from"));
+    assert_eq!(Some(&Token::Address(alice)), transfer.param("to"));
+    assert_eq!(Some(&Token::Uint(U256::from(30u8))), transfer.param("value"));
+    assert_eq!(U256::from(30u8), simulator.storage(&token, &U256::ZERO));
+    assert_eq!(
+        Status::Revert(Revert::Error("insufficient balance".to_owned())),
+        executions[1].status
+    );
+    assert!(executions[1].events.is_empty());
+}
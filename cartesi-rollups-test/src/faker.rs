@@ -7,10 +7,10 @@
 //!
 //! ```
 //! # use std::error::Error;
-//! # use cartesi_rollups::{MachineIo, RollupsRequest};
+//! # use cartesi_rollups::{Address, FinishStatus, MachineIo, RollupsError, RollupsRequest};
 //! # pub fn run(machine: impl MachineIo) -> Result<(), Box<dyn Error>> {
-//! # let address = [0u8; 20];
-//! let request = machine.submit()?;
+//! # let address = Address::ZERO;
+//! let request = machine.submit(FinishStatus::Accept)?;
 //!
 //! match request {
 //!     RollupsRequest::AdvanceState { payload, .. } => {
@@ -24,13 +24,14 @@
 //! # Ok(())
 //! # }
 //! ```
-use cartesi_rollups::{MachineIo, RollupsRequest};
-use std::cell::RefCell;
+use cartesi_rollups::{Address, FinishStatus, MachineIo, RollupsError, RollupsRequest};
+use cartesi_rollups_evm_utils::outputs::Epoch;
+use std::cell::{Cell, RefCell};
 use std::collections::VecDeque;
 use std::rc::Rc;
 use thiserror::Error;
 
-type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+type Result<T> = std::result::Result<T, RollupsError>;
 
 /// Defines errors of in-memory Cartesi rollup device.
 ///
@@ -43,19 +44,40 @@ pub enum FakeCartesiMachineError {
     EmptyRequests,
 }
 
+impl From<FakeCartesiMachineError> for RollupsError {
+    fn from(error: FakeCartesiMachineError) -> Self {
+        match error {
+            FakeCartesiMachineError::EmptyRequests => RollupsError::QueueExhausted(Box::new(error)),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct Data {
     pub notices: Vec<Vec<u8>>,
-    pub vouchers: Vec<(Vec<u8>, Vec<u8>)>,
+    pub vouchers: Vec<(Address, Vec<u8>)>,
     pub reports: Vec<Vec<u8>>,
     pub exceptions: Vec<Vec<u8>>,
 }
 
 /// See the [module-level documentation](./index.html) for more details.
+///
+/// [`Rejecting`] a request discards the notices and vouchers written since the request was popped.
+///
+/// [`Rejecting`]: FinishStatus::Reject
+///
+/// Attach an [`Epoch`] using [`with_epoch`] to compute the claim and the output proofs of the advance state requests.
+///
+/// [`with_epoch`]: FakeCartesiMachine::with_epoch
 #[derive(Clone, Debug, Default)]
 pub struct FakeCartesiMachine {
     requests: RefCell<VecDeque<RollupsRequest>>,
     data: Rc<RefCell<Data>>,
+    /// Number of notices and vouchers written before the current request was popped.
+    checkpoint: Cell<(usize, usize)>,
+    /// Whether the current request advances the state.
+    advancing: Cell<bool>,
+    epoch: Option<Rc<RefCell<Epoch>>>,
 }
 
 impl FakeCartesiMachine {
@@ -66,8 +88,19 @@ impl FakeCartesiMachine {
         Self {
             requests: RefCell::new(requests.into_iter().collect()),
             data,
+            checkpoint: Cell::default(),
+            advancing: Cell::default(),
+            epoch: None,
         }
     }
+
+    /// Adds the outputs of each advance state request to `epoch` when it gets finished.
+    ///
+    /// The outputs of a rejected request are discarded, so it is added without outputs.
+    pub fn with_epoch(mut self, epoch: Rc<RefCell<Epoch>>) -> Self {
+        self.epoch = Some(epoch);
+        self
+    }
 }
 
 impl MachineIo for FakeCartesiMachine {
@@ -76,11 +109,8 @@ impl MachineIo for FakeCartesiMachine {
         Ok(payload.len())
     }
 
-    fn write_voucher(&self, address: &[u8; 20], payload: &[u8]) -> Result<usize> {
-        self.data
-            .borrow_mut()
-            .vouchers
-            .push((address.to_vec(), payload.to_vec()));
+    fn write_voucher(&self, address: &Address, payload: &[u8]) -> Result<usize> {
+        self.data.borrow_mut().vouchers.push((*address, payload.to_vec()));
         Ok(payload.len())
     }
 
@@ -89,12 +119,33 @@ impl MachineIo for FakeCartesiMachine {
         Ok(())
     }
 
-    fn submit(&self) -> Result<RollupsRequest> {
-        Ok(self
+    fn submit(&self, status: FinishStatus) -> Result<RollupsRequest> {
+        let mut data = self.data.borrow_mut();
+
+        if status == FinishStatus::Reject {
+            let (notices, vouchers) = self.checkpoint.get();
+
+            data.notices.truncate(notices);
+            data.vouchers.truncate(vouchers);
+        }
+        if let (Some(epoch), true) = (&self.epoch, self.advancing.take()) {
+            let (notices, vouchers) = self.checkpoint.get();
+
+            epoch
+                .borrow_mut()
+                .add_input(&data.vouchers[vouchers..], &data.notices[notices..]);
+        }
+        self.checkpoint.set((data.notices.len(), data.vouchers.len()));
+
+        let request = self
             .requests
             .borrow_mut()
             .pop_front()
-            .ok_or(FakeCartesiMachineError::EmptyRequests)?)
+            .ok_or(FakeCartesiMachineError::EmptyRequests)?;
+        self.advancing
+            .set(matches!(request, RollupsRequest::AdvanceState { .. }));
+
+        Ok(request)
     }
 
     fn throw_exception(&self, payload: &[u8]) -> Result<()> {
@@ -102,3 +153,71 @@ impl MachineIo for FakeCartesiMachine {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cartesi_rollups::{MetadataVersion, RollupsMetadata};
+
+    fn advance(payload: Vec<u8>) -> RollupsRequest {
+        RollupsRequest::AdvanceState {
+            metadata: RollupsMetadata {
+                msg_sender: Address::ZERO,
+                epoch_index: 0,
+                input_index: 0,
+                block_number: 0,
+                timestamp: 0,
+                version: MetadataVersion::V1,
+            },
+            payload,
+        }
+    }
+
+    #[test]
+    fn test_rejecting_discards_outputs_of_previous_input() {
+        let actual_data = Rc::new(RefCell::new(Data::default()));
+        let machine = FakeCartesiMachine::new([advance(vec![1]), advance(vec![2])], actual_data.clone());
+
+        machine.submit(FinishStatus::Accept).unwrap();
+        machine.write_notice(&[1]).unwrap();
+        machine.submit(FinishStatus::Accept).unwrap();
+        machine.write_notice(&[2]).unwrap();
+        machine.write_voucher(&Address::ZERO, &[2]).unwrap();
+        machine.write_report(&[2]).unwrap();
+        machine.submit(FinishStatus::Reject).unwrap_err();
+
+        let expected_data = Data {
+            notices: vec![vec![1]],
+            vouchers: vec![],
+            reports: vec![vec![2]],
+            exceptions: vec![],
+        };
+
+        assert_eq!(expected_data, *actual_data.borrow());
+    }
+
+    #[test]
+    fn test_epoch_records_outputs_of_advance_requests() {
+        let actual_data = Rc::new(RefCell::new(Data::default()));
+        let actual_epoch = Rc::new(RefCell::new(Epoch::new()));
+        let requests = [
+            advance(vec![1]),
+            RollupsRequest::InspectState { payload: vec![] },
+            advance(vec![2]),
+        ];
+        let machine = FakeCartesiMachine::new(requests, actual_data).with_epoch(actual_epoch.clone());
+
+        machine.submit(FinishStatus::Accept).unwrap();
+        machine.write_notice(&[1]).unwrap();
+        machine.submit(FinishStatus::Accept).unwrap();
+        machine.submit(FinishStatus::Accept).unwrap();
+        machine.write_voucher(&Address::ZERO, &[2]).unwrap();
+        machine.submit(FinishStatus::Reject).unwrap_err();
+
+        let mut expected_epoch = Epoch::new();
+        expected_epoch.add_input(&[], &[vec![1]]);
+        expected_epoch.add_input(&[], &[]);
+
+        assert_eq!(expected_epoch, *actual_epoch.borrow());
+    }
+}
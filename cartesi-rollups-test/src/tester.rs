@@ -1,9 +1,13 @@
+use cartesi_rollups_evm_utils::outputs::{notice_hash, voucher_hash, Epoch};
+use cartesi_rollups_evm_utils::Address;
+use std::cell::RefCell;
 use std::env;
 use std::error::Error;
 use std::fs::{remove_file, File};
 use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
+use std::rc::Rc;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Notice {
@@ -37,6 +41,52 @@ impl Notice {
         self.notice_index = notice_index;
         self
     }
+
+    /// Returns the hash of the notice the contracts prove, see [`notice_hash`].
+    pub fn hash(&self) -> [u8; 32] {
+        notice_hash(self.payload.as_bytes())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Voucher {
+    epoch_index: usize,
+    input_index: usize,
+    voucher_index: usize,
+    destination: Address,
+    payload: String,
+}
+
+impl Voucher {
+    pub fn new(destination: Address, payload: impl Into<String>) -> Self {
+        Self {
+            epoch_index: 0,
+            input_index: 0,
+            voucher_index: 0,
+            destination,
+            payload: payload.into(),
+        }
+    }
+
+    pub fn with_epoch_index(mut self, epoch_index: usize) -> Self {
+        self.epoch_index = epoch_index;
+        self
+    }
+
+    pub fn with_input_index(mut self, input_index: usize) -> Self {
+        self.input_index = input_index;
+        self
+    }
+
+    pub fn with_voucher_index(mut self, voucher_index: usize) -> Self {
+        self.voucher_index = voucher_index;
+        self
+    }
+
+    /// Returns the hash of the voucher the contracts prove, see [`voucher_hash`].
+    pub fn hash(&self) -> [u8; 32] {
+        voucher_hash(&self.destination, self.payload.as_bytes())
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -71,6 +121,7 @@ pub struct TestMachineIo {
     bin_name: String,
     /// Directory where cartesi dependencies and build output are placed.
     target_dir: String,
+    epoch: Option<Rc<RefCell<Epoch>>>,
 }
 
 impl Default for TestMachineIo {
@@ -81,15 +132,29 @@ impl Default for TestMachineIo {
             epoch_index: 0,
             bin_name: env::var("CARGO_PKG_NAME").expect("Cannot read bin_name from CARGO_PKG_NAME"),
             target_dir: format!("{}/cartesi", Self::target_dir().unwrap()),
+            epoch: None,
         }
     }
 }
 
 impl TestMachineIo {
+    /// Adds the notices and vouchers of each written input to `epoch` when processed, to compute the claim and the
+    /// output proofs of the epoch of the inputs.
+    pub fn with_epoch(mut self, epoch: Rc<RefCell<Epoch>>) -> Self {
+        self.epoch = Some(epoch);
+        self
+    }
+
     pub fn write_input(mut self, payload: impl AsRef<str>) -> Self {
         self.input_index += 1;
-        RequestWriter::write_input_metadata("0xdeadbeefdeadbeefdeadbeefdeadbeefdeadbeef", 0, self.input_index, 0, 0);
-        RequestWriter::write_input_payload(payload, 0, self.input_index);
+        RequestWriter::write_input_metadata(
+            "0xdeadbeefdeadbeefdeadbeefdeadbeefdeadbeef",
+            self.epoch_index,
+            self.input_index,
+            0,
+            0,
+        );
+        RequestWriter::write_input_payload(payload, self.epoch_index, self.input_index);
         self
     }
 
@@ -98,8 +163,8 @@ impl TestMachineIo {
         self
     }
 
-    pub fn process(self) -> (Vec<Notice>, Vec<Report>) {
-        Command::new("/opt/cartesi/bin/remote-cartesi-machine")
+    pub fn process(self) -> (Vec<Notice>, Vec<Voucher>, Vec<Report>) {
+        let mut server = Command::new("/opt/cartesi/bin/remote-cartesi-machine")
             .arg(format!("--server-address=localhost:{}", self.port))
             .stderr(Stdio::null())
             .stdout(Stdio::null())
@@ -112,7 +177,8 @@ impl TestMachineIo {
             .arg("--remote-shutdown")
             .arg("--rollup")
             .arg(format!(
-                "--rollup-advance-state=epoch_index:0,input_index_begin:1,input_index_end:{}",
+                "--rollup-advance-state=epoch_index:{},input_index_begin:1,input_index_end:{}",
+                self.epoch_index,
                 self.input_index + 1
             ))
             .arg("--rollup-inspect-state=query:query.bin")
@@ -131,39 +197,79 @@ impl TestMachineIo {
             .arg(format!("/mnt/dapp/{}", self.bin_name))
             .output()
             .unwrap();
+        // The server exits on `--remote-shutdown`, unless the machine failed before reaching it.
+        server.kill().ok();
+        server.wait().unwrap();
 
         let r#type = "report";
         let reports = (0..usize::MAX)
             .map_while(|report_index| {
                 let path = format!("query-{}-{}.bin", r#type, report_index);
-                Self::decode_from_file(path, r#type).map(|payload| Report { report_index, payload })
+                Self::decode_from_file(path, r#type).map(|value| Report {
+                    report_index,
+                    payload: Self::field(&value, "payload"),
+                })
             })
             .collect();
 
-        let r#type = "notice";
-        let values = (0..=self.epoch_index)
-            .flat_map(|epoch_index| {
-                (1..=self.input_index).flat_map(move |input_index| {
-                    (0..usize::MAX).map_while(move |notice_index| {
-                        let path = format!(
-                            "epoch-{}-input-{}-{}-{}.bin",
-                            epoch_index, input_index, r#type, notice_index
-                        );
-                        Self::decode_from_file(path, r#type).map(|payload| Notice {
-                            epoch_index,
-                            input_index,
-                            notice_index,
-                            payload,
-                        })
+        let epoch_index = self.epoch_index;
+        let mut notices = vec![];
+        let mut vouchers = vec![];
+
+        for input_index in 1..=self.input_index {
+            let r#type = "notice";
+            let input_notices: Vec<_> = (0..usize::MAX)
+                .map_while(|notice_index| {
+                    let path = format!(
+                        "epoch-{}-input-{}-{}-{}.bin",
+                        epoch_index, input_index, r#type, notice_index
+                    );
+                    Self::decode_from_file(path, r#type).map(|value| Notice {
+                        epoch_index,
+                        input_index,
+                        notice_index,
+                        payload: Self::field(&value, "payload"),
                     })
                 })
-            })
-            .collect();
+                .collect();
+
+            let r#type = "voucher";
+            let input_vouchers: Vec<_> = (0..usize::MAX)
+                .map_while(|voucher_index| {
+                    let path = format!(
+                        "epoch-{}-input-{}-{}-{}.bin",
+                        epoch_index, input_index, r#type, voucher_index
+                    );
+                    Self::decode_from_file(path, r#type).map(|value| Voucher {
+                        epoch_index,
+                        input_index,
+                        voucher_index,
+                        destination: Self::field(&value, "destination").parse().unwrap(),
+                        payload: Self::field(&value, "payload"),
+                    })
+                })
+                .collect();
 
-        (values, reports)
+            if let Some(epoch) = &self.epoch {
+                let voucher_outputs: Vec<_> = input_vouchers
+                    .iter()
+                    .map(|voucher| (voucher.destination, voucher.payload.as_bytes().to_vec()))
+                    .collect();
+                let notice_outputs: Vec<_> = input_notices
+                    .iter()
+                    .map(|notice| notice.payload.as_bytes().to_vec())
+                    .collect();
+
+                epoch.borrow_mut().add_input(&voucher_outputs, &notice_outputs);
+            }
+            notices.extend(input_notices);
+            vouchers.extend(input_vouchers);
+        }
+
+        (notices, vouchers, reports)
     }
 
-    fn decode_from_file(path: impl AsRef<Path>, r#type: impl AsRef<str>) -> Option<String> {
+    fn decode_from_file(path: impl AsRef<Path>, r#type: impl AsRef<str>) -> Option<serde_json::Value> {
         File::open(path)
             .map(|file| {
                 let output = Command::new("/opt/cartesi/bin/rollup-memory-range")
@@ -173,12 +279,15 @@ impl TestMachineIo {
                     .output()
                     .unwrap();
 
-                let value = serde_json::from_slice::<serde_json::Value>(output.stdout.as_slice()).unwrap();
-                value.get("payload").unwrap().as_str().unwrap().to_owned()
+                serde_json::from_slice(output.stdout.as_slice()).unwrap()
             })
             .ok()
     }
 
+    fn field(value: &serde_json::Value, name: &str) -> String {
+        value.get(name).unwrap().as_str().unwrap().to_owned()
+    }
+
     fn target_name() -> &'static str {
         "riscv64ima-cartesi-linux-gnu"
     }
@@ -197,7 +306,7 @@ impl TestMachineIo {
             path = path.join("..");
         }
         if !path.exists() {
-            return Err(Box::try_from("Target not found.").unwrap());
+            return Err(Box::from("Target not found."));
         }
 
         Ok(path
@@ -254,7 +363,7 @@ impl RequestWriter {
         block_number: usize,
         time_stamp: u64,
     ) {
-        let input = Command::new("echo")
+        let mut input = Command::new("echo")
             .arg(format!(
                 "{{
                     \"msg_sender\": \"{}\",
@@ -275,9 +384,10 @@ impl RequestWriter {
         let encoder = Command::new("/opt/cartesi/bin/rollup-memory-range")
             .arg("encode")
             .arg("input-metadata")
-            .stdin(Stdio::from(input.stdout.unwrap()))
+            .stdin(Stdio::from(input.stdout.take().unwrap()))
             .output()
             .unwrap();
+        input.wait().unwrap();
         let path = format!("epoch-{}-input-metadata-{}.bin", epoch_index, input_index);
         let mut file = File::create(path).unwrap();
         file.write_all(&encoder.stdout).unwrap();
@@ -296,7 +406,7 @@ impl RequestWriter {
     }
 
     fn write_payload(payload: impl AsRef<str>, r#type: impl AsRef<str>, path: impl AsRef<Path>) {
-        let input = Command::new("echo")
+        let mut input = Command::new("echo")
             .arg(format!("{{\"payload\": \"{}\"}}", payload.as_ref()))
             .stdout(Stdio::piped())
             .spawn()
@@ -304,9 +414,10 @@ impl RequestWriter {
         let encoder = Command::new("/opt/cartesi/bin/rollup-memory-range")
             .arg("encode")
             .arg(r#type.as_ref())
-            .stdin(Stdio::from(input.stdout.unwrap()))
+            .stdin(Stdio::from(input.stdout.take().unwrap()))
             .output()
             .unwrap();
+        input.wait().unwrap();
         let mut file = File::create(path).unwrap();
         file.write_all(&encoder.stdout).unwrap();
     }
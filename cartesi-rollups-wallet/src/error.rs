@@ -0,0 +1,30 @@
+use cartesi_rollups::{Address, RollupsError, U256};
+use cartesi_rollups_evm_utils::portals::DepositError;
+use thiserror::Error;
+
+/// Defines errors of the operations of a [`Wallet`].
+///
+/// A failed operation leaves the balances unchanged.
+///
+/// [`Wallet`]: crate::Wallet
+#[derive(Error, Debug)]
+pub enum WalletError {
+    #[error("balance of {owner} is {available}, expected at least {required}")]
+    InsufficientBalance {
+        owner: Address,
+        available: U256,
+        required: U256,
+    },
+    #[error("token {token_id} of {token} is not owned by {owner}")]
+    NotOwner {
+        token: Address,
+        token_id: U256,
+        owner: Address,
+    },
+    #[error("balance of {owner} would overflow")]
+    Overflow { owner: Address },
+    #[error(transparent)]
+    Deposit(#[from] DepositError),
+    #[error(transparent)]
+    Rollups(#[from] RollupsError),
+}
@@ -0,0 +1,5 @@
+mod error;
+mod wallet;
+
+pub use error::*;
+pub use wallet::*;
@@ -0,0 +1,400 @@
+//! Items in this module keep track of the assets the users of a DApp deposited through the portals.
+//!
+//! # Examples
+//!
+//! ```
+//! # use cartesi_rollups::{Address, FinishStatus, MachineIo, RollupsMetadata, U256};
+//! # use cartesi_rollups_evm_utils::VoucherBuilder;
+//! # use cartesi_rollups_wallet::{Wallet, WalletError};
+//! # fn advance(
+//! #     wallet: &mut Wallet,
+//! #     machine: &dyn MachineIo,
+//! #     vouchers: &VoucherBuilder,
+//! #     metadata: RollupsMetadata,
+//! #     payload: Vec<u8>,
+//! # ) -> Result<(), WalletError> {
+//! if wallet.deposit(&metadata, &payload)?.is_none() {
+//!     // Not a deposit, so the user asks for their Ether back.
+//!     let owner = metadata.msg_sender;
+//!     wallet.withdraw_ether(machine, vouchers, &owner, wallet.ether_balance(&owner))?;
+//! }
+//!
+//! // The input is accepted, so its changes are kept.
+//! wallet.finish(FinishStatus::Accept);
+//! # Ok(())
+//! # }
+//! ```
+use crate::WalletError;
+use cartesi_rollups::{Address, FinishStatus, MachineIo, RollupsMetadata, U256};
+use cartesi_rollups_evm_utils::portals::{Deposit, Portals};
+use cartesi_rollups_evm_utils::VoucherBuilder;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+type Result<T> = std::result::Result<T, WalletError>;
+
+/// Ledger of Ether, ERC-20, ERC-721 and ERC-1155 balances per owner.
+///
+/// Balances are credited by [`depositing`] the inputs of the portals and debited by withdrawals, which write the
+/// vouchers transferring the assets back on the base layer. Every operation either succeeds or leaves the balances
+/// unchanged.
+///
+/// The changes made while processing an input are pending until the input is finished: [`Wallet::commit`] keeps them
+/// once it is accepted, and [`Wallet::rollback`] reverts them when it is rejected, just like the machine reverts its
+/// state, including the debits of the withdrawals whose vouchers are discarded.
+///
+/// See the [module-level documentation](./index.html) for more details.
+///
+/// [`depositing`]: Wallet::deposit
+#[derive(Clone, Debug, Default)]
+pub struct Wallet {
+    portals: Portals,
+    ether: Ledger<Address, U256>,
+    erc20: Ledger<(Address, Address), U256>,
+    erc721: Ledger<(Address, U256), Address>,
+    erc1155: Ledger<(Address, U256, Address), U256>,
+}
+
+impl Wallet {
+    /// Creates an empty wallet accepting deposits of the default [`Portals`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty wallet accepting deposits of `portals`.
+    pub fn with_portals(portals: Portals) -> Self {
+        Self {
+            portals,
+            ..Self::default()
+        }
+    }
+
+    pub fn portals(&self) -> &Portals {
+        &self.portals
+    }
+
+    /// Keeps the changes made since the last commit or rollback, once the input that made them is accepted.
+    pub fn commit(&mut self) {
+        self.ether.commit();
+        self.erc20.commit();
+        self.erc721.commit();
+        self.erc1155.commit();
+    }
+
+    /// Reverts the changes made since the last commit or rollback, once the input that made them is rejected.
+    pub fn rollback(&mut self) {
+        self.ether.rollback();
+        self.erc20.rollback();
+        self.erc721.rollback();
+        self.erc1155.rollback();
+    }
+
+    /// Commits or rolls back the pending changes according to the `status` the input is finished with.
+    pub fn finish(&mut self, status: FinishStatus) {
+        match status {
+            FinishStatus::Accept => self.commit(),
+            FinishStatus::Reject => self.rollback(),
+        }
+    }
+
+    /// Credits the deposit of an advance state request if it was sent by one of the portals.
+    ///
+    /// Returns the deposit so the caller can act on its data, or `None` if the input is not a deposit.
+    pub fn deposit(&mut self, metadata: &RollupsMetadata, payload: &[u8]) -> Result<Option<Deposit>> {
+        let deposit = match self.portals.parse(&metadata.msg_sender, payload)? {
+            Some(deposit) => deposit,
+            None => return Ok(None),
+        };
+
+        match &deposit {
+            Deposit::Ether(deposit) => {
+                let changes = [Change::Credit(deposit.sender, deposit.sender, deposit.value)];
+                apply(&mut self.ether, changes)?;
+            }
+            Deposit::Erc20(deposit) if deposit.success => {
+                let key = (deposit.token, deposit.sender);
+                apply(&mut self.erc20, [Change::Credit(key, deposit.sender, deposit.amount)])?;
+            }
+            Deposit::Erc20(_) => {}
+            Deposit::Erc721(deposit) => {
+                self.erc721.insert((deposit.token, deposit.token_id), deposit.sender);
+            }
+            Deposit::Erc1155Single(deposit) => {
+                let key = (deposit.token, deposit.token_id, deposit.sender);
+                apply(&mut self.erc1155, [Change::Credit(key, deposit.sender, deposit.value)])?;
+            }
+            Deposit::Erc1155Batch(deposit) => {
+                let changes = deposit
+                    .token_ids
+                    .iter()
+                    .zip(&deposit.values)
+                    .map(|(&token_id, &value)| {
+                        Change::Credit((deposit.token, token_id, deposit.sender), deposit.sender, value)
+                    });
+                apply(&mut self.erc1155, changes)?;
+            }
+        }
+
+        Ok(Some(deposit))
+    }
+
+    pub fn ether_balance(&self, owner: &Address) -> U256 {
+        self.ether.get(owner).copied().unwrap_or_default()
+    }
+
+    pub fn erc20_balance(&self, token: &Address, owner: &Address) -> U256 {
+        self.erc20.get(&(*token, *owner)).copied().unwrap_or_default()
+    }
+
+    pub fn erc721_owner(&self, token: &Address, token_id: U256) -> Option<Address> {
+        self.erc721.get(&(*token, token_id)).copied()
+    }
+
+    pub fn erc1155_balance(&self, token: &Address, token_id: U256, owner: &Address) -> U256 {
+        self.erc1155
+            .get(&(*token, token_id, *owner))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Moves `value` Wei from `from` to `to`.
+    pub fn transfer_ether(&mut self, from: &Address, to: &Address, value: U256) -> Result<()> {
+        let changes = [Change::Debit(*from, *from, value), Change::Credit(*to, *to, value)];
+
+        apply(&mut self.ether, changes)
+    }
+
+    /// Moves `amount` of ERC-20 `token` from `from` to `to`.
+    pub fn transfer_erc20(&mut self, token: &Address, from: &Address, to: &Address, amount: U256) -> Result<()> {
+        let changes = [
+            Change::Debit((*token, *from), *from, amount),
+            Change::Credit((*token, *to), *to, amount),
+        ];
+
+        apply(&mut self.erc20, changes)
+    }
+
+    /// Moves the ERC-721 `token` of `token_id` from `from` to `to`.
+    pub fn transfer_erc721(&mut self, token: &Address, from: &Address, to: &Address, token_id: U256) -> Result<()> {
+        self.check_erc721_owner(token, from, token_id)?;
+        self.erc721.insert((*token, token_id), *to);
+
+        Ok(())
+    }
+
+    /// Moves `value` of ERC-1155 `token` of `token_id` from `from` to `to`.
+    pub fn transfer_erc1155(
+        &mut self,
+        token: &Address,
+        from: &Address,
+        to: &Address,
+        token_id: U256,
+        value: U256,
+    ) -> Result<()> {
+        let changes = [
+            Change::Debit((*token, token_id, *from), *from, value),
+            Change::Credit((*token, token_id, *to), *to, value),
+        ];
+
+        apply(&mut self.erc1155, changes)
+    }
+
+    /// Debits `value` Wei from `owner` and writes the voucher withdrawing it to `owner` on the base layer.
+    ///
+    /// Returns the result of [`MachineIo::write_voucher`].
+    pub fn withdraw_ether(
+        &mut self,
+        machine: &dyn MachineIo,
+        vouchers: &VoucherBuilder,
+        owner: &Address,
+        value: U256,
+    ) -> Result<usize> {
+        let pending = prepare(&self.ether, [Change::Debit(*owner, *owner, value)])?;
+        let (destination, payload) = vouchers.ether_withdrawal(owner, value);
+        let index = machine.write_voucher(&destination, &payload)?;
+        commit(&mut self.ether, pending);
+
+        Ok(index)
+    }
+
+    /// Debits `amount` of ERC-20 `token` from `owner` and writes the voucher transferring it to `owner` on the base
+    /// layer.
+    pub fn withdraw_erc20(
+        &mut self,
+        machine: &dyn MachineIo,
+        vouchers: &VoucherBuilder,
+        token: &Address,
+        owner: &Address,
+        amount: U256,
+    ) -> Result<usize> {
+        let pending = prepare(&self.erc20, [Change::Debit((*token, *owner), *owner, amount)])?;
+        let (destination, payload) = vouchers.erc20_transfer(token, owner, amount);
+        let index = machine.write_voucher(&destination, &payload)?;
+        commit(&mut self.erc20, pending);
+
+        Ok(index)
+    }
+
+    /// Removes the ERC-721 `token` of `token_id` from `owner` and writes the voucher transferring it to `owner` on the
+    /// base layer.
+    pub fn withdraw_erc721(
+        &mut self,
+        machine: &dyn MachineIo,
+        vouchers: &VoucherBuilder,
+        token: &Address,
+        owner: &Address,
+        token_id: U256,
+    ) -> Result<usize> {
+        self.check_erc721_owner(token, owner, token_id)?;
+        let (destination, payload) = vouchers.erc721_transfer(token, owner, token_id);
+        let index = machine.write_voucher(&destination, &payload)?;
+        self.erc721.remove(&(*token, token_id));
+
+        Ok(index)
+    }
+
+    /// Debits `value` of ERC-1155 `token` of `token_id` from `owner` and writes the voucher transferring it to `owner`
+    /// on the base layer.
+    pub fn withdraw_erc1155(
+        &mut self,
+        machine: &dyn MachineIo,
+        vouchers: &VoucherBuilder,
+        token: &Address,
+        owner: &Address,
+        token_id: U256,
+        value: U256,
+    ) -> Result<usize> {
+        let changes = [Change::Debit((*token, token_id, *owner), *owner, value)];
+        let pending = prepare(&self.erc1155, changes)?;
+        let (destination, payload) = vouchers.erc1155_transfer(token, owner, token_id, value, &[]);
+        let index = machine.write_voucher(&destination, &payload)?;
+        commit(&mut self.erc1155, pending);
+
+        Ok(index)
+    }
+
+    fn check_erc721_owner(&self, token: &Address, owner: &Address, token_id: U256) -> Result<()> {
+        match self.erc721_owner(token, token_id) {
+            Some(actual) if actual == *owner => Ok(()),
+            _ => Err(WalletError::NotOwner {
+                token: *token,
+                token_id,
+                owner: *owner,
+            }),
+        }
+    }
+}
+
+/// Map of the balances or owners of a kind of asset, remembering how to revert the changes since the last commit.
+#[derive(Clone, Debug)]
+struct Ledger<K, V> {
+    entries: HashMap<K, V>,
+    /// Previous value of each changed key, in the order of the changes.
+    undo: Vec<(K, Option<V>)>,
+}
+
+impl<K: Clone + Eq + Hash, V> Ledger<K, V> {
+    fn get(&self, key: &K) -> Option<&V> {
+        self.entries.get(key)
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        let previous = self.entries.insert(key.clone(), value);
+        self.undo.push((key, previous));
+    }
+
+    fn remove(&mut self, key: &K) {
+        if let Some(previous) = self.entries.remove(key) {
+            self.undo.push((key.clone(), Some(previous)));
+        }
+    }
+
+    fn commit(&mut self) {
+        self.undo.clear();
+    }
+
+    fn rollback(&mut self) {
+        while let Some((key, previous)) = self.undo.pop() {
+            match previous {
+                Some(value) => self.entries.insert(key, value),
+                None => self.entries.remove(&key),
+            };
+        }
+    }
+}
+
+impl<K, V> Default for Ledger<K, V> {
+    fn default() -> Self {
+        Self {
+            entries: HashMap::new(),
+            undo: Vec::new(),
+        }
+    }
+}
+
+/// Change of the balance under key `K` owned by the given address.
+enum Change<K> {
+    Credit(K, Address, U256),
+    Debit(K, Address, U256),
+}
+
+/// Computes the balances resulting from `changes` without modifying `balances`.
+fn prepare<K: Clone + Eq + Hash>(
+    balances: &Ledger<K, U256>,
+    changes: impl IntoIterator<Item = Change<K>>,
+) -> Result<HashMap<K, U256>> {
+    let mut pending = HashMap::new();
+
+    for change in changes {
+        let (key, balance) = match change {
+            Change::Credit(key, owner, amount) => {
+                let balance = pending
+                    .get(&key)
+                    .or_else(|| balances.get(&key))
+                    .copied()
+                    .unwrap_or_default();
+
+                (
+                    key,
+                    U256::checked_add(balance, amount).ok_or(WalletError::Overflow { owner })?,
+                )
+            }
+            Change::Debit(key, owner, amount) => {
+                let balance = pending
+                    .get(&key)
+                    .or_else(|| balances.get(&key))
+                    .copied()
+                    .unwrap_or_default();
+                let remaining = balance.checked_sub(amount).ok_or(WalletError::InsufficientBalance {
+                    owner,
+                    available: balance,
+                    required: amount,
+                })?;
+
+                (key, remaining)
+            }
+        };
+        pending.insert(key, balance);
+    }
+
+    Ok(pending)
+}
+
+fn commit<K: Clone + Eq + Hash>(balances: &mut Ledger<K, U256>, pending: HashMap<K, U256>) {
+    for (key, balance) in pending {
+        match balance.is_zero() {
+            true => balances.remove(&key),
+            false => balances.insert(key, balance),
+        };
+    }
+}
+
+fn apply<K: Clone + Eq + Hash>(
+    balances: &mut Ledger<K, U256>,
+    changes: impl IntoIterator<Item = Change<K>>,
+) -> Result<()> {
+    let pending = prepare(balances, changes)?;
+    commit(balances, pending);
+
+    Ok(())
+}
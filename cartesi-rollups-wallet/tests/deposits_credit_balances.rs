@@ -0,0 +1,78 @@
+use cartesi_rollups::{Address, MetadataVersion, RollupsMetadata, U256};
+use cartesi_rollups_evm_utils::abi::{encode, Token};
+use cartesi_rollups_evm_utils::portals::Portals;
+use cartesi_rollups_wallet::{Wallet, WalletError};
+
+const TOKEN: Address = Address::new([0xaa; 20]);
+const ALICE: Address = Address::new([0xa1; 20]);
+const BOB: Address = Address::new([0xb0; 20]);
+
+fn metadata(msg_sender: Address) -> RollupsMetadata {
+    RollupsMetadata {
+        msg_sender,
+        epoch_index: 0,
+        input_index: 0,
+        block_number: 0,
+        timestamp: 0,
+        version: MetadataVersion::V1,
+    }
+}
+
+#[test]
+fn test_deposits_credit_balances() {
+    let mut wallet = Wallet::new();
+    let value = U256::from(100u8).to_be_bytes();
+    let id = U256::from(7u8);
+    let layer_data = encode(&[Token::Bytes(vec![]), Token::Bytes(vec![])]);
+
+    let deposits = [
+        (Portals::ETHER, [ALICE.as_bytes().as_slice(), &value].concat()),
+        (
+            Portals::ERC20,
+            [[1].as_slice(), TOKEN.as_bytes(), ALICE.as_bytes(), &value].concat(),
+        ),
+        (
+            Portals::ERC20,
+            [[0].as_slice(), TOKEN.as_bytes(), ALICE.as_bytes(), &value].concat(),
+        ),
+        (
+            Portals::ERC721,
+            [
+                TOKEN.as_bytes().as_slice(),
+                ALICE.as_bytes(),
+                &id.to_be_bytes(),
+                &layer_data,
+            ]
+            .concat(),
+        ),
+        (
+            Portals::ERC1155_SINGLE,
+            [
+                TOKEN.as_bytes().as_slice(),
+                ALICE.as_bytes(),
+                &id.to_be_bytes(),
+                &value,
+                &layer_data,
+            ]
+            .concat(),
+        ),
+    ];
+
+    for (portal, payload) in deposits {
+        assert!(wallet.deposit(&metadata(portal), &payload).unwrap().is_some());
+    }
+    assert!(wallet.deposit(&metadata(ALICE), &value).unwrap().is_none());
+
+    wallet.transfer_ether(&ALICE, &BOB, U256::from(40u8)).unwrap();
+    wallet.transfer_erc721(&TOKEN, &ALICE, &BOB, id).unwrap();
+    let error = wallet
+        .transfer_erc20(&TOKEN, &ALICE, &BOB, U256::from(101u8))
+        .unwrap_err();
+
+    assert_eq!(U256::from(60u8), wallet.ether_balance(&ALICE));
+    assert_eq!(U256::from(40u8), wallet.ether_balance(&BOB));
+    assert_eq!(U256::from(100u8), wallet.erc20_balance(&TOKEN, &ALICE));
+    assert_eq!(Some(BOB), wallet.erc721_owner(&TOKEN, id));
+    assert_eq!(U256::from(100u8), wallet.erc1155_balance(&TOKEN, id, &ALICE));
+    assert!(matches!(error, WalletError::InsufficientBalance { .. }));
+}
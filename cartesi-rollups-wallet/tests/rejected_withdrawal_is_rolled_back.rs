@@ -0,0 +1,66 @@
+use cartesi_rollups::{Address, FinishStatus, MachineIo, MetadataVersion, RollupsMetadata, RollupsRequest, U256};
+use cartesi_rollups_evm_utils::portals::Portals;
+use cartesi_rollups_evm_utils::VoucherBuilder;
+use cartesi_rollups_test::{Data, FakeCartesiMachine};
+use cartesi_rollups_wallet::Wallet;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[test]
+fn test_rejected_withdrawal_is_rolled_back() {
+    let dapp = Address::new([0xda; 20]);
+    let alice = Address::new([0xa1; 20]);
+    let deposit = [alice.as_bytes().as_slice(), &U256::from(100u8).to_be_bytes()].concat();
+    let requests = [(Portals::ETHER, deposit), (alice, vec![]), (alice, vec![])]
+        .into_iter()
+        .zip(1..)
+        .map(|((msg_sender, payload), input_index)| RollupsRequest::AdvanceState {
+            metadata: RollupsMetadata {
+                msg_sender,
+                epoch_index: 0,
+                input_index,
+                block_number: 0,
+                timestamp: 0,
+                version: MetadataVersion::V1,
+            },
+            payload,
+        });
+
+    let actual_data = Rc::new(RefCell::new(Data::default()));
+    let machine = FakeCartesiMachine::new(requests, actual_data.clone());
+    let vouchers = VoucherBuilder::new(dapp);
+    let mut wallet = Wallet::new();
+    let mut status = FinishStatus::Accept;
+    let mut balances = vec![];
+
+    while let Ok(RollupsRequest::AdvanceState { metadata, payload }) = machine.submit(status) {
+        // The first withdrawal is rejected after its voucher was written, the second one is accepted.
+        status = match wallet.deposit(&metadata, &payload).unwrap() {
+            Some(_) => FinishStatus::Accept,
+            None if metadata.input_index == 2 => {
+                wallet
+                    .withdraw_ether(&machine, &vouchers, &alice, U256::from(30u8))
+                    .unwrap();
+                FinishStatus::Reject
+            }
+            None => {
+                wallet
+                    .withdraw_ether(&machine, &vouchers, &alice, U256::from(40u8))
+                    .unwrap();
+                FinishStatus::Accept
+            }
+        };
+        wallet.finish(status);
+        balances.push(wallet.ether_balance(&alice));
+    }
+
+    let expected_data = Rc::new(RefCell::new(Data {
+        notices: vec![],
+        vouchers: vec![vouchers.ether_withdrawal(&alice, U256::from(40u8))],
+        reports: vec![],
+        exceptions: vec![],
+    }));
+
+    assert_eq!(vec![U256::from(100u8), U256::from(100u8), U256::from(60u8)], balances);
+    assert_eq!(expected_data, actual_data);
+}
@@ -0,0 +1,46 @@
+use cartesi_rollups::{Address, MetadataVersion, RollupsMetadata, U256};
+use cartesi_rollups_evm_utils::portals::Portals;
+use cartesi_rollups_evm_utils::VoucherBuilder;
+use cartesi_rollups_test::{Data, FakeCartesiMachine};
+use cartesi_rollups_wallet::{Wallet, WalletError};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[test]
+fn test_withdrawal_writes_voucher() {
+    let dapp = Address::new([0xda; 20]);
+    let alice = Address::new([0xa1; 20]);
+    let metadata = RollupsMetadata {
+        msg_sender: Portals::ETHER,
+        epoch_index: 0,
+        input_index: 0,
+        block_number: 0,
+        timestamp: 0,
+        version: MetadataVersion::V1,
+    };
+    let deposit = [alice.as_bytes().as_slice(), &U256::from(100u8).to_be_bytes()].concat();
+
+    let actual_data = Rc::new(RefCell::new(Data::default()));
+    let machine = FakeCartesiMachine::new([], actual_data.clone());
+    let vouchers = VoucherBuilder::new(dapp);
+    let mut wallet = Wallet::new();
+
+    wallet.deposit(&metadata, &deposit).unwrap();
+    wallet
+        .withdraw_ether(&machine, &vouchers, &alice, U256::from(30u8))
+        .unwrap();
+    let error = wallet
+        .withdraw_ether(&machine, &vouchers, &alice, U256::from(71u8))
+        .unwrap_err();
+
+    let expected_data = Rc::new(RefCell::new(Data {
+        notices: vec![],
+        vouchers: vec![vouchers.ether_withdrawal(&alice, U256::from(30u8))],
+        reports: vec![],
+        exceptions: vec![],
+    }));
+
+    assert_eq!(U256::from(70u8), wallet.ether_balance(&alice));
+    assert!(matches!(error, WalletError::InsufficientBalance { .. }));
+    assert_eq!(expected_data, actual_data);
+}
@@ -0,0 +1,227 @@
+//! Items in this module decode the payloads of requests into typed values and encode typed outputs.
+//!
+//! A [`Codec`] converts between values and payloads: [`Json`] and `Bincode`, with the `bincode` feature, for any
+//! `serde` type, [`Abi`] for any type with the ABI traits of `cartesi-rollups-evm-utils`. [`TypedMachineIo`] wraps a
+//! [`MachineIo`] with a codec, so handlers receive a [`TypedRequest`] and write values instead of bytes. Advance and
+//! inspect state payloads decode into types of their own, e.g. commands and queries.
+//!
+//! Payloads that do not decode are answered by the adapter itself: the error is written as a report and the request
+//! is rejected, then the next request is fetched.
+//!
+//! # Examples
+//!
+//! ```
+//! # use cartesi_rollups::{FinishStatus, Json, MachineIo, RollupsError, TypedMachineIo, TypedRequest};
+//! # use serde::{Deserialize, Serialize};
+//! #[derive(Deserialize)]
+//! struct Deposit {
+//!     amount: u64,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct Query {
+//!     verbose: bool,
+//! }
+//!
+//! #[derive(Serialize)]
+//! struct Balance {
+//!     total: u64,
+//! }
+//!
+//! # fn run(machine: impl MachineIo) -> Result<(), RollupsError> {
+//! let machine = TypedMachineIo::new(machine, Json);
+//! let mut total = 0;
+//!
+//! loop {
+//!     match machine.submit::<Deposit, Query>(FinishStatus::Accept)? {
+//!         TypedRequest::AdvanceState { payload, .. } => {
+//!             total += payload.amount;
+//!             machine.write_notice(&Balance { total })?;
+//!         }
+//!         TypedRequest::InspectState { payload } if payload.verbose => {
+//!             machine.write_report(&Balance { total })?;
+//!         }
+//!         TypedRequest::InspectState { .. } => machine.write_report(&total)?,
+//!     }
+//! }
+//! # }
+//! ```
+use crate::{Address, BoxError, FinishStatus, MachineIo, RollupsError, RollupsMetadata, RollupsRequest};
+use cartesi_rollups_evm_utils::abi::{AbiDecode, AbiEncode};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use thiserror::Error;
+
+/// The implementor of this trait converts values of `T` to payloads.
+pub trait Encoder<T: ?Sized> {
+    fn encode(&self, value: &T) -> Result<Vec<u8>, BoxError>;
+}
+
+/// The implementor of this trait converts payloads to values of `T`.
+pub trait Decoder<T> {
+    fn decode(&self, payload: &[u8]) -> Result<T, BoxError>;
+}
+
+/// Converts values of `T` from and to payloads, implemented by every [`Encoder`] and [`Decoder`] of `T`.
+pub trait Codec<T>: Encoder<T> + Decoder<T> {}
+
+impl<T, C: Encoder<T> + Decoder<T>> Codec<T> for C {}
+
+/// Encodes values as JSON.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Json;
+
+impl<T: Serialize + ?Sized> Encoder<T> for Json {
+    fn encode(&self, value: &T) -> Result<Vec<u8>, BoxError> {
+        Ok(serde_json::to_vec(value)?)
+    }
+}
+
+impl<T: DeserializeOwned> Decoder<T> for Json {
+    fn decode(&self, payload: &[u8]) -> Result<T, BoxError> {
+        Ok(serde_json::from_slice(payload)?)
+    }
+}
+
+/// Encodes values with `bincode`.
+#[cfg(feature = "bincode")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Bincode;
+
+#[cfg(feature = "bincode")]
+impl<T: Serialize + ?Sized> Encoder<T> for Bincode {
+    fn encode(&self, value: &T) -> Result<Vec<u8>, BoxError> {
+        Ok(bincode::serialize(value)?)
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl<T: DeserializeOwned> Decoder<T> for Bincode {
+    fn decode(&self, payload: &[u8]) -> Result<T, BoxError> {
+        Ok(bincode::deserialize(payload)?)
+    }
+}
+
+/// Encodes values like `abi.encode` does on the base layer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Abi;
+
+impl<T: AbiEncode + ?Sized> Encoder<T> for Abi {
+    fn encode(&self, value: &T) -> Result<Vec<u8>, BoxError> {
+        Ok(value.abi_encode())
+    }
+}
+
+impl<T: AbiDecode> Decoder<T> for Abi {
+    fn decode(&self, payload: &[u8]) -> Result<T, BoxError> {
+        Ok(T::abi_decode(payload)?)
+    }
+}
+
+/// Defines errors of a [`Codec`], kept as the source of [`RollupsError::Other`] by [`TypedMachineIo`].
+#[derive(Error, Debug)]
+pub enum CodecError {
+    #[error("failed to decode payload: {0}")]
+    Decode(#[source] BoxError),
+    #[error("failed to encode output: {0}")]
+    Encode(#[source] BoxError),
+}
+
+/// [`RollupsRequest`] with a decoded payload, an `A` when advancing the state and an `I` when inspecting it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TypedRequest<A, I = A> {
+    AdvanceState { metadata: RollupsMetadata, payload: A },
+    InspectState { payload: I },
+}
+
+/// Adapter of a [`MachineIo`] decoding requests and encoding outputs with a [`Codec`].
+///
+/// See the [module-level documentation](./index.html) for more details.
+#[derive(Debug)]
+pub struct TypedMachineIo<M, C> {
+    machine: M,
+    codec: C,
+}
+
+impl<M: MachineIo, C> TypedMachineIo<M, C> {
+    pub fn new(machine: M, codec: C) -> Self {
+        Self { machine, codec }
+    }
+
+    /// Returns the wrapped machine, e.g. for writing vouchers, whose payloads are calls of the base layer.
+    pub fn machine(&self) -> &M {
+        &self.machine
+    }
+
+    pub fn into_inner(self) -> M {
+        self.machine
+    }
+
+    /// Writes a notice with the encoded `value`.
+    pub fn write_notice<T: ?Sized>(&self, value: &T) -> Result<usize, RollupsError>
+    where
+        C: Encoder<T>,
+    {
+        self.machine.write_notice(&self.encode(value)?)
+    }
+
+    /// Writes a voucher for `address` with the encoded `value`, usually with the [`Abi`] codec.
+    pub fn write_voucher<T: ?Sized>(&self, address: &Address, value: &T) -> Result<usize, RollupsError>
+    where
+        C: Encoder<T>,
+    {
+        self.machine.write_voucher(address, &self.encode(value)?)
+    }
+
+    /// Writes a report with the encoded `value`.
+    pub fn write_report<T: ?Sized>(&self, value: &T) -> Result<(), RollupsError>
+    where
+        C: Encoder<T>,
+    {
+        self.machine.write_report(&self.encode(value)?)
+    }
+
+    /// Finishes the previous request with `status` and retrieves the next one whose payload decodes, into an `A` when
+    /// advancing the state and into an `I` when inspecting it.
+    ///
+    /// A request whose payload does not decode gets a report with the error and is rejected.
+    pub fn submit<A, I>(&self, status: FinishStatus) -> Result<TypedRequest<A, I>, RollupsError>
+    where
+        C: Decoder<A> + Decoder<I>,
+    {
+        let mut status = status;
+
+        loop {
+            let request = match self.machine.submit(status)? {
+                RollupsRequest::AdvanceState { metadata, payload } => self
+                    .codec
+                    .decode(&payload)
+                    .map(|payload| TypedRequest::AdvanceState { metadata, payload }),
+                RollupsRequest::InspectState { payload } => self
+                    .codec
+                    .decode(&payload)
+                    .map(|payload| TypedRequest::InspectState { payload }),
+            };
+
+            match request {
+                Ok(request) => return Ok(request),
+                Err(error) => {
+                    let error = CodecError::Decode(error);
+
+                    log::warn!("rejecting request: {error}");
+                    self.machine.write_report(error.to_string().as_bytes())?;
+                    status = FinishStatus::Reject;
+                }
+            }
+        }
+    }
+
+    fn encode<T: ?Sized>(&self, value: &T) -> Result<Vec<u8>, RollupsError>
+    where
+        C: Encoder<T>,
+    {
+        self.codec
+            .encode(value)
+            .map_err(|error| RollupsError::Other(Box::new(CodecError::Encode(error))))
+    }
+}
@@ -0,0 +1,140 @@
+//! Items in this module drive a DApp through the requests of a [`MachineIo`].
+//!
+//! Instead of writing the submit loop by hand, implement [`DApp`] and hand it over to [`run`].
+//!
+//! # Examples
+//!
+//! ```
+//! # use std::error::Error;
+//! # use cartesi_rollups::{Context, DApp, FinishStatus, RollupsMetadata};
+//! struct Echo;
+//!
+//! impl DApp for Echo {
+//!     fn advance(
+//!         &mut self,
+//!         ctx: &Context,
+//!         _metadata: RollupsMetadata,
+//!         payload: Vec<u8>,
+//!     ) -> Result<FinishStatus, Box<dyn Error>> {
+//!         ctx.write_notice(&payload)?;
+//!
+//!         Ok(FinishStatus::Accept)
+//!     }
+//!
+//!     fn inspect(&self, ctx: &Context, payload: Vec<u8>) -> Result<(), Box<dyn Error>> {
+//!         ctx.write_report(&payload)?;
+//!
+//!         Ok(())
+//!     }
+//! }
+//! ```
+use crate::relay::voucher_builder;
+use crate::{
+    Address, FinishStatus, MachineIo, RollupsError, RollupsMetadata, RollupsRequest, UnknownDAppAddress,
+    VoucherBuilder, U256,
+};
+use std::error::Error;
+
+/// Gives the handlers of a [`DApp`] access to the outputs of the machine.
+pub struct Context<'a> {
+    machine: &'a dyn MachineIo,
+}
+
+impl<'a> Context<'a> {
+    pub fn new(machine: &'a dyn MachineIo) -> Self {
+        Self { machine }
+    }
+
+    /// Writes a notice with `payload`.
+    pub fn write_notice(&self, payload: &[u8]) -> Result<usize, RollupsError> {
+        self.machine.write_notice(payload)
+    }
+
+    /// Writes a voucher with `payload` for `address`.
+    pub fn write_voucher(&self, address: &Address, payload: &[u8]) -> Result<usize, RollupsError> {
+        self.machine.write_voucher(address, payload)
+    }
+
+    /// Writes a voucher with `payload` for `address` sending `value` Wei, see [`MachineIo::write_voucher_with_value`].
+    pub fn write_voucher_with_value(
+        &self,
+        address: &Address,
+        value: &U256,
+        payload: &[u8],
+    ) -> Result<usize, RollupsError> {
+        self.machine.write_voucher_with_value(address, value, payload)
+    }
+
+    /// Writes a report with `payload`.
+    pub fn write_report(&self, payload: &[u8]) -> Result<(), RollupsError> {
+        self.machine.write_report(payload)
+    }
+
+    /// Returns the address of the DApp contract, if the machine knows it.
+    ///
+    /// See [`DAppAddressMachine`](crate::DAppAddressMachine).
+    pub fn dapp_address(&self) -> Option<Address> {
+        self.machine.dapp_address()
+    }
+
+    /// Returns the builder of the vouchers of the DApp, or an error until its address is known.
+    pub fn voucher_builder(&self) -> Result<VoucherBuilder, UnknownDAppAddress> {
+        voucher_builder(self.machine)
+    }
+}
+
+/// The implementor of this trait handles the requests [`run`] retrieves from the machine.
+pub trait DApp {
+    /// Advances the state of the dapp using the `payload` and `metadata`.
+    ///
+    /// The returned status finishes the input. Returning an error rejects the input, see [`run`].
+    fn advance(
+        &mut self,
+        ctx: &Context,
+        metadata: RollupsMetadata,
+        payload: Vec<u8>,
+    ) -> Result<FinishStatus, Box<dyn Error>>;
+
+    /// Inspects the current state of the dapp using the `payload` without advancing it.
+    fn inspect(&self, ctx: &Context, payload: Vec<u8>) -> Result<(), Box<dyn Error>>;
+}
+
+/// Runs `dapp` by submitting to `machine` and dispatching each request to the matching [`DApp`] handler.
+///
+/// An error returned by a handler is written as a report and the request gets rejected. The only exception is a
+/// [`RollupsError`] coming from the machine itself, which leaves the outputs in an unknown state. That one is written
+/// using [`MachineIo::throw_exception`] and returned.
+///
+/// Returns only when the machine fails.
+pub fn run(mut dapp: impl DApp, machine: impl MachineIo) -> Result<(), RollupsError> {
+    let ctx = Context::new(&machine);
+    let mut status = FinishStatus::Accept;
+
+    loop {
+        status = match machine.submit(status)? {
+            RollupsRequest::AdvanceState { metadata, payload } => match dapp.advance(&ctx, metadata, payload) {
+                Ok(status) => status,
+                Err(error) => handle_error(&machine, error)?,
+            },
+            RollupsRequest::InspectState { payload } => match dapp.inspect(&ctx, payload) {
+                Ok(()) => FinishStatus::Accept,
+                Err(error) => handle_error(&machine, error)?,
+            },
+        };
+    }
+}
+
+fn handle_error(machine: &impl MachineIo, error: Box<dyn Error>) -> Result<FinishStatus, RollupsError> {
+    match error.downcast::<RollupsError>() {
+        Ok(error) => {
+            machine.throw_exception(error.to_string().as_bytes())?;
+
+            Err(*error)
+        }
+        Err(error) => {
+            machine.write_report(error.to_string().as_bytes())?;
+
+            Ok(FinishStatus::Reject)
+        }
+    }
+}
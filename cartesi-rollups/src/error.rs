@@ -0,0 +1,52 @@
+//! Items in this module define the errors reported by [`MachineIo`] implementations.
+//!
+//! [`MachineIo`]: crate::MachineIo
+use std::error::Error;
+use std::io;
+use thiserror::Error;
+
+/// Type-erased error kept as the source of a [`RollupsError`].
+pub type BoxError = Box<dyn Error + Send + Sync>;
+
+/// Defines errors of the Cartesi rollup device abstraction.
+///
+/// Every variant keeps the error it was mapped from as its [`source`], so the original cause stays reachable.
+///
+/// [`source`]: Error::source
+#[derive(Error, Debug)]
+pub enum RollupsError {
+    /// There are no more requests to handle.
+    #[error("reached end of request queue")]
+    QueueExhausted(#[source] BoxError),
+    /// The payload of `length` bytes does not fit the device buffer.
+    #[error("payload of {length} bytes is too large")]
+    PayloadTooLarge {
+        length: usize,
+        #[source]
+        source: BoxError,
+    },
+    /// The device rejected the request; the errno is available through [`RollupsError::errno`].
+    #[error("rollup device request failed")]
+    Device(#[source] io::Error),
+    /// The device asked for handling a request of a type this crate does not know.
+    #[error("unsupported request type {0}")]
+    UnsupportedRequestType(u32),
+    /// Any other failure of the implementor.
+    #[error(transparent)]
+    Other(BoxError),
+}
+
+impl RollupsError {
+    /// Returns the errno reported by the device, if this error originates from it.
+    ///
+    /// A [`RollupsError::PayloadTooLarge`] has one when its source is the [`io::Error`] of the device.
+    pub fn errno(&self) -> Option<i32> {
+        match self {
+            RollupsError::Device(error) => error.raw_os_error(),
+            RollupsError::PayloadTooLarge { source, .. } => {
+                source.downcast_ref::<io::Error>().and_then(io::Error::raw_os_error)
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,392 @@
+//! Items in this module record the requests and outputs of a DApp and replay them, to reproduce misbehaving inputs.
+//!
+//! Wrap the machine in [`RecordingMachine`] to write a journal with one JSON [`JournalEntry`] per line, then run the
+//! DApp against a [`ReplayMachine`] reading that journal. The replay hands over the recorded requests and lists every
+//! [`Divergence`] between the outputs written now and the recorded ones. Outputs are recorded as the [`Event`] lines
+//! `StdioMachine` writes.
+//!
+//! ```text
+//! {"type":"finish","status":"accept"}
+//! {"type":"request","request":{"type":"inspect_state","payload":"0x717565"}}
+//! {"type":"report","payload":"0x7265706f7274"}
+//! {"type":"finish","status":"accept"}
+//! ```
+//!
+//! # Examples
+//!
+//! ```
+//! # use cartesi_rollups::{MachineIo, RecordingMachine, ReplayMachine};
+//! # fn record(machine: impl MachineIo) -> Result<(), cartesi_rollups::RollupsError> {
+//! let machine = RecordingMachine::new(machine, Vec::new());
+//! // Runs the DApp with `machine` until the input misbehaves...
+//! let (_, journal) = machine.into_inner();
+//!
+//! let replay = ReplayMachine::new(journal.as_slice());
+//! // ...and runs the fixed DApp with `replay` until the journal is exhausted.
+//! for divergence in replay.into_divergences() {
+//!     eprintln!("{divergence:?}");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use crate::{Address, Event, FinishStatus, MachineIo, RollupsError, RollupsRequest, U256};
+use serde::{Deserialize, Serialize};
+use std::cell::{Cell, RefCell};
+use std::io;
+use std::io::{BufRead, Write};
+
+/// Line of a journal.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JournalEntry {
+    /// Request returned by [`MachineIo::submit`].
+    Request { request: RollupsRequest },
+    /// Output or finish status, serialized as the [`Event`] itself.
+    #[serde(untagged)]
+    Event(Event),
+}
+
+/// [`MachineIo`] writing every request and output of the wrapped machine to a journal.
+///
+/// Only successful calls are recorded. A failure to write the journal is logged and leaves the call untouched, so
+/// recording never changes the behavior of the DApp.
+///
+/// See the [module-level documentation](./index.html) for more details.
+#[derive(Debug)]
+pub struct RecordingMachine<M, W> {
+    machine: M,
+    journal: RefCell<W>,
+}
+
+impl<M: MachineIo, W: Write> RecordingMachine<M, W> {
+    pub fn new(machine: M, journal: W) -> Self {
+        Self {
+            machine,
+            journal: RefCell::new(journal),
+        }
+    }
+
+    pub fn into_inner(self) -> (M, W) {
+        (self.machine, self.journal.into_inner())
+    }
+
+    fn record(&self, entry: JournalEntry) {
+        let mut journal = self.journal.borrow_mut();
+        let result = serde_json::to_writer(&mut *journal, &entry)
+            .map_err(io::Error::from)
+            .and_then(|_| journal.write_all(b"\n"))
+            .and_then(|_| journal.flush());
+
+        if let Err(error) = result {
+            log::error!("failed to record {entry:?} in the journal: {error}");
+        }
+    }
+}
+
+impl<M: MachineIo, W: Write> MachineIo for RecordingMachine<M, W> {
+    fn write_notice(&self, payload: &[u8]) -> Result<usize, RollupsError> {
+        let index = self.machine.write_notice(payload)?;
+
+        self.record(JournalEntry::Event(Event::Notice {
+            index,
+            payload: payload.to_vec(),
+        }));
+
+        Ok(index)
+    }
+
+    fn write_voucher(&self, address: &Address, payload: &[u8]) -> Result<usize, RollupsError> {
+        let index = self.machine.write_voucher(address, payload)?;
+
+        self.record(JournalEntry::Event(voucher_event(index, address, &U256::ZERO, payload)));
+
+        Ok(index)
+    }
+
+    fn write_voucher_with_value(&self, address: &Address, value: &U256, payload: &[u8]) -> Result<usize, RollupsError> {
+        let index = self.machine.write_voucher_with_value(address, value, payload)?;
+
+        self.record(JournalEntry::Event(voucher_event(index, address, value, payload)));
+
+        Ok(index)
+    }
+
+    fn write_report(&self, payload: &[u8]) -> Result<(), RollupsError> {
+        self.machine.write_report(payload)?;
+        self.record(JournalEntry::Event(Event::Report {
+            payload: payload.to_vec(),
+        }));
+
+        Ok(())
+    }
+
+    fn submit(&self, status: FinishStatus) -> Result<RollupsRequest, RollupsError> {
+        self.record(JournalEntry::Event(Event::Finish { status }));
+
+        let request = self.machine.submit(status)?;
+
+        self.record(JournalEntry::Request {
+            request: request.clone(),
+        });
+
+        Ok(request)
+    }
+
+    fn throw_exception(&self, payload: &[u8]) -> Result<(), RollupsError> {
+        self.machine.throw_exception(payload)?;
+        self.record(JournalEntry::Event(Event::Exception {
+            payload: payload.to_vec(),
+        }));
+
+        Ok(())
+    }
+
+    fn dapp_address(&self) -> Option<Address> {
+        self.machine.dapp_address()
+    }
+}
+
+/// Output of a replay differing from the journal.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Divergence {
+    /// One-based line of the journal holding the expected entry, or the line after the recorded outputs.
+    pub line: usize,
+    /// Recorded event, if the journal has one at this position.
+    pub expected: Option<Event>,
+    /// Event written by the replay, if it wrote one at this position.
+    pub actual: Option<Event>,
+}
+
+/// [`MachineIo`] handing over the requests of a journal and comparing the outputs with the recorded ones.
+///
+/// The outputs of a request, its finish status included, are compared once the request is finished, and every
+/// mismatch is kept as a [`Divergence`]. Notices and vouchers get the indices the journal recorded for them, and
+/// reaching the end of the journal is [`RollupsError::QueueExhausted`].
+///
+/// See the [module-level documentation](./index.html) for more details.
+#[derive(Debug)]
+pub struct ReplayMachine<R> {
+    journal: RefCell<R>,
+    /// Number of journal lines read.
+    line: Cell<usize>,
+    /// Recorded outputs of the current request, next to their lines.
+    expected: RefCell<Vec<(usize, Event)>>,
+    /// Outputs of the current request written by the replay.
+    actual: RefCell<Vec<Event>>,
+    divergences: RefCell<Vec<Divergence>>,
+    started: Cell<bool>,
+    app_contract: Cell<Option<Address>>,
+}
+
+impl<R: BufRead> ReplayMachine<R> {
+    pub fn new(journal: R) -> Self {
+        Self {
+            journal: RefCell::new(journal),
+            line: Cell::new(0),
+            expected: RefCell::new(Vec::new()),
+            actual: RefCell::new(Vec::new()),
+            divergences: RefCell::new(Vec::new()),
+            started: Cell::new(false),
+            app_contract: Cell::new(None),
+        }
+    }
+
+    /// Returns the divergences of the requests finished so far.
+    pub fn divergences(&self) -> Vec<Divergence> {
+        self.divergences.borrow().clone()
+    }
+
+    /// Returns the divergences of the requests finished so far and of the current one, e.g. after an exception.
+    pub fn into_divergences(self) -> Vec<Divergence> {
+        if self.started.get() {
+            self.compare_outputs();
+        }
+
+        self.divergences.into_inner()
+    }
+
+    /// Reads the next entry of the journal, skipping blank lines.
+    fn read_entry(&self) -> Result<Option<(usize, JournalEntry)>, RollupsError> {
+        let mut journal = self.journal.borrow_mut();
+        let mut line = String::new();
+
+        loop {
+            line.clear();
+
+            if journal
+                .read_line(&mut line)
+                .map_err(|error| RollupsError::Other(Box::new(error)))?
+                == 0
+            {
+                return Ok(None);
+            }
+
+            self.line.set(self.line.get() + 1);
+
+            if !line.trim().is_empty() {
+                let entry = serde_json::from_str(&line).map_err(|error| RollupsError::Other(Box::new(error)))?;
+
+                return Ok(Some((self.line.get(), entry)));
+            }
+        }
+    }
+
+    /// Reads the recorded outputs of the current request, up to and including its finish status.
+    fn read_outputs(&self) -> Result<(), RollupsError> {
+        let mut expected = Vec::new();
+
+        while let Some((line, entry)) = self.read_entry()? {
+            let event = match entry {
+                JournalEntry::Event(event) => event,
+                JournalEntry::Request { .. } => {
+                    return Err(RollupsError::Other(
+                        format!("expected an output on line {line} of the journal, found a request").into(),
+                    ))
+                }
+            };
+            let finished = matches!(event, Event::Finish { .. });
+
+            expected.push((line, event));
+
+            if finished {
+                break;
+            }
+        }
+
+        *self.expected.borrow_mut() = expected;
+
+        Ok(())
+    }
+
+    /// Records a divergence for every output of the current request differing from the journal.
+    fn compare_outputs(&self) {
+        let expected = self.expected.take();
+        let actual = self.actual.take();
+        let end = expected.last().map_or(self.line.get(), |(line, _)| *line) + 1;
+        let mut divergences = self.divergences.borrow_mut();
+
+        for position in 0..expected.len().max(actual.len()) {
+            let (line, expected) = match expected.get(position) {
+                Some((line, entry)) => (*line, Some(entry.clone())),
+                None => (end, None),
+            };
+            let actual = actual.get(position).cloned();
+
+            if expected != actual {
+                divergences.push(Divergence { line, expected, actual });
+            }
+        }
+    }
+
+    /// Returns the index recorded for the output written next, if the journal has one of the same kind there.
+    fn recorded_index(&self, voucher: bool) -> Option<usize> {
+        let position = self.actual.borrow().len();
+
+        match self.expected.borrow().get(position) {
+            Some((_, Event::Notice { index, .. })) if !voucher => Some(*index),
+            Some((_, Event::Voucher { index, .. })) if voucher => Some(*index),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of notices or vouchers written by the replay for the current request.
+    fn written(&self, voucher: bool) -> usize {
+        self.actual
+            .borrow()
+            .iter()
+            .filter(|event| match event {
+                Event::Notice { .. } => !voucher,
+                Event::Voucher { .. } => voucher,
+                _ => false,
+            })
+            .count()
+    }
+}
+
+impl<R: BufRead> MachineIo for ReplayMachine<R> {
+    fn write_notice(&self, payload: &[u8]) -> Result<usize, RollupsError> {
+        let index = self.recorded_index(false).unwrap_or_else(|| self.written(false));
+
+        self.actual.borrow_mut().push(Event::Notice {
+            index,
+            payload: payload.to_vec(),
+        });
+
+        Ok(index)
+    }
+
+    fn write_voucher(&self, address: &Address, payload: &[u8]) -> Result<usize, RollupsError> {
+        self.write_voucher_with_value(address, &U256::ZERO, payload)
+    }
+
+    fn write_voucher_with_value(&self, address: &Address, value: &U256, payload: &[u8]) -> Result<usize, RollupsError> {
+        let index = self.recorded_index(true).unwrap_or_else(|| self.written(true));
+
+        self.actual
+            .borrow_mut()
+            .push(voucher_event(index, address, value, payload));
+
+        Ok(index)
+    }
+
+    fn write_report(&self, payload: &[u8]) -> Result<(), RollupsError> {
+        self.actual.borrow_mut().push(Event::Report {
+            payload: payload.to_vec(),
+        });
+
+        Ok(())
+    }
+
+    fn submit(&self, status: FinishStatus) -> Result<RollupsRequest, RollupsError> {
+        if !self.started.replace(true) {
+            self.read_outputs()?;
+        }
+
+        self.actual.borrow_mut().push(Event::Finish { status });
+        self.compare_outputs();
+
+        let request = match self.read_entry()? {
+            Some((_, JournalEntry::Request { request })) => request,
+            Some((line, entry)) => {
+                return Err(RollupsError::Other(
+                    format!("expected a request on line {line} of the journal, found {entry:?}").into(),
+                ))
+            }
+            None => {
+                return Err(RollupsError::QueueExhausted(
+                    io::Error::from(io::ErrorKind::UnexpectedEof).into(),
+                ))
+            }
+        };
+
+        self.read_outputs()?;
+
+        if let RollupsRequest::AdvanceState { metadata, .. } = &request {
+            self.app_contract
+                .set(metadata.app_contract().or(self.app_contract.get()));
+        }
+
+        Ok(request)
+    }
+
+    fn throw_exception(&self, payload: &[u8]) -> Result<(), RollupsError> {
+        self.actual.borrow_mut().push(Event::Exception {
+            payload: payload.to_vec(),
+        });
+
+        Ok(())
+    }
+
+    fn dapp_address(&self) -> Option<Address> {
+        self.app_contract.get()
+    }
+}
+
+fn voucher_event(index: usize, address: &Address, value: &U256, payload: &[u8]) -> Event {
+    Event::Voucher {
+        index,
+        destination: *address,
+        value: *value,
+        payload: payload.to_vec(),
+    }
+}
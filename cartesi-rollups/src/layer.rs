@@ -0,0 +1,527 @@
+//! Items in this module wrap a [`MachineIo`] in layers handling cross-cutting concerns once for every DApp.
+//!
+//! A [`Layer`] turns a machine into another one, and [`MachineIoExt::layer`] stacks them, the last layer being the
+//! outermost:
+//!
+//! - [`PayloadLimitLayer`] fails outputs with larger payloads than a limit.
+//! - [`LoggingLayer`] logs every request and output, without their payloads unless logging at trace level.
+//! - [`MetricsLayer`] counts the requests and outputs and times the handling of every input.
+//! - [`SenderAllowListLayer`] rejects the advance state requests of unknown senders.
+//!
+//! # Examples
+//!
+//! ```
+//! # use cartesi_rollups::{Address, LoggingLayer, MachineIo, MachineIoExt, MetricsLayer, PayloadLimitLayer};
+//! # fn start(machine: impl MachineIo) -> Result<(), cartesi_rollups::RollupsError> {
+//! let metrics = MetricsLayer::default();
+//! let machine = machine
+//!     .layer(PayloadLimitLayer::new(1024))
+//!     .layer(LoggingLayer)
+//!     .layer(metrics.clone());
+//! // Runs the DApp with `machine`...
+//! println!("{:?}", metrics.metrics());
+//! # Ok(())
+//! # }
+//! ```
+use crate::{Address, FinishStatus, MachineIo, RollupsError, RollupsRequest, U256};
+use std::cell::{Cell, RefCell};
+use std::collections::HashSet;
+use std::fmt;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+/// Wraps a [`MachineIo`] in another one.
+pub trait Layer<M> {
+    type Machine: MachineIo;
+
+    fn layer(self, machine: M) -> Self::Machine;
+}
+
+/// Adds [`layer`] to every [`MachineIo`].
+///
+/// [`layer`]: MachineIoExt::layer
+pub trait MachineIoExt: MachineIo + Sized {
+    /// Wraps this machine in `layer`.
+    fn layer<L: Layer<Self>>(self, layer: L) -> L::Machine {
+        layer.layer(self)
+    }
+}
+
+impl<M: MachineIo> MachineIoExt for M {}
+
+/// The payload of an output is larger than the limit of a [`PayloadLimitLayer`].
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("payload exceeds the limit of {limit} bytes")]
+pub struct PayloadLimitExceeded {
+    pub limit: usize,
+}
+
+/// Fails outputs with payloads of more than `limit` bytes with [`RollupsError::PayloadTooLarge`], before they reach
+/// the machine.
+///
+/// Exceptions are not limited, so the failure reported by [`run`] for such an output is not lost.
+///
+/// [`run`]: crate::run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PayloadLimitLayer {
+    limit: usize,
+}
+
+impl PayloadLimitLayer {
+    pub fn new(limit: usize) -> Self {
+        Self { limit }
+    }
+}
+
+impl<M: MachineIo> Layer<M> for PayloadLimitLayer {
+    type Machine = PayloadLimitMachine<M>;
+
+    fn layer(self, machine: M) -> Self::Machine {
+        PayloadLimitMachine {
+            machine,
+            limit: self.limit,
+        }
+    }
+}
+
+/// [`MachineIo`] of a [`PayloadLimitLayer`].
+#[derive(Debug)]
+pub struct PayloadLimitMachine<M> {
+    machine: M,
+    limit: usize,
+}
+
+impl<M> PayloadLimitMachine<M> {
+    pub fn into_inner(self) -> M {
+        self.machine
+    }
+
+    fn check(&self, payload: &[u8]) -> Result<(), RollupsError> {
+        match payload.len() > self.limit {
+            true => Err(RollupsError::PayloadTooLarge {
+                length: payload.len(),
+                source: Box::new(PayloadLimitExceeded { limit: self.limit }),
+            }),
+            false => Ok(()),
+        }
+    }
+}
+
+impl<M: MachineIo> MachineIo for PayloadLimitMachine<M> {
+    fn write_notice(&self, payload: &[u8]) -> Result<usize, RollupsError> {
+        self.check(payload)?;
+        self.machine.write_notice(payload)
+    }
+
+    fn write_voucher(&self, address: &Address, payload: &[u8]) -> Result<usize, RollupsError> {
+        self.check(payload)?;
+        self.machine.write_voucher(address, payload)
+    }
+
+    fn write_voucher_with_value(&self, address: &Address, value: &U256, payload: &[u8]) -> Result<usize, RollupsError> {
+        self.check(payload)?;
+        self.machine.write_voucher_with_value(address, value, payload)
+    }
+
+    fn write_report(&self, payload: &[u8]) -> Result<(), RollupsError> {
+        self.check(payload)?;
+        self.machine.write_report(payload)
+    }
+
+    fn submit(&self, status: FinishStatus) -> Result<RollupsRequest, RollupsError> {
+        self.machine.submit(status)
+    }
+
+    fn throw_exception(&self, payload: &[u8]) -> Result<(), RollupsError> {
+        self.machine.throw_exception(payload)
+    }
+
+    fn dapp_address(&self) -> Option<Address> {
+        self.machine.dapp_address()
+    }
+}
+
+/// Logs every request and output at info level and failures at error level.
+///
+/// Only the lengths of the payloads are logged, the payloads themselves are logged at trace level.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LoggingLayer;
+
+impl<M: MachineIo> Layer<M> for LoggingLayer {
+    type Machine = LoggingMachine<M>;
+
+    fn layer(self, machine: M) -> Self::Machine {
+        LoggingMachine { machine }
+    }
+}
+
+/// [`MachineIo`] of a [`LoggingLayer`].
+#[derive(Debug)]
+pub struct LoggingMachine<M> {
+    machine: M,
+}
+
+impl<M> LoggingMachine<M> {
+    pub fn into_inner(self) -> M {
+        self.machine
+    }
+}
+
+/// Logs the `outcome` of writing an output of `kind` with `payload`, described by `fields`.
+///
+/// The outcome holds the index of the output, if it has one.
+fn log_output(kind: &str, fields: fmt::Arguments, payload: &[u8], outcome: Result<Option<usize>, &RollupsError>) {
+    match outcome {
+        Ok(Some(index)) => log::info!("{kind}: {{ {fields}length: {} index: {index} }}", payload.len()),
+        Ok(None) => log::info!("{kind}: {{ {fields}length: {} }}", payload.len()),
+        Err(error) => log::error!("{kind}: {{ {fields}length: {} error: {error} }}", payload.len()),
+    }
+    log::trace!("{kind} payload: 0x{}", hex::encode(payload));
+}
+
+impl<M: MachineIo> MachineIo for LoggingMachine<M> {
+    fn write_notice(&self, payload: &[u8]) -> Result<usize, RollupsError> {
+        let result = self.machine.write_notice(payload);
+
+        log_output(
+            "notice",
+            format_args!(""),
+            payload,
+            result.as_ref().map(|index| Some(*index)),
+        );
+
+        result
+    }
+
+    fn write_voucher(&self, address: &Address, payload: &[u8]) -> Result<usize, RollupsError> {
+        let result = self.machine.write_voucher(address, payload);
+
+        log_output(
+            "voucher",
+            format_args!("destination: {address} "),
+            payload,
+            result.as_ref().map(|index| Some(*index)),
+        );
+
+        result
+    }
+
+    fn write_voucher_with_value(&self, address: &Address, value: &U256, payload: &[u8]) -> Result<usize, RollupsError> {
+        let result = self.machine.write_voucher_with_value(address, value, payload);
+
+        log_output(
+            "voucher",
+            format_args!("destination: {address} value: {value} "),
+            payload,
+            result.as_ref().map(|index| Some(*index)),
+        );
+
+        result
+    }
+
+    fn write_report(&self, payload: &[u8]) -> Result<(), RollupsError> {
+        let result = self.machine.write_report(payload);
+
+        log_output("report", format_args!(""), payload, result.as_ref().map(|_| None));
+
+        result
+    }
+
+    fn submit(&self, status: FinishStatus) -> Result<RollupsRequest, RollupsError> {
+        log::info!("finish: {{ status: {status:?} }}");
+
+        let result = self.machine.submit(status);
+
+        match &result {
+            Ok(RollupsRequest::AdvanceState { metadata, payload }) => {
+                log::info!(
+                    "advance: {{ msg_sender: {} block_number: {} timestamp: {} epoch_index: {} input_index: {} length: {} }}",
+                    metadata.msg_sender,
+                    metadata.block_number,
+                    metadata.timestamp,
+                    metadata.epoch_index,
+                    metadata.input_index,
+                    payload.len()
+                );
+                log::trace!("advance payload: 0x{}", hex::encode(payload));
+            }
+            Ok(RollupsRequest::InspectState { payload }) => {
+                log::info!("inspect: {{ length: {} }}", payload.len());
+                log::trace!("inspect payload: 0x{}", hex::encode(payload));
+            }
+            Err(RollupsError::QueueExhausted(_)) => log::info!("no more requests"),
+            Err(error) => log::error!("submit failed: {error}"),
+        }
+
+        result
+    }
+
+    fn throw_exception(&self, payload: &[u8]) -> Result<(), RollupsError> {
+        let result = self.machine.throw_exception(payload);
+
+        log_output("exception", format_args!(""), payload, result.as_ref().map(|_| None));
+
+        result
+    }
+
+    fn dapp_address(&self) -> Option<Address> {
+        self.machine.dapp_address()
+    }
+}
+
+/// Counters and timings collected by a [`MetricsLayer`].
+///
+/// The handling time of a request lasts from [`submit`] returning it to [`submit`] being called again.
+///
+/// [`submit`]: MachineIo::submit
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Metrics {
+    pub advances: u64,
+    pub inspects: u64,
+    /// Number of handled advance state requests finished with [`FinishStatus::Accept`].
+    pub accepted: u64,
+    /// Number of handled advance state requests finished with [`FinishStatus::Reject`].
+    pub rejected: u64,
+    pub notices: u64,
+    pub vouchers: u64,
+    pub reports: u64,
+    pub exceptions: u64,
+    /// Total handling time of the advance state requests.
+    pub advance_time: Duration,
+    /// Total handling time of the inspect state requests.
+    pub inspect_time: Duration,
+    /// Input index and handling time of the slowest advance state request.
+    pub slowest_advance: Option<(u64, Duration)>,
+}
+
+/// Collects [`Metrics`] of the machine, readable through any clone of the layer.
+///
+/// The handling time of every input is also logged at debug level.
+#[derive(Debug, Clone, Default)]
+pub struct MetricsLayer {
+    metrics: Rc<RefCell<Metrics>>,
+}
+
+impl MetricsLayer {
+    /// Creates a layer collecting into `metrics`.
+    pub fn new(metrics: Rc<RefCell<Metrics>>) -> Self {
+        Self { metrics }
+    }
+
+    /// Returns the metrics collected so far.
+    pub fn metrics(&self) -> Metrics {
+        self.metrics.borrow().clone()
+    }
+}
+
+impl<M: MachineIo> Layer<M> for MetricsLayer {
+    type Machine = MetricsMachine<M>;
+
+    fn layer(self, machine: M) -> Self::Machine {
+        MetricsMachine {
+            machine,
+            metrics: self.metrics,
+            current: Cell::new(None),
+        }
+    }
+}
+
+/// [`MachineIo`] of a [`MetricsLayer`].
+#[derive(Debug)]
+pub struct MetricsMachine<M> {
+    machine: M,
+    metrics: Rc<RefCell<Metrics>>,
+    /// Input index of the current request, if it advances the state, and when it was returned.
+    current: Cell<Option<(Option<u64>, Instant)>>,
+}
+
+impl<M> MetricsMachine<M> {
+    pub fn into_inner(self) -> M {
+        self.machine
+    }
+
+    /// Counts the output of `result` with `counter`.
+    fn count<T>(
+        &self,
+        result: Result<T, RollupsError>,
+        counter: fn(&mut Metrics) -> &mut u64,
+    ) -> Result<T, RollupsError> {
+        if result.is_ok() {
+            *counter(&mut self.metrics.borrow_mut()) += 1;
+        }
+
+        result
+    }
+
+    /// Accounts the handling time of the current request, finished with `status`.
+    fn finish(&self, status: FinishStatus) {
+        let Some((input_index, start)) = self.current.take() else {
+            return;
+        };
+        let elapsed = start.elapsed();
+        let mut metrics = self.metrics.borrow_mut();
+
+        match input_index {
+            Some(input_index) => {
+                log::debug!("advance {input_index} handled in {elapsed:?} with {status:?}");
+
+                metrics.advance_time += elapsed;
+                match status {
+                    FinishStatus::Accept => metrics.accepted += 1,
+                    FinishStatus::Reject => metrics.rejected += 1,
+                }
+                if metrics.slowest_advance.is_none_or(|(_, slowest)| elapsed > slowest) {
+                    metrics.slowest_advance = Some((input_index, elapsed));
+                }
+            }
+            None => {
+                log::debug!("inspect handled in {elapsed:?}");
+
+                metrics.inspect_time += elapsed;
+            }
+        }
+    }
+}
+
+impl<M: MachineIo> MachineIo for MetricsMachine<M> {
+    fn write_notice(&self, payload: &[u8]) -> Result<usize, RollupsError> {
+        self.count(self.machine.write_notice(payload), |metrics| &mut metrics.notices)
+    }
+
+    fn write_voucher(&self, address: &Address, payload: &[u8]) -> Result<usize, RollupsError> {
+        self.count(self.machine.write_voucher(address, payload), |metrics| {
+            &mut metrics.vouchers
+        })
+    }
+
+    fn write_voucher_with_value(&self, address: &Address, value: &U256, payload: &[u8]) -> Result<usize, RollupsError> {
+        self.count(
+            self.machine.write_voucher_with_value(address, value, payload),
+            |metrics| &mut metrics.vouchers,
+        )
+    }
+
+    fn write_report(&self, payload: &[u8]) -> Result<(), RollupsError> {
+        self.count(self.machine.write_report(payload), |metrics| &mut metrics.reports)
+    }
+
+    fn submit(&self, status: FinishStatus) -> Result<RollupsRequest, RollupsError> {
+        self.finish(status);
+
+        let request = self.machine.submit(status)?;
+        let input_index = match &request {
+            RollupsRequest::AdvanceState { metadata, .. } => {
+                self.metrics.borrow_mut().advances += 1;
+                Some(metadata.input_index)
+            }
+            RollupsRequest::InspectState { .. } => {
+                self.metrics.borrow_mut().inspects += 1;
+                None
+            }
+        };
+
+        self.current.set(Some((input_index, Instant::now())));
+
+        Ok(request)
+    }
+
+    fn throw_exception(&self, payload: &[u8]) -> Result<(), RollupsError> {
+        self.count(self.machine.throw_exception(payload), |metrics| &mut metrics.exceptions)
+    }
+
+    fn dapp_address(&self) -> Option<Address> {
+        self.machine.dapp_address()
+    }
+}
+
+/// Rejects the advance state requests of senders outside of an allow-list, before they reach the DApp.
+///
+/// Deposits are sent by the portals and the address of the DApp by the `DAppAddressRelay`, so allow their addresses
+/// to receive them. Inspect state requests are always handed over.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SenderAllowListLayer {
+    senders: HashSet<Address>,
+}
+
+impl SenderAllowListLayer {
+    /// Creates a layer allowing the advance state requests of `senders`.
+    pub fn new(senders: impl IntoIterator<Item = Address>) -> Self {
+        Self {
+            senders: senders.into_iter().collect(),
+        }
+    }
+
+    pub fn allow(mut self, sender: Address) -> Self {
+        self.senders.insert(sender);
+        self
+    }
+}
+
+impl<M: MachineIo> Layer<M> for SenderAllowListLayer {
+    type Machine = SenderAllowListMachine<M>;
+
+    fn layer(self, machine: M) -> Self::Machine {
+        SenderAllowListMachine {
+            machine,
+            senders: self.senders,
+        }
+    }
+}
+
+/// [`MachineIo`] of a [`SenderAllowListLayer`].
+#[derive(Debug)]
+pub struct SenderAllowListMachine<M> {
+    machine: M,
+    senders: HashSet<Address>,
+}
+
+impl<M> SenderAllowListMachine<M> {
+    pub fn into_inner(self) -> M {
+        self.machine
+    }
+}
+
+impl<M: MachineIo> MachineIo for SenderAllowListMachine<M> {
+    fn write_notice(&self, payload: &[u8]) -> Result<usize, RollupsError> {
+        self.machine.write_notice(payload)
+    }
+
+    fn write_voucher(&self, address: &Address, payload: &[u8]) -> Result<usize, RollupsError> {
+        self.machine.write_voucher(address, payload)
+    }
+
+    fn write_voucher_with_value(&self, address: &Address, value: &U256, payload: &[u8]) -> Result<usize, RollupsError> {
+        self.machine.write_voucher_with_value(address, value, payload)
+    }
+
+    fn write_report(&self, payload: &[u8]) -> Result<(), RollupsError> {
+        self.machine.write_report(payload)
+    }
+
+    fn submit(&self, status: FinishStatus) -> Result<RollupsRequest, RollupsError> {
+        let mut status = status;
+
+        loop {
+            match self.machine.submit(status)? {
+                RollupsRequest::AdvanceState { metadata, .. } if !self.senders.contains(&metadata.msg_sender) => {
+                    log::warn!(
+                        "rejecting input {} of sender {} outside of the allow-list",
+                        metadata.input_index,
+                        metadata.msg_sender
+                    );
+                    status = FinishStatus::Reject;
+                }
+                request => return Ok(request),
+            }
+        }
+    }
+
+    fn throw_exception(&self, payload: &[u8]) -> Result<(), RollupsError> {
+        self.machine.throw_exception(payload)
+    }
+
+    fn dapp_address(&self) -> Option<Address> {
+        self.machine.dapp_address()
+    }
+}
@@ -1,3 +1,20 @@
+mod codec;
+mod dapp;
+mod error;
+mod journal;
+mod layer;
+mod relay;
 mod rollups;
+mod router;
+mod session;
 
+pub use cartesi_rollups_evm_utils::{Address, VoucherBuilder, U256};
+pub use codec::*;
+pub use dapp::*;
+pub use error::*;
+pub use journal::*;
+pub use layer::*;
+pub use relay::*;
 pub use rollups::*;
+pub use router::*;
+pub use session::*;
@@ -0,0 +1,133 @@
+//! Items in this module learn the address of the DApp contract from the input of the `DAppAddressRelay`.
+//!
+//! The DApp does not know its own address on the base layer until someone relays it, yet vouchers withdrawing Ether
+//! are sent to it. Wrap the machine in [`DAppAddressMachine`] and build such vouchers with the [`VoucherBuilder`] it
+//! returns once the relay input arrived.
+//!
+//! # Examples
+//!
+//! ```
+//! # use std::error::Error;
+//! # use cartesi_rollups::{Context, DApp, FinishStatus, RollupsMetadata, U256};
+//! struct Faucet;
+//!
+//! impl DApp for Faucet {
+//!     fn advance(
+//!         &mut self,
+//!         ctx: &Context,
+//!         metadata: RollupsMetadata,
+//!         _payload: Vec<u8>,
+//!     ) -> Result<FinishStatus, Box<dyn Error>> {
+//!         // Rejects every input until the relay input arrived.
+//!         let (destination, payload) = ctx.voucher_builder()?.ether_withdrawal(&metadata.msg_sender, U256::ONE);
+//!         ctx.write_voucher(&destination, &payload)?;
+//!
+//!         Ok(FinishStatus::Accept)
+//!     }
+//!
+//!     fn inspect(&self, _ctx: &Context, _payload: Vec<u8>) -> Result<(), Box<dyn Error>> {
+//!         Ok(())
+//!     }
+//! }
+//! # pub fn start(machine: impl cartesi_rollups::MachineIo) -> Result<(), cartesi_rollups::RollupsError> {
+//! cartesi_rollups::run(Faucet, cartesi_rollups::DAppAddressMachine::new(machine))
+//! # }
+//! ```
+use crate::{Address, FinishStatus, MachineIo, RollupsError, RollupsRequest, VoucherBuilder, U256};
+use cartesi_rollups_evm_utils::portals::Portals;
+use std::cell::Cell;
+use thiserror::Error;
+
+/// The address of the DApp contract has not been relayed yet.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("DApp address is unknown until the DAppAddressRelay input arrives")]
+pub struct UnknownDAppAddress;
+
+/// [`MachineIo`] remembering the address of the DApp contract from the input of the `DAppAddressRelay`.
+///
+/// The relay input is still handed over to the caller of [`submit`], so it can be accepted like any other input. A
+/// relay input that does not hold an address is logged and handed over unchanged as well, for the DApp to report it,
+/// e.g. with the error of [`Portals::parse_dapp_address`].
+///
+/// See the [module-level documentation](./index.html) for more details.
+///
+/// [`submit`]: MachineIo::submit
+#[derive(Debug)]
+pub struct DAppAddressMachine<M> {
+    machine: M,
+    portals: Portals,
+    dapp_address: Cell<Option<Address>>,
+}
+
+impl<M: MachineIo> DAppAddressMachine<M> {
+    /// Wraps `machine` expecting the relay input from the default [`Portals`].
+    pub fn new(machine: M) -> Self {
+        Self::with_portals(machine, Portals::default())
+    }
+
+    /// Wraps `machine` expecting the relay input from [`Portals::dapp_address_relay`] of `portals`.
+    pub fn with_portals(machine: M, portals: Portals) -> Self {
+        Self {
+            machine,
+            portals,
+            dapp_address: Cell::new(None),
+        }
+    }
+
+    /// Returns the builder of the vouchers of the DApp, or an error until the relay input arrives.
+    pub fn voucher_builder(&self) -> Result<VoucherBuilder, UnknownDAppAddress> {
+        voucher_builder(self)
+    }
+
+    pub fn into_inner(self) -> M {
+        self.machine
+    }
+}
+
+impl<M: MachineIo> MachineIo for DAppAddressMachine<M> {
+    fn write_notice(&self, payload: &[u8]) -> Result<usize, RollupsError> {
+        self.machine.write_notice(payload)
+    }
+
+    fn write_voucher(&self, address: &Address, payload: &[u8]) -> Result<usize, RollupsError> {
+        self.machine.write_voucher(address, payload)
+    }
+
+    fn write_voucher_with_value(&self, address: &Address, value: &U256, payload: &[u8]) -> Result<usize, RollupsError> {
+        self.machine.write_voucher_with_value(address, value, payload)
+    }
+
+    fn write_report(&self, payload: &[u8]) -> Result<(), RollupsError> {
+        self.machine.write_report(payload)
+    }
+
+    fn submit(&self, status: FinishStatus) -> Result<RollupsRequest, RollupsError> {
+        let request = self.machine.submit(status)?;
+
+        if let RollupsRequest::AdvanceState { metadata, payload } = &request {
+            match self.portals.parse_dapp_address(&metadata.msg_sender, payload) {
+                Ok(Some(dapp_address)) => self.dapp_address.set(Some(dapp_address)),
+                Ok(None) => {}
+                Err(error) => log::warn!("ignoring malformed DApp address relay input: {error}"),
+            }
+        }
+
+        Ok(request)
+    }
+
+    fn throw_exception(&self, payload: &[u8]) -> Result<(), RollupsError> {
+        self.machine.throw_exception(payload)
+    }
+
+    fn dapp_address(&self) -> Option<Address> {
+        self.dapp_address.get().or_else(|| self.machine.dapp_address())
+    }
+}
+
+/// Returns the builder of the vouchers of the DApp whose address `machine` knows.
+pub(crate) fn voucher_builder(machine: &(impl MachineIo + ?Sized)) -> Result<VoucherBuilder, UnknownDAppAddress> {
+    machine
+        .dapp_address()
+        .map(VoucherBuilder::new)
+        .ok_or(UnknownDAppAddress)
+}
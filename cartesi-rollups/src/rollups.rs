@@ -1,47 +1,257 @@
 //! Items in this module define the Cartesi Rollup communication device abstraction.
-use std::error::Error;
+//!
+//! Requests serialize to the JSON of `rollup-http-server`, tagged with their `type`, with payloads as `0x`-prefixed hex
+//! strings:
+//!
+//! ```json
+//! {"type":"inspect_state","payload":"0x717565"}
+//! ```
+use crate::{Address, RollupsError, U256};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 /// Request sent from the rollups server.
 ///
 /// For example, the rollups server received some inputs and now wants the dapp to advance state.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum RollupsRequest {
     /// The handler should respond to this request by advancing the state of the dapp using the `payload` and `metadata`.
     AdvanceState {
         metadata: RollupsMetadata,
+        #[serde(with = "hex_bytes")]
         payload: Vec<u8>,
     },
     /// The handler should respond to this request by inspecting current state of the dapp using the `payload` and not
     /// advance the state.
-    InspectState { payload: Vec<u8> },
+    InspectState {
+        #[serde(with = "hex_bytes")]
+        payload: Vec<u8>,
+    },
 }
 
 /// Metadata exactly describing the input order accompanying the [`RollupsRequest`].
-#[derive(Clone, Debug)]
+///
+/// Serializes with the fields of [`MetadataVersion::V2`] next to the others, when present.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "MetadataFields", into = "MetadataFields")]
 pub struct RollupsMetadata {
-    pub msg_sender: String,
+    pub msg_sender: Address,
+    /// Always zero with [`MetadataVersion::V2`], which has no epochs.
     pub epoch_index: u64,
     pub input_index: u64,
     pub block_number: u64,
     pub timestamp: u64,
+    pub version: MetadataVersion,
+}
+
+impl RollupsMetadata {
+    /// Returns the ID of the chain of the base layer, known since [`MetadataVersion::V2`].
+    pub fn chain_id(&self) -> Option<u64> {
+        match &self.version {
+            MetadataVersion::V1 => None,
+            MetadataVersion::V2 { chain_id, .. } => Some(*chain_id),
+        }
+    }
+
+    /// Returns the address of the application contract, known since [`MetadataVersion::V2`].
+    pub fn app_contract(&self) -> Option<Address> {
+        match &self.version {
+            MetadataVersion::V1 => None,
+            MetadataVersion::V2 { app_contract, .. } => Some(*app_contract),
+        }
+    }
+
+    /// Returns the `block.prevrandao` of the block of the input, known since [`MetadataVersion::V2`].
+    pub fn prev_randao(&self) -> Option<U256> {
+        match &self.version {
+            MetadataVersion::V1 => None,
+            MetadataVersion::V2 { prev_randao, .. } => Some(*prev_randao),
+        }
+    }
+}
+
+/// Generation of the machine interface the [`RollupsMetadata`] comes from, with the fields only it has.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum MetadataVersion {
+    /// The `/dev/rollup` interface of the 0.x kernels.
+    #[default]
+    V1,
+    /// The cmio interface of Cartesi Rollups v2, the one of libcmt.
+    V2 {
+        chain_id: u64,
+        app_contract: Address,
+        prev_randao: U256,
+    },
+}
+
+/// Serialized form of [`RollupsMetadata`], the one of `rollup-http-server` of either generation.
+#[derive(Clone, Serialize, Deserialize)]
+struct MetadataFields {
+    msg_sender: Address,
+    #[serde(default)]
+    epoch_index: u64,
+    input_index: u64,
+    block_number: u64,
+    #[serde(alias = "block_timestamp")]
+    timestamp: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    chain_id: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    app_contract: Option<Address>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    prev_randao: Option<U256>,
+}
+
+impl From<MetadataFields> for RollupsMetadata {
+    fn from(fields: MetadataFields) -> Self {
+        let version = match (fields.chain_id, fields.app_contract, fields.prev_randao) {
+            (Some(chain_id), Some(app_contract), Some(prev_randao)) => MetadataVersion::V2 {
+                chain_id,
+                app_contract,
+                prev_randao,
+            },
+            _ => MetadataVersion::V1,
+        };
+
+        Self {
+            msg_sender: fields.msg_sender,
+            epoch_index: fields.epoch_index,
+            input_index: fields.input_index,
+            block_number: fields.block_number,
+            timestamp: fields.timestamp,
+            version,
+        }
+    }
+}
+
+impl From<RollupsMetadata> for MetadataFields {
+    fn from(metadata: RollupsMetadata) -> Self {
+        let (chain_id, app_contract, prev_randao) = match metadata.version {
+            MetadataVersion::V1 => (None, None, None),
+            MetadataVersion::V2 {
+                chain_id,
+                app_contract,
+                prev_randao,
+            } => (Some(chain_id), Some(app_contract), Some(prev_randao)),
+        };
+
+        Self {
+            msg_sender: metadata.msg_sender,
+            epoch_index: metadata.epoch_index,
+            input_index: metadata.input_index,
+            block_number: metadata.block_number,
+            timestamp: metadata.timestamp,
+            chain_id,
+            app_contract,
+            prev_randao,
+        }
+    }
+}
+
+/// Decides the fate of the previous [`RollupsRequest`] when [`submitting`].
+///
+/// Rejecting an advance state request discards every notice and voucher written while handling it. Reports are kept
+/// regardless of the status.
+///
+/// [`submitting`]: MachineIo::submit
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FinishStatus {
+    /// Keeps the outputs of the previous request.
+    #[default]
+    Accept,
+    /// Rolls back the outputs of the previous request.
+    Reject,
+}
+
+/// Output written through a [`MachineIo`], or the status a request finished with, serialized as a JSON object tagged
+/// by its `type`.
+///
+/// Notices and vouchers carry the index the machine returned for them.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    Notice {
+        index: usize,
+        #[serde(with = "hex_bytes")]
+        payload: Vec<u8>,
+    },
+    Voucher {
+        index: usize,
+        destination: Address,
+        /// Wei sent along with the call, left out when zero.
+        #[serde(default, skip_serializing_if = "U256::is_zero")]
+        value: U256,
+        #[serde(with = "hex_bytes")]
+        payload: Vec<u8>,
+    },
+    Report {
+        #[serde(with = "hex_bytes")]
+        payload: Vec<u8>,
+    },
+    Exception {
+        #[serde(with = "hex_bytes")]
+        payload: Vec<u8>,
+    },
+    /// The previous request finished with `status`, before the next one is retrieved.
+    Finish { status: FinishStatus },
 }
 
 /// The implementor of this trait handles communication with the rollup device.
 pub trait MachineIo {
     /// Writes a notice with `payload`.
-    fn write_notice(&self, payload: &[u8]) -> Result<usize, Box<dyn Error>>;
+    fn write_notice(&self, payload: &[u8]) -> Result<usize, RollupsError>;
 
     /// Writes a voucher with `payload` for `address`.
-    fn write_voucher(&self, address: &[u8; 20], payload: &[u8]) -> Result<usize, Box<dyn Error>>;
+    fn write_voucher(&self, address: &Address, payload: &[u8]) -> Result<usize, RollupsError>;
+
+    /// Writes a voucher with `payload` for `address`, sending `value` Wei along with the call.
+    ///
+    /// Only vouchers of Cartesi Rollups v2 carry a value, so by default a voucher without value is written with
+    /// [`write_voucher`] and any other fails.
+    ///
+    /// [`write_voucher`]: MachineIo::write_voucher
+    fn write_voucher_with_value(&self, address: &Address, value: &U256, payload: &[u8]) -> Result<usize, RollupsError> {
+        match value.is_zero() {
+            true => self.write_voucher(address, payload),
+            false => Err(RollupsError::Other(
+                "vouchers with value need the cmio interface".into(),
+            )),
+        }
+    }
 
     /// Writes a report with `payload`.
-    fn write_report(&self, payload: &[u8]) -> Result<(), Box<dyn Error>>;
+    fn write_report(&self, payload: &[u8]) -> Result<(), RollupsError>;
 
-    /// Submits all writes since the last submit and retrieves next [`RollupsRequest`] to handle.
+    /// Finishes the previous request with `status` and retrieves next [`RollupsRequest`] to handle.
     ///
-    /// Blocks the current thread. Needs to be called before the first write call.
-    fn submit(&self) -> Result<RollupsRequest, Box<dyn Error>>;
+    /// Blocks the current thread. Needs to be called before the first write call, in which case `status` is ignored.
+    fn submit(&self, status: FinishStatus) -> Result<RollupsRequest, RollupsError>;
 
     /// Rolls-back the entire machine and writes exception with `payload`.
-    fn throw_exception(&self, payload: &[u8]) -> Result<(), Box<dyn Error>>;
+    fn throw_exception(&self, payload: &[u8]) -> Result<(), RollupsError>;
+
+    /// Returns the address of the DApp contract, if known.
+    ///
+    /// The address is not known by default. See [`DAppAddressMachine`] for learning it from the relay input.
+    ///
+    /// [`DAppAddressMachine`]: crate::DAppAddressMachine
+    fn dapp_address(&self) -> Option<Address> {
+        None
+    }
+}
+
+/// Serializes bytes as a `0x`-prefixed hex string.
+pub mod hex_bytes {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&format_args!("0x{}", hex::encode(bytes)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let string = String::deserialize(deserializer)?;
+
+        hex::decode(string.strip_prefix("0x").unwrap_or(&string)).map_err(serde::de::Error::custom)
+    }
 }
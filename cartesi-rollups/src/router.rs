@@ -0,0 +1,266 @@
+//! Items in this module dispatch requests to handlers by route instead of matching payloads by hand.
+//!
+//! A [`Router`] is a [`DApp`] holding the state of the DApp and its handlers:
+//!
+//! - Advance state payloads are routed by their 4-byte ABI selector, see [`Router::selector`], or by the `"method"`
+//!   of their JSON object, see [`Router::method`]. Handlers receive the whole payload.
+//! - Inspect state payloads are routed by their URL-style path, e.g. `/balance/0xabc?token=0xdef`, which is how inspect
+//!   requests arrive over HTTP, see [`Router::inspect`].
+//!
+//! Requests without a route fail with a [`RouteError`], which [`run`] writes as a report before rejecting the request.
+//!
+//! # Examples
+//!
+//! ```
+//! # use cartesi_rollups::{FinishStatus, Router};
+//! # use cartesi_rollups_evm_utils::abi::selector;
+//! let router = Router::new(0u64)
+//!     .selector(selector("increment()"), |counter, _ctx, _metadata, _payload| {
+//!         *counter += 1;
+//!         Ok(FinishStatus::Accept)
+//!     })
+//!     .method("reset", |counter, _ctx, _metadata, _payload| {
+//!         *counter = 0;
+//!         Ok(FinishStatus::Accept)
+//!     })
+//!     .inspect("/counter", |counter, ctx, _route| {
+//!         ctx.write_report(counter.to_string().as_bytes())?;
+//!         Ok(())
+//!     });
+//! # let _ = router;
+//! ```
+//!
+//! [`run`]: crate::run
+use crate::{Context, DApp, FinishStatus, RollupsMetadata};
+use serde::Deserialize;
+use std::error::Error;
+use thiserror::Error;
+
+type AdvanceHandler<S> = Box<dyn Fn(&mut S, &Context, &RollupsMetadata, &[u8]) -> Result<FinishStatus, Box<dyn Error>>>;
+type InspectHandler<S> = Box<dyn Fn(&S, &Context, &Route) -> Result<(), Box<dyn Error>>>;
+
+/// Defines errors of a [`Router`] without a route for a request.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum RouteError {
+    #[error("unknown route: selector 0x{}", hex::encode(.0))]
+    UnknownSelector([u8; 4]),
+    #[error("unknown route: method {0:?}")]
+    UnknownMethod(String),
+    #[error("unknown route: path {0:?}")]
+    UnknownPath(String),
+    /// The payload has neither a known selector nor a method, e.g. a JSON object without `"method"`.
+    #[error("unknown route: payload of {0} bytes")]
+    Unroutable(usize),
+}
+
+/// Inspect state request matched by a path pattern of a [`Router`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Route {
+    path: String,
+    params: Vec<(String, String)>,
+    query: Vec<(String, String)>,
+}
+
+impl Route {
+    /// Returns the path of the request, without its query.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns the segment matched by `:name` in the pattern.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        find(&self.params, name)
+    }
+
+    /// Returns the first value of `name` in the query.
+    pub fn query(&self, name: &str) -> Option<&str> {
+        find(&self.query, name)
+    }
+
+    /// Matches `path` against `pattern`, whose segments starting with `:` match any segment.
+    fn matches(pattern: &str, path: &str, query: &[(String, String)]) -> Option<Self> {
+        let mut patterns = segments(pattern);
+        let mut segments = segments(path);
+        let mut params = Vec::new();
+
+        loop {
+            match (patterns.next(), segments.next()) {
+                (None, None) => break,
+                (Some(pattern), Some(segment)) => match pattern.strip_prefix(':') {
+                    Some(name) => params.push((name.to_owned(), percent_decode(segment))),
+                    None if pattern == segment => {}
+                    None => return None,
+                },
+                _ => return None,
+            }
+        }
+
+        Some(Self {
+            path: path.to_owned(),
+            params,
+            query: query.to_vec(),
+        })
+    }
+}
+
+/// [`DApp`] dispatching every request to the handler of its route.
+///
+/// Handlers get the state of the router, mutable when advancing it. Routes are tried in the order they were added.
+///
+/// See the [module-level documentation](./index.html) for more details.
+pub struct Router<S> {
+    state: S,
+    selectors: Vec<([u8; 4], AdvanceHandler<S>)>,
+    methods: Vec<(String, AdvanceHandler<S>)>,
+    paths: Vec<(String, InspectHandler<S>)>,
+}
+
+impl<S> Router<S> {
+    pub fn new(state: S) -> Self {
+        Self {
+            state,
+            selectors: Vec::new(),
+            methods: Vec::new(),
+            paths: Vec::new(),
+        }
+    }
+
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+
+    pub fn into_state(self) -> S {
+        self.state
+    }
+
+    /// Routes advance state payloads starting with `selector` to `handler`.
+    pub fn selector(
+        mut self,
+        selector: [u8; 4],
+        handler: impl Fn(&mut S, &Context, &RollupsMetadata, &[u8]) -> Result<FinishStatus, Box<dyn Error>> + 'static,
+    ) -> Self {
+        self.selectors.push((selector, Box::new(handler)));
+        self
+    }
+
+    /// Routes advance state payloads of a JSON object with `"method": method` to `handler`.
+    pub fn method(
+        mut self,
+        method: impl Into<String>,
+        handler: impl Fn(&mut S, &Context, &RollupsMetadata, &[u8]) -> Result<FinishStatus, Box<dyn Error>> + 'static,
+    ) -> Self {
+        self.methods.push((method.into(), Box::new(handler)));
+        self
+    }
+
+    /// Routes inspect state payloads whose path matches `pattern` to `handler`.
+    ///
+    /// Segments of `pattern` starting with `:` match any segment, readable with [`Route::param`], e.g.
+    /// `/balance/:address`. The leading slash is optional in both the pattern and the payload.
+    pub fn inspect(
+        mut self,
+        pattern: impl Into<String>,
+        handler: impl Fn(&S, &Context, &Route) -> Result<(), Box<dyn Error>> + 'static,
+    ) -> Self {
+        self.paths.push((pattern.into(), Box::new(handler)));
+        self
+    }
+}
+
+/// JSON object of an advance state payload routed by method.
+#[derive(Deserialize)]
+struct Method {
+    method: Option<String>,
+}
+
+impl<S> DApp for Router<S> {
+    fn advance(
+        &mut self,
+        ctx: &Context,
+        metadata: RollupsMetadata,
+        payload: Vec<u8>,
+    ) -> Result<FinishStatus, Box<dyn Error>> {
+        if let Some(selector) = payload.get(..4) {
+            if let Some((_, handler)) = self.selectors.iter().find(|(known, _)| known == selector) {
+                return handler(&mut self.state, ctx, &metadata, &payload);
+            }
+        }
+
+        let error = match serde_json::from_slice::<Method>(&payload) {
+            Ok(Method { method: Some(method) }) => match self.methods.iter().find(|(known, _)| *known == method) {
+                Some((_, handler)) => return handler(&mut self.state, ctx, &metadata, &payload),
+                None => RouteError::UnknownMethod(method),
+            },
+            Ok(Method { method: None }) => RouteError::Unroutable(payload.len()),
+            Err(_) => match payload.get(..4) {
+                Some(selector) => RouteError::UnknownSelector(selector.try_into().unwrap()),
+                None => RouteError::Unroutable(payload.len()),
+            },
+        };
+
+        Err(Box::new(error))
+    }
+
+    fn inspect(&self, ctx: &Context, payload: Vec<u8>) -> Result<(), Box<dyn Error>> {
+        let target = String::from_utf8_lossy(&payload);
+        let (path, query) = target.split_once('?').unwrap_or((&target, ""));
+        let query: Vec<_> = query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+                (
+                    percent_decode(&name.replace('+', " ")),
+                    percent_decode(&value.replace('+', " ")),
+                )
+            })
+            .collect();
+
+        for (pattern, handler) in &self.paths {
+            if let Some(route) = Route::matches(pattern, path, &query) {
+                return handler(&self.state, ctx, &route);
+            }
+        }
+
+        Err(Box::new(RouteError::UnknownPath(path.to_owned())))
+    }
+}
+
+fn segments(path: &str) -> impl Iterator<Item = &str> {
+    path.trim_start_matches('/')
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+}
+
+fn find<'a>(pairs: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    pairs
+        .iter()
+        .find(|(known, _)| known == name)
+        .map(|(_, value)| value.as_str())
+}
+
+/// Decodes the `%XX` escapes of `text`, keeping malformed escapes as they are.
+///
+/// A `+` stays as it is: only in the query it stands for a space, which the caller replaces before decoding.
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => match bytes.get(i + 1..i + 3).and_then(|hex| hex::decode(hex).ok()) {
+                Some(byte) => {
+                    decoded.extend(byte);
+                    i += 3;
+                    continue;
+                }
+                None => decoded.push(b'%'),
+            },
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
@@ -0,0 +1,176 @@
+//! Items in this module wrap any [`MachineIo`] into a session that enforces its usage rules at compile time.
+//!
+//! [`Session::submit`] hands out a handle for the first request. Notices and vouchers can only be written through an
+//! [`AdvanceRequest`], reports through both handles, and finishing a handle consumes it while retrieving the next one.
+//!
+//! # Examples
+//!
+//! ```
+//! # use cartesi_rollups::{MachineIo, Request, RollupsError, Session};
+//! # pub fn run(machine: impl MachineIo) -> Result<(), RollupsError> {
+//! let mut request = Session::new(machine).submit()?;
+//!
+//! loop {
+//!     request = match request {
+//!         Request::Advance(advance) => {
+//!             advance.write_notice(advance.payload())?;
+//!             advance.accept()?
+//!         }
+//!         Request::Inspect(inspect) => {
+//!             inspect.write_report(inspect.payload())?;
+//!             inspect.accept()?
+//!         }
+//!     };
+//! }
+//! # }
+//! ```
+//!
+//! Writing a notice while inspecting does not compile:
+//!
+//! ```compile_fail
+//! # use cartesi_rollups::{MachineIo, Request, RollupsError, Session};
+//! # pub fn run(machine: impl MachineIo) -> Result<(), RollupsError> {
+//! if let Request::Inspect(inspect) = Session::new(machine).submit()? {
+//!     inspect.write_notice(inspect.payload())?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use crate::{Address, FinishStatus, MachineIo, RollupsError, RollupsMetadata, RollupsRequest, U256};
+
+/// Owns a [`MachineIo`] until the first request is retrieved.
+///
+/// See the [module-level documentation](./index.html) for more details.
+#[derive(Debug)]
+pub struct Session<M> {
+    machine: M,
+}
+
+impl<M: MachineIo> Session<M> {
+    pub fn new(machine: M) -> Self {
+        Self { machine }
+    }
+
+    /// Retrieves the first request to handle.
+    ///
+    /// Blocks the current thread.
+    pub fn submit(self) -> Result<Request<M>, RollupsError> {
+        Request::next(self.machine, FinishStatus::Accept)
+    }
+}
+
+/// Handle of the request currently being handled.
+#[derive(Debug)]
+pub enum Request<M> {
+    Advance(AdvanceRequest<M>),
+    Inspect(InspectRequest<M>),
+}
+
+impl<M: MachineIo> Request<M> {
+    fn next(machine: M, status: FinishStatus) -> Result<Self, RollupsError> {
+        Ok(match machine.submit(status)? {
+            RollupsRequest::AdvanceState { metadata, payload } => Request::Advance(AdvanceRequest {
+                machine,
+                metadata,
+                payload,
+            }),
+            RollupsRequest::InspectState { payload } => Request::Inspect(InspectRequest { machine, payload }),
+        })
+    }
+}
+
+/// Handle of an advance state request, allowed to write every kind of output.
+#[derive(Debug)]
+pub struct AdvanceRequest<M> {
+    machine: M,
+    metadata: RollupsMetadata,
+    payload: Vec<u8>,
+}
+
+impl<M: MachineIo> AdvanceRequest<M> {
+    pub fn metadata(&self) -> &RollupsMetadata {
+        &self.metadata
+    }
+
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    /// Writes a notice with `payload`.
+    pub fn write_notice(&self, payload: &[u8]) -> Result<usize, RollupsError> {
+        self.machine.write_notice(payload)
+    }
+
+    /// Writes a voucher with `payload` for `address`.
+    pub fn write_voucher(&self, address: &Address, payload: &[u8]) -> Result<usize, RollupsError> {
+        self.machine.write_voucher(address, payload)
+    }
+
+    /// Writes a voucher with `payload` for `address` sending `value` Wei, see [`MachineIo::write_voucher_with_value`].
+    pub fn write_voucher_with_value(
+        &self,
+        address: &Address,
+        value: &U256,
+        payload: &[u8],
+    ) -> Result<usize, RollupsError> {
+        self.machine.write_voucher_with_value(address, value, payload)
+    }
+
+    /// Writes a report with `payload`.
+    pub fn write_report(&self, payload: &[u8]) -> Result<(), RollupsError> {
+        self.machine.write_report(payload)
+    }
+
+    /// Rolls-back the entire machine and writes exception with `payload`.
+    pub fn throw_exception(self, payload: &[u8]) -> Result<(), RollupsError> {
+        self.machine.throw_exception(payload)
+    }
+
+    /// Finishes this request with `status` and retrieves the next one.
+    pub fn finish(self, status: FinishStatus) -> Result<Request<M>, RollupsError> {
+        Request::next(self.machine, status)
+    }
+
+    /// Keeps the outputs of this request and retrieves the next one.
+    pub fn accept(self) -> Result<Request<M>, RollupsError> {
+        self.finish(FinishStatus::Accept)
+    }
+
+    /// Discards the notices and vouchers of this request and retrieves the next one.
+    pub fn reject(self) -> Result<Request<M>, RollupsError> {
+        self.finish(FinishStatus::Reject)
+    }
+}
+
+/// Handle of an inspect state request, allowed to write reports only.
+#[derive(Debug)]
+pub struct InspectRequest<M> {
+    machine: M,
+    payload: Vec<u8>,
+}
+
+impl<M: MachineIo> InspectRequest<M> {
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    /// Writes a report with `payload`.
+    pub fn write_report(&self, payload: &[u8]) -> Result<(), RollupsError> {
+        self.machine.write_report(payload)
+    }
+
+    /// Finishes this request with `status` and retrieves the next one.
+    pub fn finish(self, status: FinishStatus) -> Result<Request<M>, RollupsError> {
+        Request::next(self.machine, status)
+    }
+
+    /// Marks this request as accepted and retrieves the next one.
+    pub fn accept(self) -> Result<Request<M>, RollupsError> {
+        self.finish(FinishStatus::Accept)
+    }
+
+    /// Marks this request as rejected and retrieves the next one.
+    pub fn reject(self) -> Result<Request<M>, RollupsError> {
+        self.finish(FinishStatus::Reject)
+    }
+}
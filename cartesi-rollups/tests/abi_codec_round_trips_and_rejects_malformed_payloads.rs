@@ -0,0 +1,71 @@
+use cartesi_rollups::{
+    Abi, Address, Decoder, Encoder, FinishStatus, MetadataVersion, RollupsError, RollupsMetadata, RollupsRequest,
+    TypedMachineIo, TypedRequest, U256,
+};
+use cartesi_rollups_test::{Data, FakeCartesiMachine};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[test]
+fn test_abi_codec_round_trips_and_rejects_malformed_payloads() {
+    let amounts = vec![U256::from(2u8), U256::from(3u8)];
+    let owner = Address::new([0xa1; 20]);
+    let payload = Abi.encode(&amounts).unwrap();
+
+    assert_eq!(amounts, Decoder::<Vec<U256>>::decode(&Abi, &payload).unwrap());
+    assert_eq!(owner, Abi.decode(&Abi.encode(&owner).unwrap()).unwrap());
+    assert!(Decoder::<Vec<U256>>::decode(&Abi, &payload[..payload.len() - 1]).is_err());
+    assert!(Decoder::<Address>::decode(&Abi, &[0xff; 32]).is_err());
+
+    let mut requests: Vec<_> = [payload.clone(), payload[..40].to_vec()]
+        .into_iter()
+        .zip(1..)
+        .map(|(payload, input_index)| RollupsRequest::AdvanceState {
+            metadata: RollupsMetadata {
+                msg_sender: owner,
+                epoch_index: 0,
+                input_index,
+                block_number: 0,
+                timestamp: 0,
+                version: MetadataVersion::V1,
+            },
+            payload,
+        })
+        .collect();
+    requests.push(RollupsRequest::InspectState {
+        payload: Abi.encode(&owner).unwrap(),
+    });
+
+    let actual_data = Rc::new(RefCell::new(Data::default()));
+    let machine = TypedMachineIo::new(FakeCartesiMachine::new(requests, actual_data.clone()), Abi);
+    let mut total = U256::ZERO;
+
+    let error = loop {
+        let request = match machine.submit::<Vec<U256>, Address>(FinishStatus::Accept) {
+            Ok(request) => request,
+            Err(error) => break error,
+        };
+
+        match request {
+            TypedRequest::AdvanceState { payload, .. } => {
+                total = payload
+                    .iter()
+                    .try_fold(total, |total, amount| total.checked_add(*amount))
+                    .unwrap();
+                machine.write_notice(&total).unwrap();
+            }
+            TypedRequest::InspectState { payload } => {
+                assert_eq!(owner, payload);
+                machine.write_report(&total).unwrap();
+            }
+        }
+    };
+
+    let data = actual_data.borrow();
+
+    assert!(matches!(error, RollupsError::QueueExhausted(_)));
+    assert_eq!(vec![Abi.encode(&U256::from(5u8)).unwrap()], data.notices);
+    assert_eq!(2, data.reports.len());
+    assert!(String::from_utf8_lossy(&data.reports[0]).starts_with("failed to decode payload"));
+    assert_eq!(Abi.encode(&U256::from(5u8)).unwrap(), data.reports[1]);
+}
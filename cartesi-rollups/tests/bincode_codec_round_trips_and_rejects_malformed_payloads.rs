@@ -0,0 +1,86 @@
+#![cfg(feature = "bincode")]
+
+use cartesi_rollups::{
+    Address, Bincode, Decoder, Encoder, FinishStatus, MetadataVersion, RollupsError, RollupsMetadata, RollupsRequest,
+    TypedMachineIo, TypedRequest,
+};
+use cartesi_rollups_test::{Data, FakeCartesiMachine};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Deposit {
+    owner: String,
+    amount: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Query {
+    owner: String,
+}
+
+#[test]
+fn test_bincode_codec_round_trips_and_rejects_malformed_payloads() {
+    let deposit = Deposit {
+        owner: "alice".to_owned(),
+        amount: 7,
+    };
+    let payload = Bincode.encode(&deposit).unwrap();
+
+    assert_eq!(deposit, Bincode.decode(&payload).unwrap());
+    assert!(Decoder::<Deposit>::decode(&Bincode, &payload[..payload.len() - 1]).is_err());
+
+    let mut requests: Vec<_> = [payload.clone(), payload[..4].to_vec()]
+        .into_iter()
+        .zip(1..)
+        .map(|(payload, input_index)| RollupsRequest::AdvanceState {
+            metadata: RollupsMetadata {
+                msg_sender: Address::ZERO,
+                epoch_index: 0,
+                input_index,
+                block_number: 0,
+                timestamp: 0,
+                version: MetadataVersion::V1,
+            },
+            payload,
+        })
+        .collect();
+    requests.push(RollupsRequest::InspectState {
+        payload: Bincode
+            .encode(&Query {
+                owner: "alice".to_owned(),
+            })
+            .unwrap(),
+    });
+
+    let actual_data = Rc::new(RefCell::new(Data::default()));
+    let machine = TypedMachineIo::new(FakeCartesiMachine::new(requests, actual_data.clone()), Bincode);
+    let mut total = 0u64;
+
+    let error = loop {
+        let request = match machine.submit::<Deposit, Query>(FinishStatus::Accept) {
+            Ok(request) => request,
+            Err(error) => break error,
+        };
+
+        match request {
+            TypedRequest::AdvanceState { payload, .. } => {
+                total += payload.amount;
+                machine.write_notice(&total).unwrap();
+            }
+            TypedRequest::InspectState { payload } => {
+                assert_eq!("alice", payload.owner);
+                machine.write_report(&total).unwrap();
+            }
+        }
+    };
+
+    let data = actual_data.borrow();
+
+    assert!(matches!(error, RollupsError::QueueExhausted(_)));
+    assert_eq!(vec![Bincode.encode(&7u64).unwrap()], data.notices);
+    assert_eq!(2, data.reports.len());
+    assert!(String::from_utf8_lossy(&data.reports[0]).starts_with("failed to decode payload"));
+    assert_eq!(Bincode.encode(&7u64).unwrap(), data.reports[1]);
+}
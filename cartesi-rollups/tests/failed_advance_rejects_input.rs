@@ -0,0 +1,53 @@
+use cartesi_rollups::{run, Address, Context, DApp, FinishStatus, MetadataVersion, RollupsMetadata, RollupsRequest};
+use cartesi_rollups_test::{Data, FakeCartesiMachine};
+use std::cell::RefCell;
+use std::error::Error;
+use std::rc::Rc;
+
+struct Failing;
+
+impl DApp for Failing {
+    fn advance(
+        &mut self,
+        ctx: &Context,
+        _metadata: RollupsMetadata,
+        payload: Vec<u8>,
+    ) -> Result<FinishStatus, Box<dyn Error>> {
+        ctx.write_notice(&payload)?;
+
+        Err("invalid input".into())
+    }
+
+    fn inspect(&self, _ctx: &Context, _payload: Vec<u8>) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_failed_advance_rejects_input() {
+    let request = RollupsRequest::AdvanceState {
+        metadata: RollupsMetadata {
+            msg_sender: Address::ZERO,
+            epoch_index: 0,
+            input_index: 0,
+            block_number: 0,
+            timestamp: 0,
+            version: MetadataVersion::V1,
+        },
+        payload: vec![1, 2, 3],
+    };
+
+    let actual_data = Rc::new(RefCell::new(Data::default()));
+    let machine = FakeCartesiMachine::new([request], actual_data.clone());
+
+    run(Failing, machine).unwrap_err();
+
+    let expected_data = Rc::new(RefCell::new(Data {
+        notices: vec![],
+        vouchers: vec![],
+        reports: vec![b"invalid input".to_vec()],
+        exceptions: vec![],
+    }));
+
+    assert_eq!(expected_data, actual_data);
+}
@@ -0,0 +1,79 @@
+use cartesi_rollups::{
+    Address, Context, DApp, FinishStatus, LoggingLayer, MachineIoExt, MetadataVersion, MetricsLayer, PayloadLimitLayer,
+    RollupsError, RollupsMetadata, RollupsRequest, SenderAllowListLayer,
+};
+use cartesi_rollups_test::{Data, FakeCartesiMachine};
+use std::cell::RefCell;
+use std::error::Error;
+use std::rc::Rc;
+
+struct Echo;
+
+impl DApp for Echo {
+    fn advance(
+        &mut self,
+        ctx: &Context,
+        _metadata: RollupsMetadata,
+        payload: Vec<u8>,
+    ) -> Result<FinishStatus, Box<dyn Error>> {
+        ctx.write_notice(&payload)?;
+
+        Ok(FinishStatus::Accept)
+    }
+
+    fn inspect(&self, ctx: &Context, payload: Vec<u8>) -> Result<(), Box<dyn Error>> {
+        ctx.write_report(&payload)?;
+
+        Ok(())
+    }
+}
+
+#[test]
+fn test_layered_machine_filters_senders_and_limits_payloads() {
+    let allowed = Address::new([0xa1; 20]);
+    let requests = [
+        (allowed, vec![1]),
+        (Address::new([0xb2; 20]), vec![2]),
+        (allowed, vec![3; 5]),
+    ]
+    .into_iter()
+    .zip(1..)
+    .map(|((msg_sender, payload), input_index)| RollupsRequest::AdvanceState {
+        metadata: RollupsMetadata {
+            msg_sender,
+            epoch_index: 0,
+            input_index,
+            block_number: 0,
+            timestamp: 0,
+            version: MetadataVersion::V1,
+        },
+        payload,
+    });
+    let mut requests: Vec<_> = requests.collect();
+    requests.insert(2, RollupsRequest::InspectState { payload: vec![4] });
+
+    let actual_data = Rc::new(RefCell::new(Data::default()));
+    let metrics = MetricsLayer::default();
+    let machine = FakeCartesiMachine::new(requests, actual_data.clone())
+        .layer(PayloadLimitLayer::new(4))
+        .layer(SenderAllowListLayer::new([allowed]))
+        .layer(LoggingLayer)
+        .layer(metrics.clone());
+
+    let error = cartesi_rollups::run(Echo, machine).unwrap_err();
+
+    let expected_data = Rc::new(RefCell::new(Data {
+        notices: vec![vec![1]],
+        vouchers: vec![],
+        reports: vec![vec![4]],
+        exceptions: vec![b"payload of 5 bytes is too large".to_vec()],
+    }));
+    let metrics = metrics.metrics();
+
+    assert!(matches!(error, RollupsError::PayloadTooLarge { length: 5, .. }));
+    assert_eq!(expected_data, actual_data);
+    assert_eq!((2, 1), (metrics.advances, metrics.inspects));
+    assert_eq!((1, 0), (metrics.accepted, metrics.rejected));
+    assert_eq!((1, 1, 1), (metrics.notices, metrics.reports, metrics.exceptions));
+    assert!(metrics.slowest_advance.is_some());
+}
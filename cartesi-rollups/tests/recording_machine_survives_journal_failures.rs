@@ -0,0 +1,49 @@
+use cartesi_rollups::{FinishStatus, MachineIo, RecordingMachine, RollupsError, RollupsRequest};
+use cartesi_rollups_test::{Data, FakeCartesiMachine};
+use std::cell::RefCell;
+use std::io;
+use std::io::Write;
+use std::rc::Rc;
+
+/// Journal whose every write fails, like a full disk.
+struct FullDisk;
+
+impl Write for FullDisk {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        Err(io::Error::from(io::ErrorKind::StorageFull))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_recording_machine_survives_journal_failures() {
+    let requests = [RollupsRequest::InspectState {
+        payload: b"query".to_vec(),
+    }];
+
+    let actual_data = Rc::new(RefCell::new(Data::default()));
+    let machine = RecordingMachine::new(FakeCartesiMachine::new(requests, actual_data.clone()), FullDisk);
+
+    let request = machine.submit(FinishStatus::Accept).unwrap();
+    machine.write_report(b"report").unwrap();
+    let error = machine.submit(FinishStatus::Accept).unwrap_err();
+
+    let expected_data = Rc::new(RefCell::new(Data {
+        notices: vec![],
+        vouchers: vec![],
+        reports: vec![b"report".to_vec()],
+        exceptions: vec![],
+    }));
+
+    assert_eq!(
+        RollupsRequest::InspectState {
+            payload: b"query".to_vec()
+        },
+        request
+    );
+    assert!(matches!(error, RollupsError::QueueExhausted(_)));
+    assert_eq!(expected_data, actual_data);
+}
@@ -0,0 +1,53 @@
+use cartesi_rollups::{Address, MetadataVersion, Request, RollupsError, RollupsMetadata, RollupsRequest, Session};
+use cartesi_rollups_test::{Data, FakeCartesiMachine};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[test]
+fn test_rejected_advance_discards_notices() {
+    let requests = [1, 2].map(|input_index| RollupsRequest::AdvanceState {
+        metadata: RollupsMetadata {
+            msg_sender: Address::ZERO,
+            epoch_index: 0,
+            input_index,
+            block_number: 0,
+            timestamp: 0,
+            version: MetadataVersion::V1,
+        },
+        payload: vec![input_index as u8],
+    });
+
+    let actual_data = Rc::new(RefCell::new(Data::default()));
+    let machine = FakeCartesiMachine::new(requests, actual_data.clone());
+
+    let mut request = Session::new(machine).submit().unwrap();
+
+    let error = loop {
+        let next = match request {
+            Request::Advance(advance) => {
+                advance.write_notice(advance.payload()).unwrap();
+
+                match advance.metadata().input_index {
+                    1 => advance.accept(),
+                    _ => advance.reject(),
+                }
+            }
+            Request::Inspect(inspect) => inspect.accept(),
+        };
+
+        match next {
+            Ok(next) => request = next,
+            Err(error) => break error,
+        }
+    };
+
+    let expected_data = Rc::new(RefCell::new(Data {
+        notices: vec![vec![1]],
+        vouchers: vec![],
+        reports: vec![],
+        exceptions: vec![],
+    }));
+
+    assert!(matches!(error, RollupsError::QueueExhausted(_)));
+    assert_eq!(expected_data, actual_data);
+}
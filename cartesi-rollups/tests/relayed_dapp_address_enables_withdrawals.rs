@@ -0,0 +1,78 @@
+use cartesi_rollups::{
+    Address, Context, DApp, DAppAddressMachine, FinishStatus, MetadataVersion, RollupsError, RollupsMetadata,
+    RollupsRequest, U256,
+};
+use cartesi_rollups_evm_utils::portals::Portals;
+use cartesi_rollups_test::{Data, FakeCartesiMachine};
+use std::cell::RefCell;
+use std::error::Error;
+use std::rc::Rc;
+
+struct Faucet;
+
+impl DApp for Faucet {
+    fn advance(
+        &mut self,
+        ctx: &Context,
+        metadata: RollupsMetadata,
+        payload: Vec<u8>,
+    ) -> Result<FinishStatus, Box<dyn Error>> {
+        if metadata.msg_sender == Portals::DAPP_ADDRESS_RELAY {
+            Portals::default().parse_dapp_address(&metadata.msg_sender, &payload)?;
+            return Ok(FinishStatus::Accept);
+        }
+
+        let (destination, payload) = ctx.voucher_builder()?.ether_withdrawal(&metadata.msg_sender, U256::ONE);
+        ctx.write_voucher(&destination, &payload)?;
+
+        Ok(FinishStatus::Accept)
+    }
+
+    fn inspect(&self, _ctx: &Context, _payload: Vec<u8>) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_relayed_dapp_address_enables_withdrawals() {
+    let dapp = Address::new([0xda; 20]);
+    let user = Address::new([0x11; 20]);
+    let request = |msg_sender, payload: &[u8]| RollupsRequest::AdvanceState {
+        metadata: RollupsMetadata {
+            msg_sender,
+            epoch_index: 0,
+            input_index: 0,
+            block_number: 0,
+            timestamp: 0,
+            version: MetadataVersion::V1,
+        },
+        payload: payload.to_vec(),
+    };
+    let requests = [
+        request(user, b""),
+        request(Portals::DAPP_ADDRESS_RELAY, &dapp.as_bytes()[..19]),
+        request(user, b""),
+        request(Portals::DAPP_ADDRESS_RELAY, dapp.as_bytes()),
+        request(user, b""),
+    ];
+
+    let actual_data = Rc::new(RefCell::new(Data::default()));
+    let machine = DAppAddressMachine::new(FakeCartesiMachine::new(requests, actual_data.clone()));
+
+    let error = cartesi_rollups::run(Faucet, machine).unwrap_err();
+
+    let (destination, payload) = cartesi_rollups::VoucherBuilder::new(dapp).ether_withdrawal(&user, U256::ONE);
+    let expected_data = Rc::new(RefCell::new(Data {
+        notices: vec![],
+        vouchers: vec![(destination, payload)],
+        reports: vec![
+            b"DApp address is unknown until the DAppAddressRelay input arrives".to_vec(),
+            b"deposit payload ended after 19 bytes, expected at least 20".to_vec(),
+            b"DApp address is unknown until the DAppAddressRelay input arrives".to_vec(),
+        ],
+        exceptions: vec![],
+    }));
+
+    assert!(matches!(error, RollupsError::QueueExhausted(_)));
+    assert_eq!(expected_data, actual_data);
+}
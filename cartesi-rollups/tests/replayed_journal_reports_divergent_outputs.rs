@@ -0,0 +1,96 @@
+use cartesi_rollups::{
+    Address, Divergence, Event, FinishStatus, MachineIo, MetadataVersion, RecordingMachine, ReplayMachine,
+    RollupsError, RollupsMetadata, RollupsRequest,
+};
+use cartesi_rollups_test::{Data, FakeCartesiMachine};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Echoes every advance payload as a notice, followed by a notice of `suffix` if there is one.
+fn echo(machine: &impl MachineIo, suffix: Option<&[u8]>) -> RollupsError {
+    let mut status = FinishStatus::Accept;
+
+    loop {
+        let request = match machine.submit(status) {
+            Ok(request) => request,
+            Err(error) => return error,
+        };
+
+        status = match request {
+            RollupsRequest::AdvanceState { payload, .. } => {
+                machine.write_notice(&payload).unwrap();
+                if let Some(suffix) = suffix {
+                    machine.write_notice(suffix).unwrap();
+                }
+                FinishStatus::Accept
+            }
+            RollupsRequest::InspectState { payload } => {
+                machine.write_report(&payload).unwrap();
+                FinishStatus::Reject
+            }
+        };
+    }
+}
+
+#[test]
+fn test_replayed_journal_reports_divergent_outputs() {
+    let requests = vec![
+        RollupsRequest::AdvanceState {
+            metadata: RollupsMetadata {
+                msg_sender: Address::ZERO,
+                epoch_index: 0,
+                input_index: 1,
+                block_number: 0,
+                timestamp: 0,
+                version: MetadataVersion::V1,
+            },
+            payload: vec![1],
+        },
+        RollupsRequest::InspectState { payload: vec![2] },
+    ];
+    let machine = FakeCartesiMachine::new(requests, Rc::new(RefCell::new(Data::default())));
+    let machine = RecordingMachine::new(machine, Vec::new());
+
+    assert!(matches!(echo(&machine, None), RollupsError::QueueExhausted(_)));
+
+    let (_, journal) = machine.into_inner();
+
+    assert_eq!(
+        7,
+        journal
+            .split(|byte| *byte == b'\n')
+            .filter(|line| !line.is_empty())
+            .count()
+    );
+
+    let replay = ReplayMachine::new(journal.as_slice());
+
+    assert!(matches!(echo(&replay, None), RollupsError::QueueExhausted(_)));
+    assert_eq!(Vec::<Divergence>::new(), replay.into_divergences());
+
+    let replay = ReplayMachine::new(journal.as_slice());
+
+    assert!(matches!(echo(&replay, Some(b"new")), RollupsError::QueueExhausted(_)));
+    assert_eq!(
+        vec![
+            Divergence {
+                line: 4,
+                expected: Some(Event::Finish {
+                    status: FinishStatus::Accept
+                }),
+                actual: Some(Event::Notice {
+                    index: 1,
+                    payload: b"new".to_vec()
+                }),
+            },
+            Divergence {
+                line: 5,
+                expected: None,
+                actual: Some(Event::Finish {
+                    status: FinishStatus::Accept
+                }),
+            },
+        ],
+        replay.into_divergences()
+    );
+}
@@ -0,0 +1,118 @@
+use cartesi_rollups::{
+    Address, FinishStatus, MetadataVersion, RollupsError, RollupsMetadata, RollupsRequest, Router, U256,
+};
+use cartesi_rollups_evm_utils::abi::{decode_call, encode_call, selector, Token};
+use cartesi_rollups_test::{Data, FakeCartesiMachine};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+const DEPOSIT: &str = "deposit(address,uint256)";
+
+#[test]
+fn test_router_dispatches_by_selector_method_and_path() {
+    let owner = Address::new([0xa1; 20]);
+    let holder = Address::new([0xb2; 20]);
+    let payloads = [
+        encode_call(DEPOSIT, &[Token::Address(holder), Token::Uint(U256::from(5u8))]).unwrap(),
+        encode_call(DEPOSIT, &[Token::Address(owner), Token::Uint(U256::from(7u8))]).unwrap(),
+        br#"{"method":"burn"}"#.to_vec(),
+        br#"{"method":"mint"}"#.to_vec(),
+        br#"{"amount":1}"#.to_vec(),
+        encode_call("withdraw(uint256)", &[Token::Uint(U256::ONE)]).unwrap(),
+    ];
+    let mut requests: Vec<_> = payloads
+        .into_iter()
+        .zip(1..)
+        .map(|(payload, input_index)| RollupsRequest::AdvanceState {
+            metadata: RollupsMetadata {
+                msg_sender: owner,
+                epoch_index: 0,
+                input_index,
+                block_number: 0,
+                timestamp: 0,
+                version: MetadataVersion::V1,
+            },
+            payload,
+        })
+        .collect();
+    requests.extend(
+        [
+            "/balance/0xb2b2b2b2b2b2b2b2b2b2b2b2b2b2b2b2b2b2b2b2?unit=wei",
+            "supply",
+            "/allowance/0xa1",
+            "/greet/a+b%20c?greeting=hello+there%2B",
+        ]
+        .map(|path| RollupsRequest::InspectState {
+            payload: path.as_bytes().to_vec(),
+        }),
+    );
+
+    let router = Router::new(HashMap::<Address, U256>::new())
+        .selector(selector(DEPOSIT), |balances, _ctx, _metadata, payload| {
+            let [Token::Address(owner), Token::Uint(amount)] = decode_call(DEPOSIT, payload)?[..] else {
+                unreachable!();
+            };
+            let balance = balances.entry(owner).or_default();
+            *balance = balance.checked_add(amount).ok_or("balance overflows")?;
+
+            Ok(FinishStatus::Accept)
+        })
+        .method("burn", |balances, _ctx, metadata, _payload| {
+            balances.remove(&metadata.msg_sender);
+
+            Ok(FinishStatus::Accept)
+        })
+        .inspect("/balance/:owner", |balances, ctx, route| {
+            let owner = route.param("owner").unwrap().parse()?;
+            let balance = balances.get(&owner).copied().unwrap_or_default();
+
+            ctx.write_report(format!("{balance} {}", route.query("unit").unwrap()).as_bytes())?;
+
+            Ok(())
+        })
+        .inspect("/supply", |balances, ctx, _route| {
+            let supply = balances
+                .values()
+                .try_fold(U256::ZERO, |supply, balance| supply.checked_add(*balance))
+                .ok_or("supply overflows")?;
+
+            ctx.write_report(supply.to_string().as_bytes())?;
+
+            Ok(())
+        })
+        .inspect("/greet/:name", |_balances, ctx, route| {
+            let greeting = format!("{}, {}", route.query("greeting").unwrap(), route.param("name").unwrap());
+
+            ctx.write_report(greeting.as_bytes())?;
+
+            Ok(())
+        });
+
+    let actual_data = Rc::new(RefCell::new(Data::default()));
+    let machine = FakeCartesiMachine::new(requests, actual_data.clone());
+
+    let error = cartesi_rollups::run(router, machine).unwrap_err();
+
+    let expected_data = Rc::new(RefCell::new(Data {
+        notices: vec![],
+        vouchers: vec![],
+        reports: vec![
+            br#"unknown route: method "mint""#.to_vec(),
+            b"unknown route: payload of 12 bytes".to_vec(),
+            format!(
+                "unknown route: selector 0x{}",
+                hex::encode(selector("withdraw(uint256)"))
+            )
+            .into_bytes(),
+            b"5 wei".to_vec(),
+            b"5".to_vec(),
+            br#"unknown route: path "/allowance/0xa1""#.to_vec(),
+            b"hello there+, a+b c".to_vec(),
+        ],
+        exceptions: vec![],
+    }));
+
+    assert!(matches!(error, RollupsError::QueueExhausted(_)));
+    assert_eq!(expected_data, actual_data);
+}
@@ -0,0 +1,83 @@
+use cartesi_rollups::{
+    Address, FinishStatus, Json, MetadataVersion, RollupsError, RollupsMetadata, RollupsRequest, TypedMachineIo,
+    TypedRequest,
+};
+use cartesi_rollups_test::{Data, FakeCartesiMachine};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Deposit {
+    amount: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Query {
+    owner: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Balance {
+    total: u64,
+}
+
+#[test]
+fn test_typed_machine_rejects_undecodable_payloads() {
+    let requests = [r#"{"amount":2}"#, "not json", r#"{"amount":3}"#]
+        .into_iter()
+        .zip(1..)
+        .map(|(payload, input_index)| RollupsRequest::AdvanceState {
+            metadata: RollupsMetadata {
+                msg_sender: Address::ZERO,
+                epoch_index: 0,
+                input_index,
+                block_number: 0,
+                timestamp: 0,
+                version: MetadataVersion::V1,
+            },
+            payload: payload.as_bytes().to_vec(),
+        });
+    let mut requests: Vec<_> = requests.collect();
+    requests.push(RollupsRequest::InspectState {
+        payload: br#"{"owner":"alice"}"#.to_vec(),
+    });
+
+    let actual_data = Rc::new(RefCell::new(Data::default()));
+    let machine = TypedMachineIo::new(FakeCartesiMachine::new(requests, actual_data.clone()), Json);
+    let mut total = 0;
+
+    let error = loop {
+        let request = match machine.submit::<Deposit, Query>(FinishStatus::Accept) {
+            Ok(request) => request,
+            Err(error) => break error,
+        };
+
+        match request {
+            TypedRequest::AdvanceState { payload, .. } => {
+                total += payload.amount;
+                machine.write_notice(&Balance { total }).unwrap();
+            }
+            TypedRequest::InspectState { payload } => {
+                assert_eq!(
+                    Query {
+                        owner: "alice".to_owned()
+                    },
+                    payload
+                );
+                machine.write_report(&Balance { total }).unwrap();
+            }
+        }
+    };
+
+    let data = actual_data.borrow();
+
+    assert!(matches!(error, RollupsError::QueueExhausted(_)));
+    assert_eq!(
+        vec![br#"{"total":2}"#.to_vec(), br#"{"total":5}"#.to_vec()],
+        data.notices
+    );
+    assert_eq!(2, data.reports.len());
+    assert!(String::from_utf8_lossy(&data.reports[0]).starts_with("failed to decode payload"));
+    assert_eq!(br#"{"total":5}"#.to_vec(), data.reports[1]);
+}
@@ -1,25 +1,35 @@
-use cartesi_rollups_linux::{MachineIo, RollupsRequest};
+use cartesi_rollups_linux::{Context, DApp, FinishStatus, MachineIo, RollupsError, RollupsMetadata};
 use std::error::Error;
 
-pub fn run(machine: impl MachineIo) -> Result<(), Box<dyn Error>> {
-    loop {
-        let request = machine.submit()?;
-
-        match request {
-            RollupsRequest::AdvanceState { payload, .. } => {
-                machine.write_notice(payload.as_slice())?;
-            }
-            RollupsRequest::InspectState { payload } => {
-                machine.write_report(payload.as_slice())?;
-            }
-        }
+pub struct Echo;
+
+impl DApp for Echo {
+    fn advance(
+        &mut self,
+        ctx: &Context,
+        _metadata: RollupsMetadata,
+        payload: Vec<u8>,
+    ) -> Result<FinishStatus, Box<dyn Error>> {
+        ctx.write_notice(payload.as_slice())?;
+
+        Ok(FinishStatus::Accept)
     }
+
+    fn inspect(&self, ctx: &Context, payload: Vec<u8>) -> Result<(), Box<dyn Error>> {
+        ctx.write_report(payload.as_slice())?;
+
+        Ok(())
+    }
+}
+
+pub fn run(machine: impl MachineIo) -> Result<(), RollupsError> {
+    cartesi_rollups_linux::run(Echo, machine)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use cartesi_rollups_linux::RollupsMetadata;
+    use cartesi_rollups_linux::{Address, MetadataVersion, RollupsRequest};
     use cartesi_rollups_test::{Data, FakeCartesiMachine};
     use std::cell::RefCell;
     use std::rc::Rc;
@@ -28,11 +38,12 @@ mod tests {
     fn test_advancing_state_echoes_notice() {
         let request = RollupsRequest::AdvanceState {
             metadata: RollupsMetadata {
-                msg_sender: "test".to_owned(),
+                msg_sender: Address::ZERO,
                 epoch_index: 0,
                 input_index: 0,
                 block_number: 0,
                 timestamp: 0,
+                version: MetadataVersion::V1,
             },
             payload: vec![1, 2, 3],
         };
@@ -1,9 +1,9 @@
-use cartesi_rollups_linux::LinuxMachine;
+use cartesi_rollups_linux::{LinuxMachine, LoggingLayer, MachineIoExt};
 
 fn main() {
     env_logger::init();
 
-    let machine = LinuxMachine::open_default_device().unwrap();
+    let machine = LinuxMachine::open_default_device().unwrap().layer(LoggingLayer);
 
     echo::run(machine).unwrap();
 }